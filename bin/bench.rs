@@ -53,7 +53,7 @@ fn push_to_ast(c: &mut Criterion) {
         let input = lib::gen_input(size);
         group.throughput(Throughput::BytesDecimal(input.len() as u64));
         group.bench_with_input(BenchmarkId::new("size", size), &input, |b, input| {
-            b.iter(|| lib::parse_events_push(input, &mut lib::AstBuilderListener::new(input)));
+            b.iter(|| lib::parse_events_push(input, lib::AstBuilderListener::new(input)));
         });
     }
 }
@@ -94,11 +94,7 @@ fn parse_events_push(c: &mut Criterion) {
         let input = lib::gen_input(size);
         group.throughput(Throughput::BytesDecimal(input.len() as u64));
         group.bench_with_input(BenchmarkId::new("size", size), &input, |b, input| {
-            b.iter(|| {
-                let mut push_to_events = lib::PushToEvents::new();
-                lib::parse_events_push(input, &mut push_to_events);
-                let (_events, _error) = push_to_events.into_events();
-            });
+            b.iter(|| lib::parse_events_push(input, lib::PushToEvents::new()));
         });
     }
 }
@@ -0,0 +1,267 @@
+use crate::event_to_tree::TreeBuilder;
+use crate::reader_parser::ReaderState;
+use crate::{Json, ParseError, ParseEvent, ParseEventKind, ParseOptions};
+
+use futures_core::{ready, Stream};
+use futures_io::AsyncRead;
+
+use std::borrow::Cow;
+use std::future::poll_fn;
+use std::io;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Parses input read from `reader` to a [Stream] of [ParseEvent]s.
+pub fn parse_events<R: AsyncRead + Unpin>(reader: R) -> AsyncReaderParser<R> {
+    AsyncReaderParser::new(reader, ParseOptions::default())
+}
+
+/// Parses input read from `reader` to a [Stream] of [ParseEvent]s, with the given options.
+pub fn parse_events_with_options<R: AsyncRead + Unpin>(
+    reader: R,
+    options: ParseOptions,
+) -> AsyncReaderParser<R> {
+    AsyncReaderParser::new(reader, options)
+}
+
+/// Parses a [Stream] of [ParseEvent]s from `parser` to [Json], as in
+/// [event_to_tree][crate::event_to_tree].
+pub async fn event_to_tree<R: AsyncRead + Unpin>(
+    parser: &mut AsyncReaderParser<R>,
+) -> Result<Json, ParseError> {
+    let mut builder = TreeBuilder::new();
+    loop {
+        // The parser reports an error before the end of the stream when the input doesn't have a
+        // value.
+        let event = poll_fn(|cx| Pin::new(&mut *parser).poll_next(cx)).await;
+        let event = event.ok_or_else(|| builder.end_of_input(parser.state.byte_offset()))??;
+        let value = builder.add_event(event, |event| match event.kind {
+            ParseEventKind::Str { .. } => parser.str().into_owned(),
+            _ => parser.text().to_string(),
+        });
+        if let Some(value) = value {
+            return Ok(value);
+        }
+    }
+}
+
+/// A [Stream] of [ParseEvent]s parsed from a [futures_io::AsyncRead], the async version of
+/// [ReaderParser][crate::ReaderParser].
+///
+/// The events, byte offsets and errors are the same as [ReaderParser][crate::ReaderParser]'s, and
+/// the text of the last string, number or comment event is available from
+/// [AsyncReaderParser::str] and [AsyncReaderParser::text].
+///
+/// A read that doesn't complete a token, e.g. in a long string, yields to the executor before
+/// the next read, so parsing a large input from a reader that is always ready doesn't block other
+/// tasks.
+pub struct AsyncReaderParser<R: AsyncRead + Unpin> {
+    reader: R,
+    state: ReaderState,
+}
+
+impl<R: AsyncRead + Unpin> AsyncReaderParser<R> {
+    fn new(reader: R, options: ParseOptions) -> AsyncReaderParser<R> {
+        AsyncReaderParser {
+            reader,
+            state: ReaderState::new(options),
+        }
+    }
+
+    /// The value of the last [ParseEventKind::Str] event, decoded. The string is borrowed from
+    /// the parser when it doesn't have escape sequences.
+    pub fn str(&self) -> Cow<'_, str> {
+        self.state.str()
+    }
+
+    /// The input text of the last [ParseEventKind::Str], [ParseEventKind::Number] or
+    /// [ParseEventKind::Comment] event. Empty after other events.
+    pub fn text(&self) -> &str {
+        self.state.text()
+    }
+
+    /// The error of the last [ParseErrorKind::Io][crate::ParseErrorKind::Io] error.
+    pub fn take_io_error(&mut self) -> Option<io::Error> {
+        self.state.take_io_error()
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncReaderParser<R> {
+    type Item = Result<ParseEvent, ParseError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(event) = this.state.next_event() {
+            return Poll::Ready(Some(event));
+        }
+        if this.state.is_done() {
+            return Poll::Ready(None);
+        }
+
+        let result = ready!(Pin::new(&mut this.reader).poll_read(cx, &mut this.state.chunk));
+        this.state.parse_chunk(result);
+
+        match this.state.next_event() {
+            Some(event) => Poll::Ready(Some(event)),
+            None if this.state.is_done() => Poll::Ready(None),
+            None => {
+                // Yield before reading the next chunk.
+                cx.waker().wake_by_ref();
+                Poll::Pending
+            }
+        }
+    }
+}
+
+/// A reader that returns one byte at a time. When `pending` is set, returns `Pending` before each
+/// byte.
+#[cfg(test)]
+struct ByteReader<'a> {
+    input: &'a [u8],
+    pending: bool,
+    ready: bool,
+}
+
+#[cfg(test)]
+impl<'a> AsyncRead for ByteReader<'a> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        if self.pending && !self.ready {
+            self.ready = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+        self.ready = false;
+        match self.input.split_first() {
+            Some((byte, rest)) => {
+                buf[0] = *byte;
+                self.input = rest;
+                Poll::Ready(Ok(1))
+            }
+            None => Poll::Ready(Ok(0)),
+        }
+    }
+}
+
+#[cfg(test)]
+fn collect_events<R: AsyncRead + Unpin>(
+    mut parser: AsyncReaderParser<R>,
+) -> (Vec<ParseEvent>, Option<ParseError>) {
+    futures_executor::block_on(async {
+        let mut events = vec![];
+        while let Some(event) = poll_fn(|cx| Pin::new(&mut parser).poll_next(cx)).await {
+            match event {
+                Ok(event) => events.push(event),
+                Err(error) => return (events, Some(error)),
+            }
+        }
+        (events, None)
+    })
+}
+
+#[test]
+fn event_parser_eq_tests() {
+    use crate::test_common::*;
+
+    let mut tests: Vec<(String, ParseOptions)> = vec![];
+    for (str, _) in event_tests() {
+        tests.push((str, ParseOptions::default()));
+    }
+    for (str, _) in unexpected_token_tests() {
+        tests.push((str, ParseOptions::default()));
+    }
+    for (str, _) in json5_event_tests() {
+        tests.push((str, json5_options()));
+    }
+    for (str, _) in comment_option_event_tests() {
+        tests.push((str, comment_options()));
+    }
+
+    for (str, options) in tests {
+        println!("Parsing {:?}", str);
+        let expected = crate::reader_parser::collect_events(
+            crate::parse_events_reader_with_options(str.as_bytes(), options),
+        );
+        assert_eq!(
+            collect_events(parse_events_with_options(str.as_bytes(), options)),
+            expected
+        );
+        let reader = ByteReader {
+            input: str.as_bytes(),
+            pending: true,
+            ready: false,
+        };
+        assert_eq!(
+            collect_events(parse_events_with_options(reader, options)),
+            expected
+        );
+    }
+}
+
+#[test]
+fn event_to_tree_tests() {
+    for (str, ast) in crate::test_common::ast_tests() {
+        println!("Parsing {:?}", str);
+        let reader = ByteReader {
+            input: str.as_bytes(),
+            pending: true,
+            ready: false,
+        };
+        let mut parser = parse_events(reader);
+        let ast_ = futures_executor::block_on(event_to_tree(&mut parser)).unwrap();
+        assert_eq!(ast_, ast);
+    }
+
+    let mut parser = parse_events(&b"[1, 2"[..]);
+    let error = futures_executor::block_on(event_to_tree(&mut parser)).unwrap_err();
+    assert_eq!(error.byte_offset, 5);
+
+    // After the end of the stream.
+    let mut parser = parse_events(&b"1"[..]);
+    assert_eq!(
+        futures_executor::block_on(event_to_tree(&mut parser)),
+        Ok(Json::Int(1))
+    );
+    let error = futures_executor::block_on(event_to_tree(&mut parser)).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "unexpected end of input, expected value at byte offset 1"
+    );
+}
+
+#[test]
+fn yield_test() {
+    // The parser returns `Pending` after each read of a long string, instead of reading the next
+    // chunk.
+    let input = format!("[\"{}\"]", "a".repeat(100));
+    let mut parser = parse_events(ByteReader {
+        input: input.as_bytes(),
+        pending: false,
+        ready: false,
+    });
+    let mut cx = Context::from_waker(std::task::Waker::noop());
+    let mut num_pending = 0;
+    let mut events = vec![];
+    loop {
+        match Pin::new(&mut parser).poll_next(&mut cx) {
+            Poll::Ready(Some(event)) => events.push(event.unwrap().kind),
+            Poll::Ready(None) => break,
+            Poll::Pending => num_pending += 1,
+        }
+    }
+    assert_eq!(
+        events,
+        [
+            ParseEventKind::StartArray,
+            ParseEventKind::Str {
+                size_in_bytes: 100,
+                has_escapes: false
+            },
+            ParseEventKind::EndArray
+        ]
+    );
+    assert!(num_pending > 100);
+}
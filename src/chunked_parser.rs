@@ -0,0 +1,997 @@
+use crate::comment::{is_comment_start, scan_comment};
+use crate::error::{
+    Token, COLON, COMMA_OR_END_ARRAY, COMMA_OR_END_OBJECT, END_OF_INPUT, KEY, KEY_OR_END_OBJECT,
+    VALUE, VALUE_OR_END_ARRAY,
+};
+use crate::escape::scan_string;
+use crate::event_parser::Container;
+use crate::identifier::{is_identifier_start, scan_identifier};
+use crate::number::{is_number_start, scan_number, Number};
+use crate::skip::{skip_error, skip_token, Skipped};
+use crate::whitespace::{check_whitespace, is_whitespace};
+use crate::{
+    CommentKind, ControlFlow, Dialect, EventListener, FinishListener, ParseError, ParseErrorKind,
+    ParseOptions,
+};
+
+use std::borrow::Cow;
+use std::iter::Peekable;
+
+/// A "push" event parser that is fed the input in chunks of bytes, e.g. as they are read from a
+/// socket or a pipe.
+///
+/// [EventListener] callbacks are called as soon as the tokens are complete, with the same events
+/// and byte offsets as [parse_events_push][crate::parse_events_push] for the whole input.
+/// Incomplete tokens at the end of a chunk are kept until the next chunk. As the listener doesn't
+/// have the input, the text of strings, numbers and comments is passed to
+/// [EventListener::handle_text].
+///
+/// Parsing stops after the first error, or when a callback returns [ControlFlow::Stop]. An error in a string
+/// or comment that spans many chunks may be reported with a later chunk.
+/// [ParseOptions::recover_errors] is not supported.
+pub struct ChunkedParser {
+    options: ParseOptions,
+
+    /// The input that is not parsed yet, starting with an incomplete token.
+    buffer: String,
+
+    /// Byte offset of `buffer` in the input.
+    buffer_byte_offset: usize,
+
+    /// Bytes of a UTF-8 sequence split between the last chunk and the next one.
+    incomplete_char: Vec<u8>,
+
+    /// Containers the parser is in, with byte offsets of the opening brackets.
+    container_stack: Vec<(Container, usize)>,
+
+    /// When the buffer starts with an incomplete string or comment, the character that can end
+    /// it, and the buffer length at which to parse it again without that character. Avoids
+    /// scanning a long token again for each chunk.
+    wait_for: Option<(char, usize)>,
+
+    state: ParserState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ParserState {
+    /// Expect the top-level value.
+    TopLevel,
+
+    /// Finished parsing the top-level value, expect end of input.
+    Done,
+
+    /// Just parsed a '[', parse the first element, or finish the array on ']'.
+    ArrayExpectFirstValue,
+
+    /// Parsing an array, parse another element on ',', or finish the array on ']'.
+    ArrayExpectComma,
+
+    /// Parsing an array and we've just parsed a ',', parse the next element. In JSON5, finish the
+    /// array on ']'.
+    ArrayExpectValue,
+
+    /// Just parsed a '{', parse the first key, or finish the object on '}'.
+    ObjectExpectFirstKey,
+
+    /// Parsing an object, parse another member on ',', or finish the object on '}'.
+    ObjectExpectComma,
+
+    /// Parsing an object and we've just parsed a ',', parse the next key. In JSON5, finish the
+    /// object on '}'.
+    ObjectExpectKey,
+
+    /// Parsing an object and we've just parsed a key, expect ':'.
+    ObjectExpectColon,
+
+    /// Parsing an object and we've just parsed a ':', parse the value.
+    ObjectExpectValue,
+
+    /// Skipping an array or object after a callback returned [ControlFlow::SkipValue], until
+    /// the container stack has the given number of containers.
+    Skip(usize),
+
+    /// After an error, or a callback returning [ControlFlow::Stop].
+    Stopped,
+}
+
+/// Result of parsing a token.
+enum Step {
+    /// The token is parsed, parse the next one.
+    Continue,
+
+    /// The token may continue in the next chunk.
+    NeedInput,
+
+    /// Stop parsing.
+    Stop,
+}
+
+impl ChunkedParser {
+    pub fn new(options: ParseOptions) -> ChunkedParser {
+        ChunkedParser {
+            options,
+            buffer: String::new(),
+            buffer_byte_offset: 0,
+            incomplete_char: vec![],
+            container_stack: vec![],
+            wait_for: None,
+            state: ParserState::TopLevel,
+        }
+    }
+
+    /// Parses the next chunk of the input.
+    pub fn feed<L: EventListener>(&mut self, chunk: &[u8], listener: &mut L) {
+        if self.state == ParserState::Stopped {
+            return;
+        }
+
+        let chunk: Cow<[u8]> = if self.incomplete_char.is_empty() {
+            Cow::Borrowed(chunk)
+        } else {
+            let mut bytes = std::mem::take(&mut self.incomplete_char);
+            bytes.extend_from_slice(chunk);
+            Cow::Owned(bytes)
+        };
+
+        match std::str::from_utf8(&chunk) {
+            Ok(str) => self.feed_str(str, listener),
+            Err(error) => {
+                let (valid, rest) = chunk.split_at(error.valid_up_to());
+                self.feed_str(std::str::from_utf8(valid).unwrap(), listener);
+                match error.error_len() {
+                    None => self.incomplete_char = rest.to_vec(),
+                    Some(_) => self.invalid_utf8(listener),
+                }
+            }
+        }
+    }
+
+    /// Parses the rest of the input after the last chunk, and returns the result of
+    /// [FinishListener::finish].
+    pub fn finish<L: FinishListener>(self, mut listener: L) -> Result<L::Output, L::Error> {
+        self.finish_events(&mut listener);
+        listener.finish()
+    }
+
+    /// Like [ChunkedParser::finish], but doesn't finish the listener.
+    pub(crate) fn finish_events<L: EventListener>(mut self, listener: &mut L) {
+        if self.state == ParserState::Stopped {
+            return;
+        }
+        if !self.incomplete_char.is_empty() {
+            self.invalid_utf8(listener);
+            return;
+        }
+        self.parse(listener, true);
+    }
+
+    /// Reports an invalid UTF-8 sequence after the buffer, unless the buffer has an error.
+    fn invalid_utf8<L: EventListener>(&mut self, listener: &mut L) {
+        if self.state == ParserState::Stopped {
+            return;
+        }
+        listener.handle_error(ParseError::new(
+            self.buffer_byte_offset + self.buffer.len(),
+            ParseErrorKind::InvalidUtf8,
+        ));
+        self.state = ParserState::Stopped;
+    }
+
+    /// Appends `str` to the buffer and parses it, unless `str` can't complete the token at the
+    /// start of the buffer.
+    fn feed_str<L: EventListener>(&mut self, str: &str, listener: &mut L) {
+        self.buffer.push_str(str);
+        if let Some((char, parse_len)) = self.wait_for {
+            if !str.contains(char) && self.buffer.len() < parse_len {
+                return;
+            }
+        }
+        self.parse(listener, false);
+    }
+
+    /// Parses the complete tokens in the buffer. When `end_of_input` is `true` the tokens at the
+    /// end of the buffer are complete.
+    fn parse<L: EventListener>(&mut self, listener: &mut L, end_of_input: bool) {
+        let buffer = std::mem::take(&mut self.buffer);
+        let mut byte_offset = 0;
+        let mut step = Step::Continue;
+        while let Step::Continue = step {
+            step = self.step(&buffer, &mut byte_offset, end_of_input, listener);
+        }
+        self.buffer = buffer;
+        self.buffer.drain(..byte_offset);
+        self.buffer_byte_offset += byte_offset;
+
+        // Parsing the buffer again takes time linear in the buffer length, so wait until the
+        // buffer doubles when the end of the token is not fed.
+        self.wait_for = match step {
+            Step::NeedInput => token_end(&self.buffer).map(|char| (char, self.buffer.len() * 2)),
+            _ => None,
+        };
+    }
+
+    /// Parses the token at `byte_offset` in `buffer`, with the trivia before it. Advances
+    /// `byte_offset` past the parsed trivia and token.
+    fn step<L: EventListener>(
+        &mut self,
+        buffer: &str,
+        byte_offset: &mut usize,
+        end_of_input: bool,
+        listener: &mut L,
+    ) -> Step {
+        match self.state {
+            ParserState::Stopped => return Step::Stop,
+            ParserState::Skip(depth) => {
+                return self.skip(buffer, byte_offset, end_of_input, listener, depth)
+            }
+            _ => {}
+        }
+
+        if let Some(step) = self.skip_trivia(buffer, byte_offset, end_of_input, listener) {
+            return step;
+        }
+
+        let mut iter = chars(buffer, *byte_offset);
+        let container_byte_offset = self.container_stack.last().map(|(_, offset)| *offset);
+        let json5 = self.options.dialect == Dialect::Json5;
+        let next = iter.peek().copied();
+        let next_byte_offset = next.map_or(buffer.len(), |(offset, _)| offset);
+
+        let unexpected = |expected| {
+            ParseError::unexpected(buffer, next_byte_offset, expected, container_byte_offset)
+        };
+
+        match self.state {
+            ParserState::TopLevel => self.value(
+                &mut iter,
+                buffer,
+                byte_offset,
+                end_of_input,
+                listener,
+                VALUE,
+            ),
+
+            ParserState::Done => match next {
+                None if end_of_input => Step::Stop,
+                None => Step::NeedInput,
+                Some(_) => self.fail(&mut iter, end_of_input, listener, unexpected(END_OF_INPUT)),
+            },
+
+            ParserState::ArrayExpectFirstValue => match next {
+                Some((_, ']')) => self.end_container(next_byte_offset, byte_offset, listener),
+                Some((_, ',')) | None => self.fail(
+                    &mut iter,
+                    end_of_input,
+                    listener,
+                    unexpected(VALUE_OR_END_ARRAY),
+                ),
+                Some(_) => self.value(
+                    &mut iter,
+                    buffer,
+                    byte_offset,
+                    end_of_input,
+                    listener,
+                    VALUE_OR_END_ARRAY,
+                ),
+            },
+
+            ParserState::ArrayExpectComma => match next {
+                Some((_, ',')) => {
+                    *byte_offset += 1;
+                    self.state = ParserState::ArrayExpectValue;
+                    Step::Continue
+                }
+                Some((_, ']')) => self.end_container(next_byte_offset, byte_offset, listener),
+                _ => self.fail(
+                    &mut iter,
+                    end_of_input,
+                    listener,
+                    unexpected(COMMA_OR_END_ARRAY),
+                ),
+            },
+
+            ParserState::ArrayExpectValue => match next {
+                // Trailing comma.
+                Some((_, ']')) if json5 => {
+                    self.end_container(next_byte_offset, byte_offset, listener)
+                }
+                _ => {
+                    let expected = if json5 { VALUE_OR_END_ARRAY } else { VALUE };
+                    self.value(
+                        &mut iter,
+                        buffer,
+                        byte_offset,
+                        end_of_input,
+                        listener,
+                        expected,
+                    )
+                }
+            },
+
+            ParserState::ObjectExpectFirstKey => match next {
+                Some((_, '}')) => self.end_container(next_byte_offset, byte_offset, listener),
+                Some((_, ',')) | None => self.fail(
+                    &mut iter,
+                    end_of_input,
+                    listener,
+                    unexpected(KEY_OR_END_OBJECT),
+                ),
+                Some(_) => self.key(
+                    &mut iter,
+                    buffer,
+                    byte_offset,
+                    end_of_input,
+                    listener,
+                    KEY_OR_END_OBJECT,
+                ),
+            },
+
+            ParserState::ObjectExpectComma => match next {
+                Some((_, ',')) => {
+                    *byte_offset += 1;
+                    self.state = ParserState::ObjectExpectKey;
+                    Step::Continue
+                }
+                Some((_, '}')) => self.end_container(next_byte_offset, byte_offset, listener),
+                _ => self.fail(
+                    &mut iter,
+                    end_of_input,
+                    listener,
+                    unexpected(COMMA_OR_END_OBJECT),
+                ),
+            },
+
+            ParserState::ObjectExpectKey => match next {
+                // Trailing comma.
+                Some((_, '}')) if json5 => {
+                    self.end_container(next_byte_offset, byte_offset, listener)
+                }
+                _ => {
+                    let expected = if json5 { KEY_OR_END_OBJECT } else { KEY };
+                    self.key(
+                        &mut iter,
+                        buffer,
+                        byte_offset,
+                        end_of_input,
+                        listener,
+                        expected,
+                    )
+                }
+            },
+
+            ParserState::ObjectExpectColon => match next {
+                Some((_, ':')) => {
+                    *byte_offset += 1;
+                    self.state = ParserState::ObjectExpectValue;
+                    Step::Continue
+                }
+                _ => self.fail(&mut iter, end_of_input, listener, unexpected(COLON)),
+            },
+
+            ParserState::ObjectExpectValue => self.value(
+                &mut iter,
+                buffer,
+                byte_offset,
+                end_of_input,
+                listener,
+                VALUE,
+            ),
+
+            // Handled above.
+            ParserState::Skip(_) | ParserState::Stopped => unreachable!(),
+        }
+    }
+
+    /// Parses a value. `expected` are the tokens valid at the value's location, for errors.
+    fn value<L: EventListener, I: Iterator<Item = (usize, char)>>(
+        &mut self,
+        iter: &mut Peekable<I>,
+        buffer: &str,
+        byte_offset: &mut usize,
+        end_of_input: bool,
+        listener: &mut L,
+        expected: &'static [Token],
+    ) -> Step {
+        let container_byte_offset = self.container_stack.last().map(|(_, offset)| *offset);
+        let (start, char) = match iter.next() {
+            Some(next) => next,
+            None => {
+                let error =
+                    ParseError::unexpected(buffer, buffer.len(), expected, container_byte_offset);
+                return self.fail(iter, end_of_input, listener, error);
+            }
+        };
+        let event_byte_offset = self.buffer_byte_offset + start;
+
+        match char {
+            '[' | '{' => {
+                *byte_offset = start + 1;
+                if char == '[' {
+                    self.container_stack
+                        .push((Container::Array, event_byte_offset));
+                    self.state = ParserState::ArrayExpectFirstValue;
+                    let control = listener.handle_start_array(event_byte_offset);
+                    self.emit_start(control)
+                } else {
+                    self.container_stack
+                        .push((Container::Object, event_byte_offset));
+                    self.state = ParserState::ObjectExpectFirstKey;
+                    let control = listener.handle_start_object(event_byte_offset);
+                    self.emit_start(control)
+                }
+            }
+
+            't' | 'f' | 'n' => {
+                let keyword = match char {
+                    't' => "true",
+                    'f' => "false",
+                    _ => "null",
+                };
+                for expected in keyword.chars().skip(1) {
+                    if !matches!(iter.next(), Some((_, char)) if char == expected) {
+                        let error = ParseError::new(start, ParseErrorKind::InvalidKeyword);
+                        return self.fail(iter, end_of_input, listener, error);
+                    }
+                }
+                *byte_offset = start + keyword.len();
+                self.state = self.state_after_value();
+                self.emit(match char {
+                    'n' => listener.handle_null(event_byte_offset),
+                    _ => listener.handle_bool(event_byte_offset, char == 't'),
+                })
+            }
+
+            _ if is_number_start(char, &self.options) => {
+                match scan_number(iter, buffer, start, char, &self.options) {
+                    // More digits may follow in the next chunk.
+                    Ok(_) if !end_of_input && iter.peek().is_none() => Step::NeedInput,
+                    Ok((end, number)) => {
+                        *byte_offset = end;
+                        self.state = self.state_after_value();
+                        let size_in_bytes = end - start;
+                        self.emit(match number {
+                            Number::Int(i) => {
+                                listener.handle_int(event_byte_offset, size_in_bytes, i)
+                            }
+                            Number::NegInt(i) => {
+                                listener.handle_neg_int(event_byte_offset, size_in_bytes, i)
+                            }
+                            Number::Float(f) => {
+                                listener.handle_float(event_byte_offset, size_in_bytes, f)
+                            }
+                            Number::Raw { .. } => {
+                                with_text(listener, &buffer[start..end], |listener| {
+                                    listener.handle_number(event_byte_offset, size_in_bytes)
+                                })
+                            }
+                        })
+                    }
+                    Err(error) => self.fail(iter, end_of_input, listener, error),
+                }
+            }
+
+            '"' => self.string(iter, buffer, byte_offset, end_of_input, listener, start),
+            '\'' if self.options.dialect == Dialect::Json5 => {
+                self.string(iter, buffer, byte_offset, end_of_input, listener, start)
+            }
+
+            _ => {
+                let error = ParseError::unexpected(buffer, start, expected, container_byte_offset);
+                self.fail(iter, end_of_input, listener, error)
+            }
+        }
+    }
+
+    /// Parses an object key. `expected` are the tokens valid at the key's location, for errors.
+    fn key<L: EventListener, I: Iterator<Item = (usize, char)>>(
+        &mut self,
+        iter: &mut Peekable<I>,
+        buffer: &str,
+        byte_offset: &mut usize,
+        end_of_input: bool,
+        listener: &mut L,
+        expected: &'static [Token],
+    ) -> Step {
+        let container_byte_offset = self.container_stack.last().map(|(_, offset)| *offset);
+        let json5 = self.options.dialect == Dialect::Json5;
+        match iter.next() {
+            Some((start, '"')) => {
+                self.string(iter, buffer, byte_offset, end_of_input, listener, start)
+            }
+            Some((start, '\'')) if json5 => {
+                self.string(iter, buffer, byte_offset, end_of_input, listener, start)
+            }
+            Some((start, char)) if json5 && is_identifier_start(char) => {
+                let end = scan_identifier(iter, buffer.len());
+                if !end_of_input && iter.peek().is_none() {
+                    return Step::NeedInput;
+                }
+                *byte_offset = end;
+                self.state = ParserState::ObjectExpectColon;
+                let text = &buffer[start..end];
+                let event_byte_offset = self.buffer_byte_offset + start;
+                self.emit(with_text(listener, text, |listener| {
+                    listener.handle_str(event_byte_offset, text.len(), false, false)
+                }))
+            }
+            next => {
+                let error_byte_offset = next.map_or(buffer.len(), |(offset, _)| offset);
+                let error = ParseError::unexpected(
+                    buffer,
+                    error_byte_offset,
+                    expected,
+                    container_byte_offset,
+                );
+                self.fail(iter, end_of_input, listener, error)
+            }
+        }
+    }
+
+    /// Parses a string after the opening quote at `start`, as a value, or in the
+    /// [ParserState::ObjectExpectFirstKey] and [ParserState::ObjectExpectKey] states, as a key.
+    fn string<L: EventListener, I: Iterator<Item = (usize, char)>>(
+        &mut self,
+        iter: &mut Peekable<I>,
+        buffer: &str,
+        byte_offset: &mut usize,
+        end_of_input: bool,
+        listener: &mut L,
+        start: usize,
+    ) -> Step {
+        let quote = char::from(buffer.as_bytes()[start]);
+        match scan_string(iter, buffer.len(), quote, &self.options) {
+            Ok((closing_quote, has_escapes)) => {
+                *byte_offset = closing_quote + 1;
+                self.state = match self.state {
+                    ParserState::ObjectExpectFirstKey | ParserState::ObjectExpectKey => {
+                        ParserState::ObjectExpectColon
+                    }
+                    _ => self.state_after_value(),
+                };
+                let text = &buffer[start + 1..closing_quote];
+                let event_byte_offset = self.buffer_byte_offset + start + 1;
+                self.emit(with_text(listener, text, |listener| {
+                    listener.handle_str(event_byte_offset, text.len(), has_escapes, true)
+                }))
+            }
+            Err(error) => self.fail(iter, end_of_input, listener, error),
+        }
+    }
+
+    /// Finishes the innermost array or object with the closing bracket at `end`.
+    fn end_container<L: EventListener>(
+        &mut self,
+        end: usize,
+        byte_offset: &mut usize,
+        listener: &mut L,
+    ) -> Step {
+        *byte_offset = end + 1;
+        let event_byte_offset = self.buffer_byte_offset + end;
+        let (container, _) = self.container_stack.pop().unwrap();
+        self.state = self.state_after_value();
+        self.emit(match container {
+            Container::Array => listener.handle_end_array(event_byte_offset),
+            Container::Object => listener.handle_end_object(event_byte_offset),
+        })
+    }
+
+    /// Skips whitespace and comments, advancing `byte_offset` past each of them. Returns `None`
+    /// when the next token should be parsed.
+    fn skip_trivia<L: EventListener>(
+        &mut self,
+        buffer: &str,
+        byte_offset: &mut usize,
+        end_of_input: bool,
+        listener: &mut L,
+    ) -> Option<Step> {
+        loop {
+            let mut iter = chars(buffer, *byte_offset);
+            let (start, char) = iter.next()?;
+
+            if is_whitespace(char) {
+                if let Err(error) = check_whitespace(start, char, &self.options) {
+                    return Some(self.fail(&mut iter, end_of_input, listener, error));
+                }
+                *byte_offset += char.len_utf8();
+                continue;
+            }
+
+            if !is_comment_start(char, &self.options) {
+                return None;
+            }
+
+            match scan_comment(&mut iter, buffer.len(), start, char, &self.options) {
+                Ok((size_in_bytes, kind)) => {
+                    let text = &buffer[start..start + size_in_bytes];
+                    // The rest of the line may be in the next chunk.
+                    if kind != CommentKind::Block && !end_of_input && !text.ends_with('\n') {
+                        return Some(Step::NeedInput);
+                    }
+                    *byte_offset = start + size_in_bytes;
+                    let event_byte_offset = self.buffer_byte_offset + start;
+                    let control = with_text(listener, text, |listener| {
+                        listener.handle_comment(event_byte_offset, size_in_bytes, kind)
+                    });
+                    if let Step::Stop = self.emit(control) {
+                        return Some(Step::Stop);
+                    }
+                }
+                Err(error) => return Some(self.fail(&mut iter, end_of_input, listener, error)),
+            }
+        }
+    }
+
+    /// State after parsing a value in the current container.
+    fn state_after_value(&self) -> ParserState {
+        match self.container_stack.last() {
+            None => ParserState::Done,
+            Some((Container::Array, _)) => ParserState::ArrayExpectComma,
+            Some((Container::Object, _)) => ParserState::ObjectExpectComma,
+        }
+    }
+
+    /// Reports `error`, with a byte offset in the buffer. When `iter` is at the end of the buffer,
+    /// the error may be caused by a token that continues in the next chunk, and the token is parsed
+    /// again with the next chunk.
+    fn fail<L: EventListener, I: Iterator<Item = (usize, char)>>(
+        &mut self,
+        iter: &mut Peekable<I>,
+        end_of_input: bool,
+        listener: &mut L,
+        mut error: ParseError,
+    ) -> Step {
+        if !end_of_input && iter.peek().is_none() {
+            return Step::NeedInput;
+        }
+        error.byte_offset += self.buffer_byte_offset;
+        listener.handle_error(error);
+        self.state = ParserState::Stopped;
+        Step::Stop
+    }
+
+    /// Stops parsing if a callback returned [ControlFlow::Stop].
+    fn emit(&mut self, control: ControlFlow) -> Step {
+        match control {
+            ControlFlow::Continue | ControlFlow::SkipValue => Step::Continue,
+            ControlFlow::Stop => {
+                self.state = ParserState::Stopped;
+                Step::Stop
+            }
+        }
+    }
+
+    /// Handles the result of a start array or object callback, after pushing the container.
+    fn emit_start(&mut self, control: ControlFlow) -> Step {
+        if control == ControlFlow::SkipValue {
+            self.state = ParserState::Skip(self.container_stack.len() - 1);
+        }
+        self.emit(control)
+    }
+
+    /// Skips a token or character of an array or object in the [ParserState::Skip] state, without
+    /// calling the listener. Strings and comments are scanned, as they may have brackets.
+    fn skip<L: EventListener>(
+        &mut self,
+        buffer: &str,
+        byte_offset: &mut usize,
+        end_of_input: bool,
+        listener: &mut L,
+        depth: usize,
+    ) -> Step {
+        let mut iter = chars(buffer, *byte_offset);
+        let next = match iter.next() {
+            Some(next) => next,
+            None if end_of_input => {
+                let error = skip_error(buffer, buffer.len(), &self.container_stack);
+                return self.fail(&mut iter, end_of_input, listener, error);
+            }
+            None => return Step::NeedInput,
+        };
+
+        let skipped = skip_token(
+            &mut iter,
+            buffer,
+            next,
+            &mut self.container_stack,
+            self.buffer_byte_offset,
+            &self.options,
+        );
+        match skipped {
+            Ok(Skipped { end, comment }) => {
+                // The rest of a line comment may be in the next chunk.
+                if comment.is_some_and(|kind| kind != CommentKind::Block)
+                    && !end_of_input
+                    && !buffer[..end].ends_with('\n')
+                {
+                    return Step::NeedInput;
+                }
+                *byte_offset = end;
+                if self.container_stack.len() == depth {
+                    self.state = self.state_after_value();
+                }
+                Step::Continue
+            }
+            Err(error) => self.fail(&mut iter, end_of_input, listener, error),
+        }
+    }
+}
+
+/// Calls [EventListener::handle_text] with `text`, then `handle` unless parsing is stopped.
+fn with_text<L: EventListener>(
+    listener: &mut L,
+    text: &str,
+    handle: impl FnOnce(&mut L) -> ControlFlow,
+) -> ControlFlow {
+    match listener.handle_text(text) {
+        ControlFlow::Stop => ControlFlow::Stop,
+        ControlFlow::Continue | ControlFlow::SkipValue => handle(listener),
+    }
+}
+
+/// The character that ends the string or comment at the start of `buffer`.
+fn token_end(buffer: &str) -> Option<char> {
+    if buffer.starts_with("/*") {
+        Some('/')
+    } else if buffer.starts_with("//") || buffer.starts_with('#') {
+        Some('\n')
+    } else {
+        buffer
+            .chars()
+            .next()
+            .filter(|char| *char == '"' || *char == '\'')
+    }
+}
+
+/// Characters of `buffer` starting at `byte_offset`, with byte offsets in `buffer`.
+fn chars(buffer: &str, byte_offset: usize) -> Peekable<impl Iterator<Item = (usize, char)> + '_> {
+    buffer[byte_offset..]
+        .char_indices()
+        .map(move |(offset, char)| (byte_offset + offset, char))
+        .peekable()
+}
+
+/// Parses `input` in chunks of `chunk_size` bytes, returns the events.
+#[cfg(test)]
+fn parse_chunks(
+    input: &[u8],
+    chunk_size: usize,
+    options: ParseOptions,
+) -> (Vec<crate::ParseEvent>, Option<ParseError>) {
+    let mut listener = crate::PushToEvents::new();
+    let mut parser = ChunkedParser::new(options);
+    for chunk in input.chunks(chunk_size) {
+        parser.feed(chunk, &mut listener);
+    }
+    parser.finish_events(&mut listener);
+    listener.into_events()
+}
+
+#[test]
+fn chunk_tests() {
+    use crate::test_common::*;
+
+    let default_options = ParseOptions::default();
+    let lossless_options = ParseOptions {
+        lossless_numbers: true,
+        ..ParseOptions::default()
+    };
+    let mut tests: Vec<(String, ParseOptions)> = vec![];
+    let with_options =
+        |strs: Vec<String>, options: ParseOptions| strs.into_iter().map(move |str| (str, options));
+    tests.extend(with_options(
+        event_tests().into_iter().map(|(str, _)| str).collect(),
+        default_options,
+    ));
+    tests.extend(with_options(
+        number_error_tests()
+            .into_iter()
+            .map(|(str, _)| str)
+            .collect(),
+        default_options,
+    ));
+    tests.extend(with_options(
+        escape_error_tests()
+            .into_iter()
+            .map(|(str, _)| str)
+            .collect(),
+        default_options,
+    ));
+    tests.extend(with_options(
+        unexpected_token_tests()
+            .into_iter()
+            .map(|(str, _)| str)
+            .collect(),
+        default_options,
+    ));
+    tests.extend(with_options(
+        comment_option_event_tests()
+            .into_iter()
+            .map(|(str, _)| str)
+            .collect(),
+        comment_options(),
+    ));
+    tests.extend(
+        comment_error_tests()
+            .into_iter()
+            .map(|(str, options, _)| (str, options)),
+    );
+    tests.extend(with_options(
+        lossless_number_event_tests()
+            .into_iter()
+            .map(|(str, _)| str)
+            .collect(),
+        lossless_options,
+    ));
+    tests.extend(with_options(
+        json5_event_tests()
+            .into_iter()
+            .map(|(str, _)| str)
+            .collect(),
+        json5_options(),
+    ));
+    tests.extend(with_options(
+        json5_error_tests()
+            .into_iter()
+            .map(|(str, _)| str)
+            .collect(),
+        json5_options(),
+    ));
+    tests.extend(with_options(
+        strict_error_tests()
+            .into_iter()
+            .map(|(str, _, _)| str)
+            .collect(),
+        strict_options(),
+    ));
+
+    for (str, options) in tests {
+        println!("Parsing {:?}", str);
+        let mut listener = crate::PushToEvents::new();
+        crate::event_push_parser::push_events(&str, &mut listener, options);
+        let expected = listener.into_events();
+        for chunk_size in [1, 2, 3, 7, str.len().max(1)] {
+            assert_eq!(parse_chunks(str.as_bytes(), chunk_size, options), expected);
+        }
+    }
+}
+
+#[test]
+fn text_test() {
+    struct TextListener(Vec<String>);
+
+    impl EventListener for TextListener {
+        fn handle_text(&mut self, text: &str) -> ControlFlow {
+            self.0.push(text.to_string());
+            ControlFlow::Continue
+        }
+
+        fn handle_error(&mut self, _error: ParseError) {
+            panic!()
+        }
+    }
+
+    impl FinishListener for TextListener {
+        type Output = Vec<String>;
+        type Error = ParseError;
+
+        fn finish(self) -> Result<Vec<String>, ParseError> {
+            Ok(self.0)
+        }
+    }
+
+    let input = "{a: 'é😀\\n', // c\n \"b\": [1.50, 2]}";
+    let options = ParseOptions {
+        lossless_numbers: true,
+        ..crate::test_common::json5_options()
+    };
+    let mut listener = TextListener(vec![]);
+    let mut parser = ChunkedParser::new(options);
+    for chunk in input.as_bytes().chunks(1) {
+        parser.feed(chunk, &mut listener);
+    }
+    assert_eq!(
+        parser.finish(listener).unwrap(),
+        ["a", "é😀\\n", "// c\n", "b", "1.50", "2"]
+    );
+}
+
+#[test]
+fn incremental_test() {
+    let mut listener = crate::PushToEvents::new();
+    let mut parser = ChunkedParser::new(ParseOptions::default());
+    parser.feed(b"[true, 12", &mut listener);
+    parser.feed(b"3, \"a", &mut listener);
+    let (events, error) = listener.into_events();
+    let kinds: Vec<_> = events.into_iter().map(|event| event.kind).collect();
+    assert_eq!(
+        kinds,
+        [
+            crate::ParseEventKind::StartArray,
+            crate::ParseEventKind::Bool(true),
+            crate::ParseEventKind::Int(123),
+        ]
+    );
+    assert_eq!(error, None);
+}
+
+#[test]
+fn utf8_tests() {
+    let (_, error) = parse_chunks(b"[1, \xff]", 2, ParseOptions::default());
+    assert_eq!(error, Some(ParseError::new(4, ParseErrorKind::InvalidUtf8)));
+
+    let (_, error) = parse_chunks(b"[\"\xe2\x82", 1, ParseOptions::default());
+    assert_eq!(error, Some(ParseError::new(2, ParseErrorKind::InvalidUtf8)));
+
+    // An error before the invalid UTF-8 is reported first.
+    let (_, error) = parse_chunks(b"[1 2 \xff]", 8, ParseOptions::default());
+    assert_eq!(error.unwrap().byte_offset, 3);
+}
+
+#[test]
+fn long_token_test() {
+    // Parsed in linear time with one byte chunks.
+    let string = "a".repeat(100_000);
+    let input = format!("[\"{}\" /* {} */]", string, string);
+    let (events, error) = parse_chunks(input.as_bytes(), 1, crate::test_common::comment_options());
+    assert_eq!(error, None);
+    assert_eq!(
+        events[1].kind,
+        crate::ParseEventKind::Str {
+            size_in_bytes: string.len(),
+            has_escapes: false
+        }
+    );
+    assert_eq!(
+        events[2].kind,
+        crate::ParseEventKind::Comment {
+            size_in_bytes: string.len() + 6,
+            kind: CommentKind::Block
+        }
+    );
+}
+
+#[test]
+fn control_flow_tests() {
+    use crate::test_common::ControlListener;
+    use crate::ParseEventKind;
+
+    // Stops at `stop_index`, skips the values in the top-level array or object when `skip` is
+    // set.
+    fn control(stop_index: usize, skip: bool) -> impl FnMut(usize, &ParseEventKind) -> ControlFlow {
+        move |index, _| match index {
+            _ if index == stop_index => ControlFlow::Stop,
+            0 => ControlFlow::Continue,
+            _ if skip => ControlFlow::SkipValue,
+            _ => ControlFlow::Continue,
+        }
+    }
+
+    let mut tests: Vec<(String, ParseOptions, usize, bool)> = vec![];
+    for (str, options) in crate::test_common::control_flow_inputs() {
+        for stop_index in 0..str.len() {
+            tests.push((str.clone(), options, stop_index, false));
+        }
+    }
+    for (str, options, _) in crate::test_common::skip_value_tests() {
+        tests.push((str, options, usize::MAX, true));
+    }
+    for (str, _) in crate::test_common::skip_value_error_tests() {
+        tests.push((str, crate::test_common::comment_options(), usize::MAX, true));
+    }
+
+    for (str, options, stop_index, skip) in tests {
+        let mut expected = ControlListener::new(control(stop_index, skip));
+        crate::event_push_parser::push_events(&str, &mut expected, options);
+        for chunk_size in 1..=str.len() {
+            println!("Parsing {:?} in chunks of {}", str, chunk_size);
+            let mut listener = ControlListener::new(control(stop_index, skip));
+            let mut parser = ChunkedParser::new(options);
+            for chunk in str.as_bytes().chunks(chunk_size) {
+                parser.feed(chunk, &mut listener);
+            }
+            parser.finish_events(&mut listener);
+            assert_eq!(listener.events, expected.events);
+            assert_eq!(listener.error, expected.error);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use crate::{CommentKind, Dialect, ParseError, ParseErrorKind, ParseOptions};
+
+use std::iter::Peekable;
+
+/// Whether `char` starts a comment enabled in `options`.
+pub(crate) fn is_comment_start(char: char, options: &ParseOptions) -> bool {
+    char == '/' || (char == '#' && options.hash_comments)
+}
+
+/// Checks that comments are allowed in the dialect of `options`, for a comment at `byte_offset`.
+pub(crate) fn check_comment(byte_offset: usize, options: &ParseOptions) -> Result<(), ParseError> {
+    if options.dialect == Dialect::Strict {
+        return Err(ParseError::new(
+            byte_offset,
+            ParseErrorKind::CommentNotAllowed,
+        ));
+    }
+    Ok(())
+}
+
+/// Scans a comment. `first` is the first character of the comment at `byte_offset`, which should
+/// be consumed. `first` should be a character accepted by [is_comment_start].
+///
+/// Returns the size of the comment and the comment kind. The size of line comments includes the
+/// newline at the end, when the comment is not terminated by the end of input.
+pub(crate) fn scan_comment<I: Iterator<Item = (usize, char)>>(
+    iter: &mut Peekable<I>,
+    input_size: usize,
+    byte_offset: usize,
+    first: char,
+    options: &ParseOptions,
+) -> Result<(usize, CommentKind), ParseError> {
+    check_comment(byte_offset, options)?;
+
+    if first == '#' {
+        let size_in_bytes = skip_line(iter, input_size) - byte_offset;
+        return Ok((size_in_bytes, CommentKind::Hash));
+    }
+
+    match iter.next() {
+        Some((_, '/')) if options.line_comments => {
+            let size_in_bytes = skip_line(iter, input_size) - byte_offset;
+            Ok((size_in_bytes, CommentKind::Line))
+        }
+
+        Some((_, '*')) if options.block_comments => {
+            let size_in_bytes = skip_block(iter, byte_offset, options)? - byte_offset;
+            Ok((size_in_bytes, CommentKind::Block))
+        }
+
+        _ => Err(ParseError::new(byte_offset, ParseErrorKind::InvalidComment)),
+    }
+}
+
+/// Skips until after the next newline, or end of input. Returns the byte offset after the line.
+fn skip_line<I: Iterator<Item = (usize, char)>>(iter: &mut I, input_size: usize) -> usize {
+    for (byte_offset, char) in iter.by_ref() {
+        if char == '\n' {
+            return byte_offset + 1;
+        }
+    }
+    input_size
+}
+
+/// Skips until after the end of a block comment. Expects the `/*` to be consumed. Returns the byte
+/// offset after the comment.
+fn skip_block<I: Iterator<Item = (usize, char)>>(
+    iter: &mut Peekable<I>,
+    comment_byte_offset: usize,
+    options: &ParseOptions,
+) -> Result<usize, ParseError> {
+    let mut depth: u32 = 1;
+    while let Some((_, char)) = iter.next() {
+        match (char, iter.peek().copied()) {
+            ('*', Some((slash_byte_offset, '/'))) => {
+                iter.next(); // consume '/'
+                depth -= 1;
+                if depth == 0 {
+                    return Ok(slash_byte_offset + 1);
+                }
+            }
+
+            ('/', Some((_, '*'))) if options.nested_block_comments => {
+                iter.next(); // consume '*'
+                depth += 1;
+            }
+
+            _ => {}
+        }
+    }
+    Err(ParseError::new(
+        comment_byte_offset,
+        ParseErrorKind::UnterminatedComment,
+    ))
+}
@@ -0,0 +1,622 @@
+use crate::whitespace::is_whitespace;
+use crate::{ParseError, ParseEvent, ParseEventKind, Span};
+
+use std::fmt;
+use std::rc::Rc;
+
+/// Kinds of nodes and tokens in a concrete syntax tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    /// The root node: the top-level value with the whitespace and comments around it.
+    Root,
+
+    /// An array node, including the brackets.
+    Array,
+
+    /// An object node, including the brackets.
+    Object,
+
+    /// An object key and value, with the colon and trivia between them. The comma after the value
+    /// is in the object.
+    Member,
+
+    /// `{`
+    LBrace,
+
+    /// `}`
+    RBrace,
+
+    /// `[`
+    LBracket,
+
+    /// `]`
+    RBracket,
+
+    /// `,`
+    Comma,
+
+    /// `:`
+    Colon,
+
+    /// A string, including the quotes.
+    String,
+
+    /// A JSON5 identifier object key.
+    Identifier,
+
+    Number,
+    True,
+    False,
+    Null,
+    Whitespace,
+
+    /// A comment, including the delimiters. Line comments include the newline.
+    Comment,
+
+    /// Text skipped when recovering from an error, with
+    /// [ParseOptions::recover_errors][crate::ParseOptions::recover_errors].
+    Error,
+}
+
+/// A token in the "green" tree: kind and text, without a location.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GreenToken {
+    kind: SyntaxKind,
+    text: String,
+}
+
+/// A node in the "green" tree: kind and children, without a location. Green nodes are immutable
+/// and can be shared by multiple trees.
+#[derive(Debug, PartialEq, Eq)]
+pub struct GreenNode {
+    kind: SyntaxKind,
+
+    /// Sum of the text sizes of the children.
+    text_len: usize,
+
+    children: Vec<GreenElement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GreenElement {
+    Node(Rc<GreenNode>),
+    Token(Rc<GreenToken>),
+}
+
+/// A node in the "red" tree: a green node with its location and parent. Red nodes are created
+/// on demand when traversing the tree.
+#[derive(Debug, Clone)]
+pub struct SyntaxNode(Rc<SyntaxNodeData>);
+
+#[derive(Debug)]
+struct SyntaxNodeData {
+    green: Rc<GreenNode>,
+    parent: Option<SyntaxNode>,
+    offset: usize,
+}
+
+/// A token in the "red" tree: a green token with its location and parent.
+#[derive(Debug, Clone)]
+pub struct SyntaxToken {
+    green: Rc<GreenToken>,
+    parent: SyntaxNode,
+    offset: usize,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(SyntaxToken),
+}
+
+impl GreenToken {
+    pub fn new(kind: SyntaxKind, text: String) -> GreenToken {
+        GreenToken { kind, text }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+}
+
+impl GreenNode {
+    pub fn new(kind: SyntaxKind, children: Vec<GreenElement>) -> GreenNode {
+        let text_len = children.iter().map(GreenElement::text_len).sum();
+        GreenNode {
+            kind,
+            text_len,
+            children,
+        }
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.kind
+    }
+
+    pub fn text_len(&self) -> usize {
+        self.text_len
+    }
+
+    pub fn children(&self) -> &[GreenElement] {
+        &self.children
+    }
+}
+
+impl GreenElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            GreenElement::Node(node) => node.kind,
+            GreenElement::Token(token) => token.kind,
+        }
+    }
+
+    pub fn text_len(&self) -> usize {
+        match self {
+            GreenElement::Node(node) => node.text_len,
+            GreenElement::Token(token) => token.text.len(),
+        }
+    }
+}
+
+impl SyntaxNode {
+    /// Makes a red tree with `green` as the root, starting at byte offset 0.
+    pub fn new_root(green: Rc<GreenNode>) -> SyntaxNode {
+        SyntaxNode(Rc::new(SyntaxNodeData {
+            green,
+            parent: None,
+            offset: 0,
+        }))
+    }
+
+    pub fn kind(&self) -> SyntaxKind {
+        self.0.green.kind
+    }
+
+    pub fn green(&self) -> &Rc<GreenNode> {
+        &self.0.green
+    }
+
+    pub fn parent(&self) -> Option<&SyntaxNode> {
+        self.0.parent.as_ref()
+    }
+
+    /// Location of the node in the input.
+    pub fn span(&self) -> Span {
+        Span::new(self.0.offset, self.0.offset + self.0.green.text_len)
+    }
+
+    pub fn children(&self) -> Vec<SyntaxElement> {
+        let mut offset = self.0.offset;
+        let mut children = Vec::with_capacity(self.0.green.children.len());
+        for child in &self.0.green.children {
+            children.push(match child {
+                GreenElement::Node(green) => {
+                    SyntaxElement::Node(SyntaxNode(Rc::new(SyntaxNodeData {
+                        green: green.clone(),
+                        parent: Some(self.clone()),
+                        offset,
+                    })))
+                }
+                GreenElement::Token(green) => SyntaxElement::Token(SyntaxToken {
+                    green: green.clone(),
+                    parent: self.clone(),
+                    offset,
+                }),
+            });
+            offset += child.text_len();
+        }
+        children
+    }
+
+    /// Child nodes, without the tokens.
+    pub fn child_nodes(&self) -> Vec<SyntaxNode> {
+        self.children()
+            .into_iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Node(node) => Some(node),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+}
+
+impl SyntaxToken {
+    pub fn kind(&self) -> SyntaxKind {
+        self.green.kind
+    }
+
+    pub fn text(&self) -> &str {
+        &self.green.text
+    }
+
+    pub fn parent(&self) -> &SyntaxNode {
+        &self.parent
+    }
+
+    /// Location of the token in the input.
+    pub fn span(&self) -> Span {
+        Span::new(self.offset, self.offset + self.green.text.len())
+    }
+}
+
+impl SyntaxElement {
+    pub fn kind(&self) -> SyntaxKind {
+        match self {
+            SyntaxElement::Node(node) => node.kind(),
+            SyntaxElement::Token(token) => token.kind(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            SyntaxElement::Node(node) => node.span(),
+            SyntaxElement::Token(token) => token.span(),
+        }
+    }
+}
+
+/// Prints the text of the tree, which is the input the tree was built from.
+impl fmt::Display for GreenNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for child in &self.children {
+            match child {
+                GreenElement::Node(node) => node.fmt(f)?,
+                GreenElement::Token(token) => f.write_str(&token.text)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.green.fmt(f)
+    }
+}
+
+impl fmt::Display for SyntaxToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.green.text)
+    }
+}
+
+/// Builds a lossless concrete syntax tree from a stream of [ParseEvent]s. Printing the tree with
+/// [Display][std::fmt::Display] gives the input.
+///
+/// Whitespace, commas and colons are not reported as events, they are found in the input
+/// between the events. With [ParseOptions::recover_errors][crate::ParseOptions::recover_errors],
+/// the input skipped by the parser becomes [SyntaxKind::Error] tokens.
+pub fn event_to_cst<I: Iterator<Item = Result<ParseEvent, ParseError>>>(
+    parser: &mut I,
+    input: &str,
+) -> Result<SyntaxNode, ParseError> {
+    let mut builder = CstBuilder {
+        input,
+        byte_offset: 0,
+        stack: vec![(SyntaxKind::Root, vec![])],
+    };
+
+    for event in parser.by_ref() {
+        let ParseEvent {
+            kind,
+            byte_offset,
+            span,
+        } = event?;
+
+        match kind {
+            ParseEventKind::StartObject => {
+                builder.trivia(byte_offset);
+                builder.stack.push((SyntaxKind::Object, vec![]));
+                builder.token(SyntaxKind::LBrace, span.start, span.end);
+            }
+
+            ParseEventKind::StartArray => {
+                builder.trivia(byte_offset);
+                builder.stack.push((SyntaxKind::Array, vec![]));
+                builder.token(SyntaxKind::LBracket, span.start, span.end);
+            }
+
+            ParseEventKind::EndObject | ParseEventKind::EndArray => {
+                builder.trivia(byte_offset);
+
+                // Key without a value, when recovering from errors.
+                if builder.current_kind() == SyntaxKind::Member {
+                    builder.finish_node();
+                }
+
+                // Missing closing brackets have empty spans when recovering from errors.
+                if span.start != span.end {
+                    let token_kind = if kind == ParseEventKind::EndObject {
+                        SyntaxKind::RBrace
+                    } else {
+                        SyntaxKind::RBracket
+                    };
+                    builder.token(token_kind, span.start, span.end);
+                }
+
+                builder.finish_node();
+                builder.finish_value();
+            }
+
+            ParseEventKind::Str { .. } => {
+                let token_kind = if span.start == byte_offset {
+                    SyntaxKind::Identifier
+                } else {
+                    SyntaxKind::String
+                };
+                if builder.current_kind() == SyntaxKind::Object {
+                    // Object key.
+                    builder.trivia(span.start);
+                    builder.stack.push((SyntaxKind::Member, vec![]));
+                    builder.token(token_kind, span.start, span.end);
+                } else {
+                    builder.token(token_kind, span.start, span.end);
+                    builder.finish_value();
+                }
+            }
+
+            ParseEventKind::Int(_)
+            | ParseEventKind::NegInt(_)
+            | ParseEventKind::Float(_)
+            | ParseEventKind::Number { .. } => {
+                builder.token(SyntaxKind::Number, span.start, span.end);
+                builder.finish_value();
+            }
+
+            ParseEventKind::Bool(true) => {
+                builder.token(SyntaxKind::True, span.start, span.end);
+                builder.finish_value();
+            }
+
+            ParseEventKind::Bool(false) => {
+                builder.token(SyntaxKind::False, span.start, span.end);
+                builder.finish_value();
+            }
+
+            ParseEventKind::Null => {
+                builder.token(SyntaxKind::Null, span.start, span.end);
+                builder.finish_value();
+            }
+
+            ParseEventKind::Comment { .. } => {
+                builder.token(SyntaxKind::Comment, span.start, span.end);
+            }
+
+            // Skipped input is added as error tokens with the trivia. An error in place of a member
+            // value finishes the member.
+            ParseEventKind::Error(_) => builder.finish_value(),
+
+            // Not generated by `parse_events`.
+            ParseEventKind::StartDocument | ParseEventKind::EndDocument => {}
+        }
+    }
+
+    builder.trivia(input.len());
+    while builder.stack.len() > 1 {
+        builder.finish_node();
+    }
+
+    let (kind, children) = builder.stack.pop().unwrap();
+    Ok(SyntaxNode::new_root(Rc::new(GreenNode::new(
+        kind, children,
+    ))))
+}
+
+struct CstBuilder<'a> {
+    input: &'a str,
+
+    /// Byte offset after the last token.
+    byte_offset: usize,
+
+    /// Nodes being built, with the children built so far. The first node is the root.
+    stack: Vec<(SyntaxKind, Vec<GreenElement>)>,
+}
+
+impl<'a> CstBuilder<'a> {
+    fn current_kind(&self) -> SyntaxKind {
+        self.stack.last().unwrap().0
+    }
+
+    /// Adds the trivia before `start`, and a token at `start..end`.
+    fn token(&mut self, kind: SyntaxKind, start: usize, end: usize) {
+        self.trivia(start);
+        let token = GreenToken::new(kind, self.input[start..end].to_string());
+        self.push(GreenElement::Token(Rc::new(token)));
+        self.byte_offset = end;
+    }
+
+    /// Adds the text between the last token and `end`, which has no events: whitespace, commas,
+    /// colons, and the input skipped when recovering from errors.
+    fn trivia(&mut self, end: usize) {
+        let mut start = self.byte_offset;
+        while start < end {
+            let text = &self.input[start..end];
+            let first = text.chars().next().unwrap();
+            let (kind, size_in_bytes) = match first {
+                ',' => (SyntaxKind::Comma, 1),
+                ':' => (SyntaxKind::Colon, 1),
+                _ if is_whitespace(first) => (
+                    SyntaxKind::Whitespace,
+                    text.find(|c: char| !is_whitespace(c)).unwrap_or(text.len()),
+                ),
+                _ => (
+                    SyntaxKind::Error,
+                    text.find(|c: char| is_whitespace(c) || c == ',' || c == ':')
+                        .unwrap_or(text.len()),
+                ),
+            };
+            let token = GreenToken::new(kind, text[..size_in_bytes].to_string());
+            self.push(GreenElement::Token(Rc::new(token)));
+            start += size_in_bytes;
+        }
+        self.byte_offset = end;
+    }
+
+    /// After a value: finishes the object member when the value is a member value.
+    fn finish_value(&mut self) {
+        if self.current_kind() == SyntaxKind::Member {
+            self.finish_node();
+        }
+    }
+
+    fn finish_node(&mut self) {
+        let (kind, children) = self.stack.pop().unwrap();
+        let node = GreenNode::new(kind, children);
+        self.push(GreenElement::Node(Rc::new(node)));
+    }
+
+    fn push(&mut self, element: GreenElement) {
+        self.stack.last_mut().unwrap().1.push(element);
+    }
+}
+
+#[cfg(test)]
+fn tree_dump(node: &SyntaxNode) -> String {
+    fn dump(element: &SyntaxElement, indent: usize, out: &mut String) {
+        let span = element.span();
+        match element {
+            SyntaxElement::Node(node) => {
+                out.push_str(&format!(
+                    "{:indent$}{:?}@{}..{}\n",
+                    "",
+                    node.kind(),
+                    span.start,
+                    span.end,
+                    indent = indent
+                ));
+                for child in node.children() {
+                    dump(&child, indent + 2, out);
+                }
+            }
+            SyntaxElement::Token(token) => {
+                out.push_str(&format!(
+                    "{:indent$}{:?}@{}..{} {:?}\n",
+                    "",
+                    token.kind(),
+                    span.start,
+                    span.end,
+                    token.text(),
+                    indent = indent
+                ));
+            }
+        }
+    }
+
+    let mut out = String::new();
+    dump(&SyntaxElement::Node(node.clone()), 0, &mut out);
+    out
+}
+
+#[test]
+fn cst_test() {
+    let input = "// a\n{\"a\" : [1,true] , b: 'c' /* d */}\n";
+    let mut parser = crate::parse_events_with_options(input, crate::test_common::json5_options());
+    let cst = event_to_cst(&mut parser, input).unwrap();
+    assert_eq!(
+        tree_dump(&cst),
+        r#"Root@0..39
+  Comment@0..5 "// a\n"
+  Object@5..38
+    LBrace@5..6 "{"
+    Member@6..20
+      String@6..9 "\"a\""
+      Whitespace@9..10 " "
+      Colon@10..11 ":"
+      Whitespace@11..12 " "
+      Array@12..20
+        LBracket@12..13 "["
+        Number@13..14 "1"
+        Comma@14..15 ","
+        True@15..19 "true"
+        RBracket@19..20 "]"
+    Whitespace@20..21 " "
+    Comma@21..22 ","
+    Whitespace@22..23 " "
+    Member@23..29
+      Identifier@23..24 "b"
+      Colon@24..25 ":"
+      Whitespace@25..26 " "
+      String@26..29 "'c'"
+    Whitespace@29..30 " "
+    Comment@30..37 "/* d */"
+    RBrace@37..38 "}"
+  Whitespace@38..39 "\n"
+"#
+    );
+    let object = &cst.child_nodes()[0];
+    assert_eq!(object.parent().unwrap().kind(), SyntaxKind::Root);
+    assert_eq!(object.child_nodes()[1].to_string(), "b: 'c'");
+}
+
+#[test]
+fn round_trip_tests() {
+    let default = crate::ParseOptions::default();
+    let inputs = crate::test_common::ast_tests()
+        .into_iter()
+        .map(|(str, _)| (str, default))
+        .chain(
+            crate::test_common::comment_option_event_tests()
+                .into_iter()
+                .map(|(str, _)| (str, crate::test_common::comment_options())),
+        )
+        .chain(
+            crate::test_common::json5_ast_tests()
+                .into_iter()
+                .map(|(str, _)| (str, crate::test_common::json5_options())),
+        );
+    for (str, options) in inputs {
+        println!("Parsing {:?}", str);
+        let mut parser = crate::parse_events_with_options(&str, options);
+        let cst = event_to_cst(&mut parser, &str).unwrap();
+        assert_eq!(cst.to_string(), str);
+        assert_eq!(cst.span(), Span::new(0, str.len()));
+    }
+}
+
+#[test]
+fn recovery_round_trip_tests() {
+    let options = crate::ParseOptions {
+        recover_errors: true,
+        ..crate::ParseOptions::default()
+    };
+    for (str, _, _) in crate::test_common::recovery_tests() {
+        println!("Parsing {:?}", str);
+        let mut parser = crate::parse_events_with_options(&str, options);
+        let cst = event_to_cst(&mut parser, &str).unwrap();
+        assert_eq!(cst.to_string(), str);
+    }
+}
+
+#[test]
+fn recovery_cst_test() {
+    let options = crate::ParseOptions {
+        recover_errors: true,
+        ..crate::ParseOptions::default()
+    };
+    let input = r#"{"a" 1, "b": 2}"#;
+    let mut parser = crate::parse_events_with_options(input, options);
+    let cst = event_to_cst(&mut parser, input).unwrap();
+    assert_eq!(
+        tree_dump(&cst),
+        r#"Root@0..15
+  Object@0..15
+    LBrace@0..1 "{"
+    Member@1..4
+      String@1..4 "\"a\""
+    Whitespace@4..5 " "
+    Error@5..6 "1"
+    Comma@6..7 ","
+    Whitespace@7..8 " "
+    Member@8..14
+      String@8..11 "\"b\""
+      Colon@11..12 ":"
+      Whitespace@12..13 " "
+      Number@13..14 "2"
+    RBrace@14..15 "}"
+"#
+    );
+}
@@ -0,0 +1,286 @@
+use crate::{LineIndex, ParseError, ParseErrorKind};
+
+use std::fmt::Write;
+
+/// Maximum number of characters shown from a source line. Longer lines are cut around the
+/// labels.
+const MAX_LINE_CHARS: usize = 100;
+
+/// Renders `error` in the input of `line_index` for humans, as the error message, location, and
+/// the line with the error marked with a caret. When the error is in an array or object, the
+/// opening bracket of the container is marked as well.
+///
+/// Example:
+///
+/// ```text
+/// error: unexpected end of input, expected ',' or '}'
+///  --> 2:9
+///   |
+/// 1 | {
+///   | - unclosed object
+/// 2 |   "a": 1
+///   |         ^
+/// ```
+pub fn render_diagnostic(line_index: &LineIndex, error: &ParseError) -> String {
+    let mut labels: Vec<Label> = vec![Label {
+        byte_offset: error.byte_offset,
+        marker: '^',
+        message: "",
+    }];
+
+    if let ParseErrorKind::UnexpectedToken {
+        container_byte_offset: Some(container_byte_offset),
+        ..
+    } = error.kind
+    {
+        let message = if line_index.input()[container_byte_offset..].starts_with('[') {
+            "unclosed array"
+        } else {
+            "unclosed object"
+        };
+        labels.push(Label {
+            byte_offset: container_byte_offset,
+            marker: '-',
+            message,
+        });
+    }
+
+    labels.sort_by_key(|label| label.byte_offset);
+
+    let location = line_index.line_col(error.byte_offset);
+    let last_line = line_index.line_col(labels.last().unwrap().byte_offset).line;
+    let gutter_width = (last_line + 1).to_string().len();
+
+    let mut out = String::new();
+    writeln!(out, "error: {}", error.kind).unwrap();
+    writeln!(
+        out,
+        "{:width$}--> {}:{}",
+        "",
+        location.line + 1,
+        location.col + 1,
+        width = gutter_width
+    )
+    .unwrap();
+    writeln!(out, "{:width$} |", "", width = gutter_width).unwrap();
+
+    let mut prev_line: Option<usize> = None;
+    for snippet in snippets(line_index, &labels) {
+        if let Some(prev_line) = prev_line {
+            if snippet.line > prev_line + 1 {
+                writeln!(out, "...").unwrap();
+            }
+        }
+        prev_line = Some(snippet.line);
+
+        let source_line = format!(
+            "{:>width$} | {}",
+            snippet.line + 1,
+            snippet.text,
+            width = gutter_width
+        );
+        writeln!(out, "{}", source_line.trim_end()).unwrap();
+
+        for (padding, label) in &snippet.labels {
+            let marker_line = format!("{}{} {}", padding, label.marker, label.message);
+            writeln!(
+                out,
+                "{:width$} | {}",
+                "",
+                marker_line.trim_end(),
+                width = gutter_width
+            )
+            .unwrap();
+        }
+    }
+
+    out
+}
+
+struct Label {
+    byte_offset: usize,
+    marker: char,
+    message: &'static str,
+}
+
+/// Part of a source line to show, with the labels in it.
+struct Snippet<'a> {
+    line: usize,
+    text: String,
+
+    /// Labels in the line, with the whitespace before the marker.
+    labels: Vec<(String, &'a Label)>,
+}
+
+/// Splits the labels, which should be sorted, into snippets.
+fn snippets<'a>(line_index: &LineIndex, labels: &'a [Label]) -> Vec<Snippet<'a>> {
+    let mut snippets: Vec<Snippet> = vec![];
+
+    // Character range of the line shown in the last snippet.
+    let mut last_window = 0..0;
+
+    for label in labels {
+        let line = line_index.line_col(label.byte_offset).line;
+        let line_start = line_index.line_start(line);
+        let line_text = line_index.line(line);
+
+        // Character index of the label in the line. Labels at the line terminator or at the end of
+        // input are at the end of the line.
+        let col_bytes = (label.byte_offset - line_start).min(line_text.len());
+        let label_char = line_text[..col_bytes].chars().count();
+
+        let same_snippet = match snippets.last() {
+            Some(snippet) => snippet.line == line && last_window.contains(&label_char),
+            None => false,
+        };
+
+        if !same_snippet {
+            let line_chars = line_text.chars().count();
+            let window_start = if line_chars <= MAX_LINE_CHARS {
+                0
+            } else {
+                label_char
+                    .saturating_sub(MAX_LINE_CHARS / 2)
+                    .min(line_chars - MAX_LINE_CHARS)
+            };
+            let window_end = (window_start + MAX_LINE_CHARS).min(line_chars);
+            last_window = window_start..window_end + 1;
+
+            let mut text: String = line_text
+                .chars()
+                .skip(window_start)
+                .take(window_end - window_start)
+                .collect();
+            if window_start != 0 {
+                text.insert_str(0, "...");
+            }
+            if window_end != line_chars {
+                text.push_str("...");
+            }
+
+            snippets.push(Snippet {
+                line,
+                text,
+                labels: vec![],
+            });
+        }
+
+        // Align the marker with the label, keeping tabs so that the marker is aligned in
+        // terminals.
+        let mut padding: String = line_text
+            .chars()
+            .take(label_char)
+            .skip(last_window.start)
+            .map(|char| if char == '\t' { '\t' } else { ' ' })
+            .collect();
+        if last_window.start != 0 {
+            padding.insert_str(0, "   ");
+        }
+
+        snippets.last_mut().unwrap().labels.push((padding, label));
+    }
+
+    snippets
+}
+
+#[test]
+fn render_test() {
+    let input = "{\n  \"a\": 1";
+    let error = crate::parse_ast(input).unwrap_err();
+    assert_eq!(
+        render_diagnostic(&LineIndex::new(input), &error),
+        "\
+error: unexpected end of input, expected ',' or '}'
+ --> 2:9
+  |
+1 | {
+  | - unclosed object
+2 |   \"a\": 1
+  |         ^
+"
+    );
+
+    let input = "{\n\n  \"a\" 1}";
+    let error = crate::parse_ast(input).unwrap_err();
+    assert_eq!(
+        render_diagnostic(&LineIndex::new(input), &error),
+        "\
+error: unexpected number, expected ':'
+ --> 3:7
+  |
+1 | {
+  | - unclosed object
+...
+3 |   \"a\" 1}
+  |       ^
+"
+    );
+
+    let input = "[1,\t2 3]";
+    let error = crate::parse_ast(input).unwrap_err();
+    assert_eq!(
+        render_diagnostic(&LineIndex::new(input), &error),
+        "\
+error: unexpected number, expected ',' or ']'
+ --> 1:7
+  |
+1 | [1,\t2 3]
+  | - unclosed array
+  |    \t  ^
+"
+    );
+
+    let input = "\"\\q\"";
+    let error = crate::parse_ast(input).unwrap_err();
+    assert_eq!(
+        render_diagnostic(&LineIndex::new(input), &error),
+        "\
+error: invalid escape sequence
+ --> 1:3
+  |
+1 | \"\\q\"
+  |   ^
+"
+    );
+}
+
+#[test]
+fn render_long_line_test() {
+    let input = format!("[{}true false]", "1, ".repeat(100));
+    let error = crate::parse_ast(&input).unwrap_err();
+    let rendered = render_diagnostic(&LineIndex::new(&input), &error);
+    let lines: Vec<&str> = rendered.lines().collect();
+    assert_eq!(lines[1], " --> 1:307");
+    assert_eq!(lines[3], format!("1 | [{}...", "1, ".repeat(33)));
+    assert_eq!(lines[4], "  | - unclosed array");
+    assert!(lines[5].starts_with("1 | ...") && !lines[5].ends_with("..."));
+    let marker = lines[6].find('^').unwrap();
+    assert_eq!(&lines[5][marker..marker + 5], "false");
+}
+
+#[test]
+fn render_all_parsers_test() {
+    let input = "{\"a\": [1, 2}";
+    let line_index = LineIndex::new(input);
+    let errors = [
+        crate::parse_events(input).find_map(Result::err).unwrap(),
+        crate::parse_events_lexgen(input)
+            .find_map(Result::err)
+            .unwrap(),
+        crate::parse_events_push(input, crate::PushToEvents::new()).unwrap_err(),
+        crate::parse_ast(input).unwrap_err(),
+    ];
+    for error in &errors {
+        assert_eq!(
+            render_diagnostic(&line_index, error),
+            "\
+error: unexpected '}', expected ',' or ']'
+ --> 1:12
+  |
+1 | {\"a\": [1, 2}
+  |       - unclosed array
+  |            ^
+"
+        );
+    }
+}
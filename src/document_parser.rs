@@ -0,0 +1,442 @@
+use crate::event_parser::EventParser;
+use crate::{event_to_tree, Json, ParseError, ParseEvent, ParseEventKind, ParseOptions, Span};
+
+/// The record separator character that starts each document in [Framing::RecordSeparator].
+const RECORD_SEPARATOR: char = '\u{1e}';
+
+/// How the documents in a multi-document input are delimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// [JSON Lines](https://jsonlines.org/) (also known as NDJSON): one document per line. Lines
+    /// with only whitespace and comments are skipped.
+    Lines,
+
+    /// [RFC 7464](https://www.rfc-editor.org/rfc/rfc7464) JSON text sequences: each document is
+    /// preceded by a record separator character (U+001E). Documents with only whitespace and
+    /// comments are skipped.
+    RecordSeparator,
+
+    /// Documents one after another, optionally separated by whitespace and comments, as in
+    /// `{"a": 1}{"b": 2} 3`.
+    Concatenated,
+}
+
+/// Parses input with multiple documents to [ParseEvent]s.
+pub fn parse_documents(input: &str, framing: Framing) -> DocumentParser<'_> {
+    DocumentParser::new(input, framing, ParseOptions::default())
+}
+
+/// Parses input with multiple documents to [ParseEvent]s, with the given options.
+pub fn parse_documents_with_options(
+    input: &str,
+    framing: Framing,
+    options: ParseOptions,
+) -> DocumentParser<'_> {
+    DocumentParser::new(input, framing, options)
+}
+
+/// Parses input with multiple documents to [Json]s, one for each document.
+pub fn parse_ast_documents(
+    input: &str,
+    framing: Framing,
+    options: ParseOptions,
+) -> JsonDocuments<'_> {
+    JsonDocuments {
+        parser: DocumentParser::new(input, framing, options),
+    }
+}
+
+/// A parser that generates [ParseEvent]s for an input with multiple documents.
+///
+/// The events of each document are between [ParseEventKind::StartDocument] and
+/// [ParseEventKind::EndDocument] events. Comments between documents are reported outside of the
+/// documents.
+///
+/// Unlike the other parsers, parsing doesn't stop at errors: an error skips the rest of the
+/// document, and parsing continues with the next document. With [Framing::Concatenated] the next
+/// document starts on the next line. An error in a document is followed by the
+/// [ParseEventKind::EndDocument] event. [ParseOptions::recover_errors] is not supported.
+#[derive(Debug)]
+pub struct DocumentParser<'a> {
+    input: &'a str,
+    framing: Framing,
+    options: ParseOptions,
+
+    /// Byte offset of the text of the next document.
+    byte_offset: usize,
+
+    /// Byte offset of the end of the current document's text.
+    document_end: usize,
+
+    state: DocumentState<'a>,
+}
+
+#[derive(Debug)]
+enum DocumentState<'a> {
+    /// Find the text of the next document.
+    NextDocument,
+
+    /// Skip trivia before the document's value. Documents with only trivia are skipped.
+    BeforeDocument(EventParser<'a>),
+
+    /// Parse the document's value.
+    InDocument(EventParser<'a>),
+
+    /// After an error in a document, end the document at the given byte offset.
+    EndDocument(usize),
+
+    /// End of input.
+    Done,
+}
+
+impl<'a> DocumentParser<'a> {
+    fn new(input: &'a str, framing: Framing, options: ParseOptions) -> DocumentParser<'a> {
+        DocumentParser {
+            input,
+            framing,
+            options: ParseOptions {
+                recover_errors: false,
+                ..options
+            },
+            byte_offset: 0,
+            document_end: 0,
+            state: DocumentState::NextDocument,
+        }
+    }
+
+    /// Finds the text of the next document, and returns the parser for it.
+    fn next_document(&mut self) -> Option<EventParser<'a>> {
+        if self.byte_offset == self.input.len() {
+            return None;
+        }
+        let start = self.byte_offset;
+        let (start, end) = match self.framing {
+            Framing::Lines => {
+                let end = match self.input[start..].find('\n') {
+                    Some(idx) => start + idx,
+                    None => self.input.len(),
+                };
+                self.byte_offset = (end + 1).min(self.input.len());
+                (start, end)
+            }
+            Framing::RecordSeparator => {
+                let start = if self.input[start..].starts_with(RECORD_SEPARATOR) {
+                    start + RECORD_SEPARATOR.len_utf8()
+                } else {
+                    start
+                };
+                let end = match self.input[start..].find(RECORD_SEPARATOR) {
+                    Some(idx) => start + idx,
+                    None => self.input.len(),
+                };
+                self.byte_offset = end;
+                (start, end)
+            }
+            Framing::Concatenated => (start, self.input.len()),
+        };
+        self.document_end = end;
+        let stop_after_value = self.framing == Framing::Concatenated;
+        Some(EventParser::new_document(
+            &self.input[..end],
+            start,
+            self.options,
+            stop_after_value,
+        ))
+    }
+
+    /// Skips the rest of the document after an error at `byte_offset`. Returns the byte offset of
+    /// the end of the document.
+    fn skip_document(&mut self, byte_offset: usize) -> usize {
+        if self.framing == Framing::Concatenated {
+            self.byte_offset = match self.input[byte_offset..].find('\n') {
+                Some(idx) => byte_offset + idx + 1,
+                None => self.input.len(),
+            };
+            self.document_end = self.byte_offset;
+        }
+        self.document_end
+    }
+}
+
+impl<'a> Iterator for DocumentParser<'a> {
+    type Item = Result<ParseEvent, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match std::mem::replace(&mut self.state, DocumentState::Done) {
+                DocumentState::NextDocument => {
+                    if let Some(parser) = self.next_document() {
+                        self.state = DocumentState::BeforeDocument(parser);
+                    }
+                }
+
+                DocumentState::BeforeDocument(mut parser) => match parser.skip_trivia() {
+                    Ok(Some(comment)) => {
+                        self.state = DocumentState::BeforeDocument(parser);
+                        return Some(Ok(comment));
+                    }
+                    Ok(None) if parser.is_at_end() => {
+                        if self.framing == Framing::Concatenated {
+                            self.byte_offset = self.input.len();
+                        }
+                        self.state = DocumentState::NextDocument;
+                    }
+                    Ok(None) => {
+                        let byte_offset = parser.byte_offset();
+                        self.state = DocumentState::InDocument(parser);
+                        return Some(Ok(ParseEvent::new(
+                            Span::new(byte_offset, byte_offset),
+                            ParseEventKind::StartDocument,
+                        )));
+                    }
+                    Err(error) => {
+                        self.skip_document(error.byte_offset);
+                        self.state = DocumentState::NextDocument;
+                        return Some(Err(error));
+                    }
+                },
+
+                DocumentState::InDocument(mut parser) => match parser.next() {
+                    Some(Ok(event)) => {
+                        self.state = DocumentState::InDocument(parser);
+                        return Some(Ok(event));
+                    }
+                    Some(Err(error)) => {
+                        let end = self.skip_document(error.byte_offset);
+                        self.state = DocumentState::EndDocument(end);
+                        return Some(Err(error));
+                    }
+                    None => {
+                        let end = parser.byte_offset();
+                        if self.framing == Framing::Concatenated {
+                            self.byte_offset = end;
+                        }
+                        self.state = DocumentState::NextDocument;
+                        return Some(Ok(ParseEvent::new(
+                            Span::new(end, end),
+                            ParseEventKind::EndDocument,
+                        )));
+                    }
+                },
+
+                DocumentState::EndDocument(end) => {
+                    self.state = DocumentState::NextDocument;
+                    return Some(Ok(ParseEvent::new(
+                        Span::new(end, end),
+                        ParseEventKind::EndDocument,
+                    )));
+                }
+
+                DocumentState::Done => return None,
+            }
+        }
+    }
+}
+
+/// An iterator of the documents in an input with multiple documents, parsed to [Json].
+///
+/// A document with an error is returned as the first error in the document, and parsing continues
+/// with the next document, as in [DocumentParser].
+#[derive(Debug)]
+pub struct JsonDocuments<'a> {
+    parser: DocumentParser<'a>,
+}
+
+impl<'a> Iterator for JsonDocuments<'a> {
+    type Item = Result<Json, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Errors between documents are reported as documents.
+        loop {
+            match self.parser.next()? {
+                Ok(ParseEvent {
+                    kind: ParseEventKind::StartDocument,
+                    ..
+                }) => break,
+                Ok(_) => {}
+                Err(error) => return Some(Err(error)),
+            }
+        }
+
+        let input = self.parser.input;
+        let mut result = event_to_tree(&mut self.parser, input);
+
+        // Skip the trivia after the value, checking for errors.
+        for event in self.parser.by_ref() {
+            match event {
+                Ok(ParseEvent {
+                    kind: ParseEventKind::EndDocument,
+                    ..
+                }) => break,
+                Ok(_) => {}
+                Err(error) => {
+                    if result.is_ok() {
+                        result = Err(error);
+                    }
+                }
+            }
+        }
+
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+fn collect_documents(input: &str, framing: Framing) -> Vec<Result<(usize, ParseEventKind), usize>> {
+    parse_documents(input, framing)
+        .map(|event| match event {
+            Ok(ParseEvent {
+                kind, byte_offset, ..
+            }) => Ok((byte_offset, kind)),
+            Err(error) => Err(error.byte_offset),
+        })
+        .collect()
+}
+
+#[test]
+fn lines_test() {
+    use crate::CommentKind;
+    use ParseEventKind::*;
+
+    let input = "1\n\n  [true] // c\n{\"a\" 1}\nnull 2\n\"x\"";
+    assert_eq!(
+        collect_documents(input, Framing::Lines),
+        [
+            Ok((0, StartDocument)),
+            Ok((0, Int(1))),
+            Ok((1, EndDocument)),
+            Ok((5, StartDocument)),
+            Ok((5, StartArray)),
+            Ok((6, Bool(true))),
+            Ok((10, EndArray)),
+            Ok((
+                12,
+                Comment {
+                    size_in_bytes: 4,
+                    kind: CommentKind::Line
+                }
+            )),
+            Ok((16, EndDocument)),
+            Ok((17, StartDocument)),
+            Ok((17, StartObject)),
+            Ok((
+                19,
+                Str {
+                    size_in_bytes: 1,
+                    has_escapes: false
+                }
+            )),
+            Err(22),
+            Ok((24, EndDocument)),
+            Ok((25, StartDocument)),
+            Ok((25, Null)),
+            Err(30),
+            Ok((31, EndDocument)),
+            Ok((32, StartDocument)),
+            Ok((
+                33,
+                Str {
+                    size_in_bytes: 1,
+                    has_escapes: false
+                }
+            )),
+            Ok((35, EndDocument)),
+        ]
+    );
+}
+
+#[test]
+fn record_separator_test() {
+    use ParseEventKind::*;
+
+    let input = "\u{1e}1\n\u{1e}\n\u{1e}[1,\n\u{1e}true\n";
+    assert_eq!(
+        collect_documents(input, Framing::RecordSeparator),
+        [
+            Ok((1, StartDocument)),
+            Ok((1, Int(1))),
+            Ok((3, EndDocument)),
+            Ok((6, StartDocument)),
+            Ok((6, StartArray)),
+            Ok((7, Int(1))),
+            Err(10),
+            Ok((10, EndDocument)),
+            Ok((11, StartDocument)),
+            Ok((11, Bool(true))),
+            Ok((16, EndDocument)),
+        ]
+    );
+}
+
+#[test]
+fn concatenated_test() {
+    use crate::CommentKind;
+    use ParseEventKind::*;
+
+    let input = "{\"a\":1}[2] /* c */ 3\n4 ]\n[5]";
+    assert_eq!(
+        collect_documents(input, Framing::Concatenated),
+        [
+            Ok((0, StartDocument)),
+            Ok((0, StartObject)),
+            Ok((
+                2,
+                Str {
+                    size_in_bytes: 1,
+                    has_escapes: false
+                }
+            )),
+            Ok((5, Int(1))),
+            Ok((6, EndObject)),
+            Ok((7, EndDocument)),
+            Ok((7, StartDocument)),
+            Ok((7, StartArray)),
+            Ok((8, Int(2))),
+            Ok((9, EndArray)),
+            Ok((10, EndDocument)),
+            Ok((
+                11,
+                Comment {
+                    size_in_bytes: 7,
+                    kind: CommentKind::Block
+                }
+            )),
+            Ok((19, StartDocument)),
+            Ok((19, Int(3))),
+            Ok((20, EndDocument)),
+            Ok((21, StartDocument)),
+            Ok((21, Int(4))),
+            Ok((22, EndDocument)),
+            Ok((23, StartDocument)),
+            Err(23),
+            Ok((25, EndDocument)),
+            Ok((25, StartDocument)),
+            Ok((25, StartArray)),
+            Ok((26, Int(5))),
+            Ok((27, EndArray)),
+            Ok((28, EndDocument)),
+        ]
+    );
+}
+
+#[test]
+fn ast_documents_test() {
+    let inputs = [
+        (Framing::Lines, "1\n[true]\n{\"a\" 1}\n\n\"x\"\n"),
+        (
+            Framing::RecordSeparator,
+            "\u{1e}1\n\u{1e}[true]\n\u{1e}{\"a\" 1}\n\u{1e}\"x\"\n",
+        ),
+        (Framing::Concatenated, "1 [true]\n{\"a\" 1}\n\"x\""),
+    ];
+    for (framing, input) in inputs {
+        println!("Parsing {:?}", input);
+        let documents: Vec<Result<Json, ParseError>> =
+            parse_ast_documents(input, framing, ParseOptions::default()).collect();
+        assert_eq!(documents.len(), 4);
+        assert_eq!(documents[0], Ok(Json::Int(1)));
+        assert_eq!(documents[1], Ok(Json::Array(vec![Json::Bool(true)])));
+        assert!(documents[2].is_err());
+        assert_eq!(documents[3], Ok(Json::String(String::from("x"))));
+    }
+}
@@ -0,0 +1,259 @@
+use std::fmt;
+
+/// A parse error, common for both event and AST parsers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// Byte offset of the parse error in the input.
+    pub byte_offset: usize,
+
+    /// What went wrong.
+    pub kind: ParseErrorKind,
+}
+
+/// Kinds of parse errors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A token that is not valid at the error location.
+    UnexpectedToken {
+        /// The token at the error location.
+        found: Token,
+
+        /// Tokens that would be valid at the error location.
+        expected: &'static [Token],
+
+        /// Byte offset of the opening bracket of the innermost array or object, when the error is
+        /// in an array or object.
+        container_byte_offset: Option<usize>,
+    },
+
+    /// Characters that don't start a token. Only generated by
+    /// [parse_events_lexgen][crate::parse_events_lexgen].
+    InvalidToken,
+
+    /// An identifier that is not `true`, `false` or `null`, or in JSON5, `Infinity` or `NaN`.
+    InvalidKeyword,
+
+    /// A number without digits after the sign, decimal point, or exponent, or a `0x` without
+    /// hexadecimal digits.
+    ExpectedDigit,
+
+    /// An integer that doesn't fit into `u64` or `i64`.
+    IntegerOverflow,
+
+    /// A number that doesn't fit into `f64`.
+    NumberOutOfRange,
+
+    /// An integer with leading zeros, in [Dialect::Strict][crate::Dialect::Strict].
+    LeadingZero,
+
+    /// An escape sequence other than the ones allowed in the dialect.
+    InvalidEscape,
+
+    /// A `\u` not followed by four hexadecimal digits.
+    InvalidUnicodeEscape,
+
+    /// A `\x` not followed by two hexadecimal digits, in [Dialect::Json5][crate::Dialect::Json5].
+    InvalidHexEscape,
+
+    /// A `\u` escape with a high surrogate not followed by one with a low surrogate, or a low
+    /// surrogate without a high surrogate.
+    UnpairedSurrogate,
+
+    /// End of input before the closing quote.
+    UnterminatedString,
+
+    /// An unescaped control character in a string, in [Dialect::Strict][crate::Dialect::Strict].
+    ControlCharacterInString,
+
+    /// A `/` not followed by the start of an enabled comment syntax.
+    InvalidComment,
+
+    /// End of input before the end of a block comment.
+    UnterminatedComment,
+
+    /// A comment, in [Dialect::Strict][crate::Dialect::Strict].
+    CommentNotAllowed,
+
+    /// A whitespace character not allowed in the dialect.
+    InvalidWhitespace,
+
+    /// Bytes that are not valid UTF-8. Only generated by [ChunkedParser][crate::ChunkedParser]
+    /// and [ReaderParser][crate::ReaderParser].
+    InvalidUtf8,
+
+    /// An error reading the input, at the byte offset of the bytes that couldn't be read. Only
+    /// generated by [ReaderParser][crate::ReaderParser].
+    Io(std::io::ErrorKind),
+}
+
+/// A token found or expected at a parse error.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    /// `{`
+    StartObject,
+
+    /// `}`
+    EndObject,
+
+    /// `[`
+    StartArray,
+
+    /// `]`
+    EndArray,
+
+    /// `,`
+    Comma,
+
+    /// `:`
+    Colon,
+
+    /// A string. As an expected token, also an identifier key in
+    /// [Dialect::Json5][crate::Dialect::Json5].
+    String,
+
+    /// A number.
+    Number,
+
+    /// Any value. Only used in expected tokens.
+    Value,
+
+    /// A character that doesn't start any of the other tokens. Only used in found tokens.
+    Char(char),
+
+    /// End of input.
+    EndOfInput,
+}
+
+pub(crate) const VALUE: &[Token] = &[Token::Value];
+pub(crate) const VALUE_OR_END_ARRAY: &[Token] = &[Token::Value, Token::EndArray];
+pub(crate) const COMMA_OR_END_ARRAY: &[Token] = &[Token::Comma, Token::EndArray];
+pub(crate) const KEY: &[Token] = &[Token::String];
+pub(crate) const KEY_OR_END_OBJECT: &[Token] = &[Token::String, Token::EndObject];
+pub(crate) const COLON: &[Token] = &[Token::Colon];
+pub(crate) const COMMA_OR_END_OBJECT: &[Token] = &[Token::Comma, Token::EndObject];
+pub(crate) const END_OF_INPUT: &[Token] = &[Token::EndOfInput];
+
+impl ParseError {
+    pub(crate) fn new(byte_offset: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError { byte_offset, kind }
+    }
+
+    /// An [ParseErrorKind::UnexpectedToken] error for the token at `byte_offset` in `input`.
+    pub(crate) fn unexpected(
+        input: &str,
+        byte_offset: usize,
+        expected: &'static [Token],
+        container_byte_offset: Option<usize>,
+    ) -> ParseError {
+        ParseError::new(
+            byte_offset,
+            ParseErrorKind::UnexpectedToken {
+                found: Token::at(input, byte_offset),
+                expected,
+                container_byte_offset,
+            },
+        )
+    }
+}
+
+impl Token {
+    /// The token starting at `byte_offset` in `input`.
+    pub(crate) fn at(input: &str, byte_offset: usize) -> Token {
+        match input[byte_offset..].chars().next() {
+            Some('{') => Token::StartObject,
+            Some('}') => Token::EndObject,
+            Some('[') => Token::StartArray,
+            Some(']') => Token::EndArray,
+            Some(',') => Token::Comma,
+            Some(':') => Token::Colon,
+            Some('"') => Token::String,
+            Some('-' | '0'..='9') => Token::Number,
+            Some(char) => Token::Char(char),
+            None => Token::EndOfInput,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte offset {}", self.kind, self.byte_offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl fmt::Display for ParseErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseErrorKind::UnexpectedToken {
+                found, expected, ..
+            } => {
+                write!(f, "unexpected {}", found)?;
+                for (i, token) in expected.iter().enumerate() {
+                    let separator = if i == 0 { ", expected" } else { " or" };
+                    write!(f, "{} {}", separator, token)?;
+                }
+                Ok(())
+            }
+            ParseErrorKind::InvalidToken => f.write_str("invalid token"),
+            ParseErrorKind::InvalidKeyword => f.write_str("invalid keyword"),
+            ParseErrorKind::ExpectedDigit => f.write_str("expected digit while parsing number"),
+            ParseErrorKind::IntegerOverflow => f.write_str("integer overflow"),
+            ParseErrorKind::NumberOutOfRange => f.write_str("number out of range"),
+            ParseErrorKind::LeadingZero => f.write_str("leading zeros are not allowed"),
+            ParseErrorKind::InvalidEscape => f.write_str("invalid escape sequence"),
+            ParseErrorKind::InvalidUnicodeEscape => f.write_str("invalid unicode escape"),
+            ParseErrorKind::InvalidHexEscape => f.write_str("invalid hexadecimal escape"),
+            ParseErrorKind::UnpairedSurrogate => {
+                f.write_str("unpaired surrogate in unicode escape")
+            }
+            ParseErrorKind::UnterminatedString => f.write_str("unterminated string"),
+            ParseErrorKind::ControlCharacterInString => {
+                f.write_str("unescaped control character in string")
+            }
+            ParseErrorKind::InvalidComment => f.write_str("invalid comment"),
+            ParseErrorKind::UnterminatedComment => f.write_str("unterminated comment"),
+            ParseErrorKind::CommentNotAllowed => f.write_str("comments are not allowed"),
+            ParseErrorKind::InvalidWhitespace => f.write_str("invalid whitespace"),
+            ParseErrorKind::InvalidUtf8 => f.write_str("invalid UTF-8"),
+            ParseErrorKind::Io(kind) => write!(f, "I/O error: {}", kind),
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::StartObject => f.write_str("'{'"),
+            Token::EndObject => f.write_str("'}'"),
+            Token::StartArray => f.write_str("'['"),
+            Token::EndArray => f.write_str("']'"),
+            Token::Comma => f.write_str("','"),
+            Token::Colon => f.write_str("':'"),
+            Token::String => f.write_str("string"),
+            Token::Number => f.write_str("number"),
+            Token::Value => f.write_str("value"),
+            Token::Char(char) => write!(f, "character {:?}", char),
+            Token::EndOfInput => f.write_str("end of input"),
+        }
+    }
+}
+
+#[test]
+fn display_test() {
+    let error = ParseError::unexpected("[1 2]", 3, COMMA_OR_END_ARRAY, Some(0));
+    assert_eq!(
+        error.to_string(),
+        "unexpected number, expected ',' or ']' at byte offset 3"
+    );
+    let error = ParseError::unexpected("{", 1, KEY_OR_END_OBJECT, Some(0));
+    assert_eq!(
+        error.kind.to_string(),
+        "unexpected end of input, expected string or '}'"
+    );
+    let error = ParseError::new(2, ParseErrorKind::InvalidEscape);
+    assert_eq!(
+        error.to_string(),
+        "invalid escape sequence at byte offset 2"
+    );
+}
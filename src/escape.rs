@@ -0,0 +1,220 @@
+use crate::{Dialect, ParseError, ParseErrorKind, ParseOptions};
+
+/// Scans a string until the closing `quote`, validating escape sequences, and in
+/// [Dialect::Strict], that the string has no unescaped control characters. Expects the opening
+/// quote to be consumed.
+///
+/// Returns the byte offset of the closing quote, and whether the string has any escape sequences.
+pub(crate) fn scan_string<I: Iterator<Item = (usize, char)>>(
+    iter: &mut I,
+    input_size: usize,
+    quote: char,
+    options: &ParseOptions,
+) -> Result<(usize, bool), ParseError> {
+    let json5 = options.dialect == Dialect::Json5;
+    let strict = options.dialect == Dialect::Strict;
+    let mut has_escapes = false;
+
+    // Byte offset of the last `\uXXXX` escape, if it was a high surrogate. The next character
+    // needs to be a `\uXXXX` escape with a low surrogate.
+    let mut high_surrogate: Option<usize> = None;
+
+    while let Some((byte_offset, char)) = iter.next() {
+        if char != '\\' {
+            if let Some(surrogate_byte_offset) = high_surrogate {
+                return Err(unpaired_surrogate(surrogate_byte_offset));
+            }
+            if char == quote {
+                return Ok((byte_offset, has_escapes));
+            }
+            if strict && char < ' ' {
+                return Err(ParseError::new(
+                    byte_offset,
+                    ParseErrorKind::ControlCharacterInString,
+                ));
+            }
+            continue;
+        }
+
+        has_escapes = true;
+
+        let (escape_byte_offset, escape) = match iter.next() {
+            Some(next) => next,
+            None => break,
+        };
+
+        if escape != 'u' {
+            if let Some(surrogate_byte_offset) = high_surrogate {
+                return Err(unpaired_surrogate(surrogate_byte_offset));
+            }
+            if json5 {
+                scan_json5_escape(iter, input_size, escape_byte_offset, escape)?;
+            } else if !matches!(escape, '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't') {
+                return Err(ParseError::new(
+                    escape_byte_offset,
+                    ParseErrorKind::InvalidEscape,
+                ));
+            }
+            continue;
+        }
+
+        let mut code: u32 = 0;
+        for _ in 0..4 {
+            let (digit_byte_offset, digit) = match iter.next() {
+                Some(next) => next,
+                None => {
+                    return Err(ParseError::new(
+                        input_size,
+                        ParseErrorKind::UnterminatedString,
+                    ))
+                }
+            };
+            match digit.to_digit(16) {
+                Some(digit) => code = code * 16 + digit,
+                None => {
+                    return Err(ParseError::new(
+                        digit_byte_offset,
+                        ParseErrorKind::InvalidUnicodeEscape,
+                    ))
+                }
+            }
+        }
+
+        match (high_surrogate.take(), code) {
+            (None, 0xD800..=0xDBFF) => high_surrogate = Some(byte_offset),
+            (None, 0xDC00..=0xDFFF) => return Err(unpaired_surrogate(byte_offset)),
+            (None, _) => {}
+            (Some(_), 0xDC00..=0xDFFF) => {}
+            (Some(surrogate_byte_offset), _) => {
+                return Err(unpaired_surrogate(surrogate_byte_offset))
+            }
+        }
+    }
+
+    Err(ParseError::new(
+        input_size,
+        ParseErrorKind::UnterminatedString,
+    ))
+}
+
+/// Validates a JSON5 escape sequence other than `\uXXXX`. `escape` is the character after the
+/// backslash, at `byte_offset`.
+fn scan_json5_escape<I: Iterator<Item = (usize, char)>>(
+    iter: &mut I,
+    input_size: usize,
+    byte_offset: usize,
+    escape: char,
+) -> Result<(), ParseError> {
+    match escape {
+        'x' => {
+            for _ in 0..2 {
+                match iter.next() {
+                    Some((_, digit)) if digit.is_ascii_hexdigit() => {}
+                    Some((digit_byte_offset, _)) => {
+                        return Err(ParseError::new(
+                            digit_byte_offset,
+                            ParseErrorKind::InvalidHexEscape,
+                        ))
+                    }
+                    None => {
+                        return Err(ParseError::new(
+                            input_size,
+                            ParseErrorKind::UnterminatedString,
+                        ))
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        '1'..='9' => Err(ParseError::new(byte_offset, ParseErrorKind::InvalidEscape)),
+
+        // Single character escapes, line continuations, and characters escaping themselves.
+        _ => Ok(()),
+    }
+}
+
+fn unpaired_surrogate(byte_offset: usize) -> ParseError {
+    ParseError::new(byte_offset, ParseErrorKind::UnpairedSurrogate)
+}
+
+/// Decodes escape sequences in a string validated by [scan_string]. The string should not
+/// include the quotes.
+pub(crate) fn unescape(str: &str) -> String {
+    let mut decoded = String::with_capacity(str.len());
+    let mut chars = str.chars();
+
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            decoded.push(char);
+            continue;
+        }
+
+        match chars.next() {
+            Some('b') => decoded.push('\u{8}'),
+            Some('f') => decoded.push('\u{c}'),
+            Some('n') => decoded.push('\n'),
+            Some('r') => decoded.push('\r'),
+            Some('t') => decoded.push('\t'),
+            Some('v') => decoded.push('\u{b}'),
+            Some('0') => decoded.push('\0'),
+            Some('x') => {
+                let code = decode_hex(&mut chars, 2);
+                decoded.push(char::from_u32(code).unwrap());
+            }
+            // Line continuations.
+            Some('\n' | '\u{2028}' | '\u{2029}') => {}
+            Some('\r') => {
+                // `\r\n` is a single line terminator.
+                let rest = chars.as_str();
+                chars = rest.strip_prefix('\n').unwrap_or(rest).chars();
+            }
+            Some('u') => {
+                let code = decode_hex(&mut chars, 4);
+                if (0xD800..=0xDBFF).contains(&code) {
+                    // Validated by `scan_string`: a high surrogate is followed by `\u` and a low
+                    // surrogate.
+                    chars.next();
+                    chars.next();
+                    let low = decode_hex(&mut chars, 4);
+                    let code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00);
+                    decoded.push(char::from_u32(code).unwrap());
+                } else {
+                    decoded.push(char::from_u32(code).unwrap());
+                }
+            }
+            // '"', '\\', '/', and characters escaping themselves in JSON5
+            Some(other) => decoded.push(other),
+            None => {}
+        }
+    }
+
+    decoded
+}
+
+/// Copies a string from the input, decoding escape sequences when the string has any.
+pub(crate) fn decode_string(str: &str, has_escapes: bool) -> String {
+    if has_escapes {
+        unescape(str)
+    } else {
+        str.to_string()
+    }
+}
+
+fn decode_hex(chars: &mut std::str::Chars, digits: usize) -> u32 {
+    let mut code: u32 = 0;
+    for _ in 0..digits {
+        code = code * 16 + chars.next().unwrap().to_digit(16).unwrap();
+    }
+    code
+}
+
+#[test]
+fn unescape_test() {
+    assert_eq!(unescape(r#"a\"b"#), "a\"b");
+    assert_eq!(unescape(r#"\\\/\b\f\n\r\t"#), "\\/\u{8}\u{c}\n\r\t");
+    assert_eq!(unescape(r#"\u00e9A"#), "\u{e9}A");
+    assert_eq!(unescape(r#"\ud83d\ude00"#), "\u{1f600}");
+    assert_eq!(unescape(r#"\'\x41\v\0\q"#), "'A\u{b}\0q");
+    assert_eq!(unescape("a\\\nb\\\r\nc"), "abc");
+}
@@ -1,32 +1,106 @@
+use crate::{ParseError, Span};
+
 /// A parse event, with location of the event in the input.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 pub struct ParseEvent {
     pub kind: ParseEventKind,
     pub byte_offset: usize,
+
+    /// Location of the token of the event. For strings the span includes the quotes, while
+    /// `byte_offset` is after the opening quote. Errors, document events, and closing brackets
+    /// missing at the end of input with
+    /// [ParseOptions::recover_errors][crate::ParseOptions::recover_errors] have empty spans.
+    pub span: Span,
 }
 
 /// Details of a parse event.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 pub enum ParseEventKind {
     StartObject,
     EndObject,
     StartArray,
     EndArray,
+
+    /// A non-negative integer.
     Int(u64),
+
+    /// A negative integer.
+    NegInt(i64),
+
+    /// A number with a fraction or exponent, or `-0`.
+    Float(f64),
+
+    /// A number that is not parsed, with [ParseOptions::lossless_numbers][crate::ParseOptions::lossless_numbers].
+    Number {
+        /// Size of the number text.
+        size_in_bytes: usize,
+    },
+
     Str {
         /// Size of the string, not including the double quotes.
         size_in_bytes: usize,
+
+        /// Whether the string has escape sequences. When this is `true` the string needs to be
+        /// decoded, the input slice is not the string value.
+        has_escapes: bool,
     },
     Bool(bool),
     Null,
     Comment {
-        /// Size of the comment, including the "//" a the beginning and newline at the end.
+        /// Size of the comment, including the comment delimiters. Line comments include the newline
+        /// at the end, unless the comment is terminated by the end of input.
         size_in_bytes: usize,
+
+        kind: CommentKind,
     },
+
+    /// Start of a document in a multi-document input, at the first token of the document. Only
+    /// generated by [parse_documents][crate::parse_documents].
+    StartDocument,
+
+    /// End of a document in a multi-document input, at the end of the document's text. Only
+    /// generated by [parse_documents][crate::parse_documents].
+    EndDocument,
+
+    /// A parse error, with [ParseOptions::recover_errors][crate::ParseOptions::recover_errors].
+    /// In an array, or after an object key, the error takes the place of a value.
+    Error(ParseError),
+}
+
+/// Syntax of a comment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentKind {
+    /// A `// ...` comment.
+    Line,
+
+    /// A `/* ... */` comment.
+    Block,
+
+    /// A `# ...` comment.
+    Hash,
 }
 
 impl ParseEvent {
-    pub(crate) fn new(byte_offset: usize, kind: ParseEventKind) -> ParseEvent {
-        ParseEvent { byte_offset, kind }
+    /// An event at the start of `span`.
+    pub(crate) fn new(span: Span, kind: ParseEventKind) -> ParseEvent {
+        ParseEvent {
+            kind,
+            byte_offset: span.start,
+            span,
+        }
+    }
+
+    /// A [ParseEventKind::Str] event for the string at `span`. Quoted strings are reported after
+    /// the opening quote.
+    pub(crate) fn str(span: Span, quoted: bool, has_escapes: bool) -> ParseEvent {
+        let quote_size = if quoted { 1 } else { 0 };
+        ParseEvent {
+            kind: ParseEventKind::Str {
+                size_in_bytes: span.end - span.start - 2 * quote_size,
+                has_escapes,
+            },
+            byte_offset: span.start + quote_size,
+            span,
+        }
     }
 }
@@ -1,47 +1,247 @@
-use crate::ParseError;
+use crate::{CommentKind, ParseError, ParseEvent, ParseEventKind, Span};
+
+/// What to do after an [EventListener] callback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ControlFlow {
+    /// Keep parsing.
+    Continue,
+
+    /// Returned from [EventListener::handle_start_array] or [EventListener::handle_start_object]:
+    /// skip the rest of the array or object, including the closing bracket, without calling the
+    /// listener. Errors in the skipped value are checked as in
+    /// [EventParser::skip_value][crate::EventParser::skip_value]. Same as
+    /// [ControlFlow::Continue] when returned from the other callbacks.
+    SkipValue,
+
+    /// Stop parsing.
+    Stop,
+}
 
 /// The trait for push-based event parsing. Used by
 /// [parse_events_push][crate::event_push_parser::parse].
 ///
-/// Methods return a `bool` for whether to keep parsing.
+/// Methods return a [ControlFlow] for how to continue parsing. Listeners that make a result
+/// implement [FinishListener] too.
 ///
-/// Default implementations of the methods return `true` to continue parsing.
+/// Default implementations of the callbacks return [ControlFlow::Continue].
 pub trait EventListener {
-    fn handle_start_object(&mut self, _byte_offset: usize) -> bool {
-        true
+    fn handle_start_object(&mut self, _byte_offset: usize) -> ControlFlow {
+        ControlFlow::Continue
     }
 
-    fn handle_end_object(&mut self, _byte_offset: usize) -> bool {
-        true
+    fn handle_end_object(&mut self, _byte_offset: usize) -> ControlFlow {
+        ControlFlow::Continue
     }
 
-    fn handle_start_array(&mut self, _byte_offset: usize) -> bool {
-        true
+    fn handle_start_array(&mut self, _byte_offset: usize) -> ControlFlow {
+        ControlFlow::Continue
     }
 
-    fn handle_end_array(&mut self, _byte_offset: usize) -> bool {
-        true
+    fn handle_end_array(&mut self, _byte_offset: usize) -> ControlFlow {
+        ControlFlow::Continue
     }
 
-    fn handle_int(&mut self, _byte_offset: usize, _i: u64) -> bool {
-        true
+    /// Called with the location and size of the number text, and the value.
+    fn handle_int(&mut self, _byte_offset: usize, _size_in_bytes: usize, _i: u64) -> ControlFlow {
+        ControlFlow::Continue
     }
 
-    fn handle_str(&mut self, _byte_offset: usize, _size_in_bytes: usize) -> bool {
-        true
+    fn handle_neg_int(
+        &mut self,
+        _byte_offset: usize,
+        _size_in_bytes: usize,
+        _i: i64,
+    ) -> ControlFlow {
+        ControlFlow::Continue
     }
 
-    fn handle_bool(&mut self, _byte_offset: usize, _b: bool) -> bool {
-        true
+    fn handle_float(&mut self, _byte_offset: usize, _size_in_bytes: usize, _f: f64) -> ControlFlow {
+        ControlFlow::Continue
     }
 
-    fn handle_null(&mut self, _byte_offset: usize) -> bool {
-        true
+    /// Called for numbers with
+    /// [ParseOptions::lossless_numbers][crate::ParseOptions::lossless_numbers].
+    fn handle_number(&mut self, _byte_offset: usize, _size_in_bytes: usize) -> ControlFlow {
+        ControlFlow::Continue
     }
 
-    fn handle_comment(&mut self, _byte_offset: usize, _size_in_bytes: usize) -> bool {
-        true
+    /// Called with the location of a string, without the double quotes. When `has_escapes` is
+    /// `true` the string needs to be decoded. `quoted` is `false` for JSON5 identifier keys.
+    fn handle_str(
+        &mut self,
+        _byte_offset: usize,
+        _size_in_bytes: usize,
+        _has_escapes: bool,
+        _quoted: bool,
+    ) -> ControlFlow {
+        ControlFlow::Continue
+    }
+
+    fn handle_bool(&mut self, _byte_offset: usize, _b: bool) -> ControlFlow {
+        ControlFlow::Continue
+    }
+
+    fn handle_null(&mut self, _byte_offset: usize) -> ControlFlow {
+        ControlFlow::Continue
+    }
+
+    fn handle_comment(
+        &mut self,
+        _byte_offset: usize,
+        _size_in_bytes: usize,
+        _kind: CommentKind,
+    ) -> ControlFlow {
+        ControlFlow::Continue
+    }
+
+    /// Called by [ChunkedParser][crate::ChunkedParser] before `handle_str`, `handle_number` and
+    /// `handle_comment`, with the input text at the location and size of the event, as the
+    /// listener doesn't have the whole input. Strings are not decoded.
+    fn handle_text(&mut self, _text: &str) -> ControlFlow {
+        ControlFlow::Continue
     }
 
     fn handle_error(&mut self, _error: ParseError);
 }
+
+/// An [EventListener] that makes a result after parsing. [FinishListener::finish] is called by
+/// [parse_events_push][crate::event_push_parser::parse] and
+/// [ChunkedParser::finish][crate::ChunkedParser::finish] after the last callback, and they return
+/// its result.
+pub trait FinishListener: EventListener {
+    /// The result of parsing.
+    type Output;
+
+    /// The errors returned by [FinishListener::finish]. Usually includes the parse error passed
+    /// to [EventListener::handle_error]. A callback can fail by recording its error and returning
+    /// [ControlFlow::Stop].
+    type Error;
+
+    /// Returns the result of parsing, or the error.
+    fn finish(self) -> Result<Self::Output, Self::Error>;
+}
+
+/// A listener that takes the callbacks as [ParseEvent]s. [EventListener] is implemented for the
+/// handlers by making an event for each callback.
+pub(crate) trait EventHandler {
+    fn on_event(&mut self, event: ParseEvent) -> ControlFlow;
+
+    /// Called for [EventListener::handle_text].
+    fn on_text(&mut self, _text: &str) -> ControlFlow {
+        ControlFlow::Continue
+    }
+
+    /// Called for [EventListener::handle_error].
+    fn on_error(&mut self, error: ParseError);
+}
+
+impl<H: EventHandler> EventListener for H {
+    fn handle_start_object(&mut self, byte_offset: usize) -> ControlFlow {
+        self.on_event(ParseEvent::new(
+            Span::new(byte_offset, byte_offset + 1),
+            ParseEventKind::StartObject,
+        ))
+    }
+
+    fn handle_end_object(&mut self, byte_offset: usize) -> ControlFlow {
+        self.on_event(ParseEvent::new(
+            Span::new(byte_offset, byte_offset + 1),
+            ParseEventKind::EndObject,
+        ))
+    }
+
+    fn handle_start_array(&mut self, byte_offset: usize) -> ControlFlow {
+        self.on_event(ParseEvent::new(
+            Span::new(byte_offset, byte_offset + 1),
+            ParseEventKind::StartArray,
+        ))
+    }
+
+    fn handle_end_array(&mut self, byte_offset: usize) -> ControlFlow {
+        self.on_event(ParseEvent::new(
+            Span::new(byte_offset, byte_offset + 1),
+            ParseEventKind::EndArray,
+        ))
+    }
+
+    fn handle_int(&mut self, byte_offset: usize, size_in_bytes: usize, i: u64) -> ControlFlow {
+        self.on_event(ParseEvent::new(
+            Span::new(byte_offset, byte_offset + size_in_bytes),
+            ParseEventKind::Int(i),
+        ))
+    }
+
+    fn handle_neg_int(&mut self, byte_offset: usize, size_in_bytes: usize, i: i64) -> ControlFlow {
+        self.on_event(ParseEvent::new(
+            Span::new(byte_offset, byte_offset + size_in_bytes),
+            ParseEventKind::NegInt(i),
+        ))
+    }
+
+    fn handle_float(&mut self, byte_offset: usize, size_in_bytes: usize, f: f64) -> ControlFlow {
+        self.on_event(ParseEvent::new(
+            Span::new(byte_offset, byte_offset + size_in_bytes),
+            ParseEventKind::Float(f),
+        ))
+    }
+
+    fn handle_number(&mut self, byte_offset: usize, size_in_bytes: usize) -> ControlFlow {
+        self.on_event(ParseEvent::new(
+            Span::new(byte_offset, byte_offset + size_in_bytes),
+            ParseEventKind::Number { size_in_bytes },
+        ))
+    }
+
+    fn handle_str(
+        &mut self,
+        byte_offset: usize,
+        size_in_bytes: usize,
+        has_escapes: bool,
+        quoted: bool,
+    ) -> ControlFlow {
+        let quote_size = if quoted { 1 } else { 0 };
+        let span = Span::new(
+            byte_offset - quote_size,
+            byte_offset + size_in_bytes + quote_size,
+        );
+        self.on_event(ParseEvent::str(span, quoted, has_escapes))
+    }
+
+    fn handle_bool(&mut self, byte_offset: usize, b: bool) -> ControlFlow {
+        let keyword = if b { "true" } else { "false" };
+        self.on_event(ParseEvent::new(
+            Span::new(byte_offset, byte_offset + keyword.len()),
+            ParseEventKind::Bool(b),
+        ))
+    }
+
+    fn handle_null(&mut self, byte_offset: usize) -> ControlFlow {
+        self.on_event(ParseEvent::new(
+            Span::new(byte_offset, byte_offset + "null".len()),
+            ParseEventKind::Null,
+        ))
+    }
+
+    fn handle_comment(
+        &mut self,
+        byte_offset: usize,
+        size_in_bytes: usize,
+        kind: CommentKind,
+    ) -> ControlFlow {
+        self.on_event(ParseEvent::new(
+            Span::new(byte_offset, byte_offset + size_in_bytes),
+            ParseEventKind::Comment {
+                size_in_bytes,
+                kind,
+            },
+        ))
+    }
+
+    fn handle_text(&mut self, text: &str) -> ControlFlow {
+        self.on_text(text)
+    }
+
+    fn handle_error(&mut self, error: ParseError) {
+        self.on_error(error)
+    }
+}
@@ -1,8 +1,23 @@
-use crate::{ParseError, ParseEvent, ParseEventKind};
+use crate::comment::{is_comment_start, scan_comment};
+use crate::error::{
+    Token, COLON, COMMA_OR_END_ARRAY, COMMA_OR_END_OBJECT, END_OF_INPUT, KEY, KEY_OR_END_OBJECT,
+    VALUE, VALUE_OR_END_ARRAY,
+};
+use crate::escape::scan_string;
+use crate::identifier::{is_identifier_start, scan_identifier};
+use crate::number::{is_number_start, scan_number};
+use crate::skip::skip_containers;
+use crate::whitespace::{check_whitespace, is_whitespace};
+use crate::{Dialect, ParseError, ParseErrorKind, ParseEvent, ParseEventKind, ParseOptions, Span};
 
 /// Parses input to [ParseEvent]s.
-pub fn parse_events(input: &str) -> EventParser {
-    EventParser::new(input)
+pub fn parse_events(input: &str) -> EventParser<'_> {
+    EventParser::new(input, ParseOptions::default())
+}
+
+/// Parses input to [ParseEvent]s, with the given options.
+pub fn parse_events_with_options(input: &str, options: ParseOptions) -> EventParser<'_> {
+    EventParser::new(input, options)
 }
 
 /// A parser that generates [ParseEvent]s.
@@ -10,18 +25,82 @@ pub fn parse_events(input: &str) -> EventParser {
 pub struct EventParser<'a> {
     input: &'a str,
     byte_offset: usize,
-    container_stack: Vec<Container>,
+    /// Containers the parser is in, with byte offsets of the opening brackets.
+    container_stack: Vec<(Container, usize)>,
     state: ParserState,
+    options: ParseOptions,
+    /// Stop after the top-level value, without parsing the rest of the input.
+    stop_after_value: bool,
 }
 
 impl<'a> EventParser<'a> {
-    fn new(input: &'a str) -> EventParser<'a> {
+    fn new(input: &'a str, options: ParseOptions) -> EventParser<'a> {
+        EventParser::new_document(input, 0, options, false)
+    }
+
+    /// Makes a parser for a document starting at `byte_offset` in a multi-document input. When
+    /// `stop_after_value` is `true` the input after the document's value is left to the caller.
+    pub(crate) fn new_document(
+        input: &'a str,
+        byte_offset: usize,
+        options: ParseOptions,
+        stop_after_value: bool,
+    ) -> EventParser<'a> {
         EventParser {
             input,
-            byte_offset: 0,
+            byte_offset,
             container_stack: vec![],
             state: ParserState::TopLevel,
+            options,
+            stop_after_value,
+        }
+    }
+
+    /// Byte offset of the input that is not parsed yet.
+    pub(crate) fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+
+    /// Whether the whole input is parsed.
+    pub(crate) fn is_at_end(&self) -> bool {
+        self.byte_offset == self.input.len()
+    }
+
+    /// Skips the next value without generating events for it, and returns its location, as in
+    /// [SpannedJson::span][crate::SpannedJson::span]. Comments before the value are skipped.
+    ///
+    /// Returns `None` without skipping anything when the parser doesn't expect a value next:
+    /// before an object key or a closing bracket, and after the top-level value. To skip an
+    /// object member, call after the key event.
+    ///
+    /// Arrays and objects are skipped by scanning for the closing bracket. Strings, comments and
+    /// bracket nesting are checked, other errors in the value are not reported. After an error
+    /// the parser doesn't generate more events.
+    pub fn skip_value(&mut self) -> Result<Option<Span>, ParseError> {
+        let result = self.skip_value_();
+        if result.is_err() {
+            self.stop();
+        }
+        result
+    }
+
+    /// Skips the rest of the innermost array or object without generating events for it,
+    /// including the closing bracket, and returns the location of the array or object. Returns
+    /// `None` when the parser is not in an array or object.
+    ///
+    /// Errors are checked as in [EventParser::skip_value].
+    pub fn skip_rest_of_container(&mut self) -> Result<Option<Span>, ParseError> {
+        let start = match self.container_stack.last() {
+            Some((_, byte_offset)) => *byte_offset,
+            None => return Ok(None),
+        };
+        let result = self.skip_containers(self.container_stack.len() - 1);
+        if let Err(error) = result {
+            self.stop();
+            return Err(error);
         }
+        self.update_state();
+        Ok(Some(Span::new(start, self.byte_offset)))
     }
 }
 
@@ -42,27 +121,54 @@ enum ParserState {
     /// Parsing an object, parse another element on ',', or finish the array on '}'.
     ObjectExpectComma,
 
-    /// Parsing an object, parse the first element, or finish the array on ']'.
+    /// Parsing an object, parse the first element, or finish the object on '}'.
     ObjectExpectKeyValue,
 
+    /// Parsing an object and we've just parsed a ',', parse the next element. In JSON5, finish the
+    /// object on '}'.
+    ObjectExpectKey,
+
     /// Parsing an object and we've just parsed a key, expect ':'.
     ObjectExpectColon,
 
     /// Parsing an array, parse another element on ',', or finish the array on ']'.
     ArrayExpectComma,
+
+    /// Parsing an array and we've just parsed a ',', parse the next element. In JSON5, finish the
+    /// array on ']'.
+    ArrayExpectValue,
+
+    /// Recovering from an error at the end of input, finish the open arrays and objects.
+    CloseContainers,
 }
 
 impl<'a> Iterator for EventParser<'a> {
     type Item = Result<ParseEvent, ParseError>;
 
     fn next(&mut self) -> Option<Self::Item> {
+        match self.next_event() {
+            Some(Err(error)) if self.options.recover_errors => Some(Ok(self.recover(error))),
+            other => other,
+        }
+    }
+}
+
+impl<'a> EventParser<'a> {
+    fn next_event(&mut self) -> Option<Result<ParseEvent, ParseError>> {
         match self.state {
-            ParserState::TopLevel => self.top_level(),
+            ParserState::TopLevel => match self.container_stack.last() {
+                // Just parsed a '['.
+                Some((Container::Array, _)) => self.top_level(VALUE_OR_END_ARRAY),
+                _ => self.top_level(VALUE),
+            },
             ParserState::Done => self.done(),
             ParserState::ObjectExpectComma => self.object_expect_comma(),
-            ParserState::ObjectExpectKeyValue => self.object_expect_key_value(),
+            ParserState::ObjectExpectKeyValue => self.object_key(true),
+            ParserState::ObjectExpectKey => self.object_key(self.json5()),
             ParserState::ObjectExpectColon => self.object_expect_colon(),
             ParserState::ArrayExpectComma => self.array_expect_comma(),
+            ParserState::ArrayExpectValue => self.array_expect_value(),
+            ParserState::CloseContainers => self.close_containers(),
         }
     }
 }
@@ -78,7 +184,8 @@ macro_rules! skip_trivia {
 }
 
 impl<'a> EventParser<'a> {
-    fn top_level(&mut self) -> Option<Result<ParseEvent, ParseError>> {
+    /// Parse a value. `expected` are the tokens that are valid in the current state, for errors.
+    fn top_level(&mut self, expected: &'static [Token]) -> Option<Result<ParseEvent, ParseError>> {
         debug_assert!(self.byte_offset <= self.input.len());
         skip_trivia!(self);
         let mut input = self.input[self.byte_offset..].chars().peekable();
@@ -88,18 +195,22 @@ impl<'a> EventParser<'a> {
                 let loc = self.byte_offset;
                 self.byte_offset += 1;
                 self.state = ParserState::TopLevel;
-                self.container_stack.push(Container::Array);
-                Some(Ok(ParseEvent::new(loc, ParseEventKind::StartArray)))
+                self.container_stack.push((Container::Array, loc));
+                Some(Ok(ParseEvent::new(
+                    self.span(loc),
+                    ParseEventKind::StartArray,
+                )))
             }
 
-            Some(']') => {
-                if let Err(err) = self.pop_array() {
-                    return Some(Err(err));
-                }
+            Some(']') if expected == VALUE_OR_END_ARRAY => {
+                self.container_stack.pop();
                 let loc = self.byte_offset;
                 self.byte_offset += 1;
                 self.update_state();
-                Some(Ok(ParseEvent::new(loc, ParseEventKind::EndArray)))
+                Some(Ok(ParseEvent::new(
+                    self.span(loc),
+                    ParseEventKind::EndArray,
+                )))
             }
 
             Some('{') => {
@@ -107,8 +218,11 @@ impl<'a> EventParser<'a> {
                 let loc = self.byte_offset;
                 self.byte_offset += 1;
                 self.state = ParserState::ObjectExpectKeyValue;
-                self.container_stack.push(Container::Object);
-                Some(Ok(ParseEvent::new(loc, ParseEventKind::StartObject)))
+                self.container_stack.push((Container::Object, loc));
+                Some(Ok(ParseEvent::new(
+                    self.span(loc),
+                    ParseEventKind::StartObject,
+                )))
             }
 
             Some('t') => {
@@ -120,13 +234,16 @@ impl<'a> EventParser<'a> {
                     let loc = self.byte_offset;
                     self.byte_offset += 4;
                     self.update_state();
-                    return Some(Ok(ParseEvent::new(loc, ParseEventKind::Bool(true))));
+                    return Some(Ok(ParseEvent::new(
+                        self.span(loc),
+                        ParseEventKind::Bool(true),
+                    )));
                 }
 
-                Some(Err(ParseError {
-                    byte_offset: self.byte_offset,
-                    reason: "unexpected keyword",
-                }))
+                Some(Err(ParseError::new(
+                    self.byte_offset,
+                    ParseErrorKind::InvalidKeyword,
+                )))
             }
 
             Some('f') => {
@@ -139,13 +256,16 @@ impl<'a> EventParser<'a> {
                     let loc = self.byte_offset;
                     self.byte_offset += 5;
                     self.update_state();
-                    return Some(Ok(ParseEvent::new(loc, ParseEventKind::Bool(false))));
+                    return Some(Ok(ParseEvent::new(
+                        self.span(loc),
+                        ParseEventKind::Bool(false),
+                    )));
                 }
 
-                Some(Err(ParseError {
-                    byte_offset: self.byte_offset,
-                    reason: "unexpected keyword",
-                }))
+                Some(Err(ParseError::new(
+                    self.byte_offset,
+                    ParseErrorKind::InvalidKeyword,
+                )))
             }
 
             Some('n') => {
@@ -157,80 +277,55 @@ impl<'a> EventParser<'a> {
                     let loc = self.byte_offset;
                     self.byte_offset += 4;
                     self.update_state();
-                    return Some(Ok(ParseEvent::new(loc, ParseEventKind::Null)));
+                    return Some(Ok(ParseEvent::new(self.span(loc), ParseEventKind::Null)));
                 }
 
-                Some(Err(ParseError {
-                    byte_offset: self.byte_offset,
-                    reason: "unexpected keyword",
-                }))
+                Some(Err(ParseError::new(
+                    self.byte_offset,
+                    ParseErrorKind::InvalidKeyword,
+                )))
             }
 
-            Some(c) if c.is_ascii_digit() => {
+            Some(c) if is_number_start(c, &self.options) => {
                 let loc = self.byte_offset;
-                input.next();
-                self.byte_offset += 1;
-
-                let mut i: u64 = u64::from((c as u8) - b'0');
-
-                while let Some(next) = input.peek().copied() {
-                    if !next.is_ascii_digit() {
-                        break;
+                let mut chars = self.input[loc..]
+                    .char_indices()
+                    .map(|(byte_idx, char)| (loc + byte_idx, char))
+                    .peekable();
+                chars.next(); // consume peeked character
+                match scan_number(&mut chars, self.input, loc, c, &self.options) {
+                    Ok((end, number)) => {
+                        self.byte_offset = end;
+                        self.update_state();
+                        Some(Ok(ParseEvent::new(self.span(loc), number.into())))
                     }
-
-                    // Consume the digit.
-                    self.byte_offset += 1;
-                    input.next();
-
-                    // Ignore overflows for the purposes of this post.
-                    i *= 10;
-                    i += u64::from((next as u8) - b'0');
+                    Err(err) => Some(Err(err)),
                 }
-
-                self.update_state();
-
-                Some(Ok(ParseEvent::new(loc, ParseEventKind::Int(i))))
             }
 
-            Some('"') => {
-                self.byte_offset += 1;
+            Some(quote @ ('"' | '\'')) if quote == '"' || self.json5() => {
                 let loc = self.byte_offset;
+                self.byte_offset += 1;
                 self.update_state();
-                match self.skip_string() {
-                    Ok(()) => {
-                        let after_string = self.byte_offset;
-                        Some(Ok(ParseEvent::new(
-                            loc,
-                            ParseEventKind::Str {
-                                size_in_bytes: after_string - loc - 1,
-                            },
-                        )))
-                    }
+                match self.skip_string(quote) {
+                    Ok(has_escapes) => Some(Ok(ParseEvent::str(self.span(loc), true, has_escapes))),
                     Err(err) => Some(Err(err)),
                 }
             }
 
-            Some(_) => Some(Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "unexpected character",
-            })),
-
-            None => Some(Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "unexpected end of input",
-            })),
+            _ => Some(Err(self.unexpected(expected))),
         }
     }
 
     fn done(&mut self) -> Option<Result<ParseEvent, ParseError>> {
+        if self.stop_after_value {
+            return None;
+        }
         skip_trivia!(self);
         if self.byte_offset == self.input.len() {
             None
         } else {
-            Some(Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "trailing characters",
-            }))
+            Some(Err(self.unexpected(END_OF_INPUT)))
         }
     }
 
@@ -239,72 +334,91 @@ impl<'a> EventParser<'a> {
         match self.input[self.byte_offset..].chars().next() {
             Some(',') => {
                 self.byte_offset += 1;
-                self.state = ParserState::TopLevel;
+                self.state = ParserState::ArrayExpectValue;
                 self.next()
             }
 
             Some(']') => {
-                if let Err(err) = self.pop_array() {
-                    return Some(Err(err));
-                }
+                self.container_stack.pop();
                 let loc = self.byte_offset;
                 self.byte_offset += 1;
                 self.update_state();
-                Some(Ok(ParseEvent::new(loc, ParseEventKind::EndArray)))
+                Some(Ok(ParseEvent::new(
+                    self.span(loc),
+                    ParseEventKind::EndArray,
+                )))
             }
 
-            Some(_) => Some(Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "unexpected character while parsing array",
-            })),
-
-            None => Some(Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "unexpected end of input while parsing array",
-            })),
+            _ => Some(Err(self.unexpected(COMMA_OR_END_ARRAY))),
         }
     }
 
-    fn object_expect_key_value(&mut self) -> Option<Result<ParseEvent, ParseError>> {
+    fn array_expect_value(&mut self) -> Option<Result<ParseEvent, ParseError>> {
         skip_trivia!(self);
         match self.input[self.byte_offset..].chars().next() {
-            Some('}') => {
-                if let Err(err) = self.pop_map() {
-                    return Some(Err(err));
-                }
+            Some(']') if self.json5() => {
+                self.container_stack.pop();
                 let loc = self.byte_offset;
                 self.byte_offset += 1;
                 self.update_state();
-                Some(Ok(ParseEvent::new(loc, ParseEventKind::EndObject)))
+                Some(Ok(ParseEvent::new(
+                    self.span(loc),
+                    ParseEventKind::EndArray,
+                )))
             }
 
-            Some('"') => {
+            Some(']') => Some(Err(self.unexpected(VALUE))),
+
+            _ if self.json5() => self.top_level(VALUE_OR_END_ARRAY),
+
+            _ => self.top_level(VALUE),
+        }
+    }
+
+    /// Parse an object key, or finish the object on '}' when `allow_end` is `true`.
+    fn object_key(&mut self, allow_end: bool) -> Option<Result<ParseEvent, ParseError>> {
+        skip_trivia!(self);
+        match self.input[self.byte_offset..].chars().next() {
+            Some('}') if allow_end => {
+                self.container_stack.pop();
+                let loc = self.byte_offset;
                 self.byte_offset += 1;
+                self.update_state();
+                Some(Ok(ParseEvent::new(
+                    self.span(loc),
+                    ParseEventKind::EndObject,
+                )))
+            }
+
+            Some(quote @ ('"' | '\'')) if quote == '"' || self.json5() => {
                 let loc = self.byte_offset;
-                match self.skip_string() {
-                    Ok(()) => {
-                        let after_string = self.byte_offset;
+                self.byte_offset += 1;
+                match self.skip_string(quote) {
+                    Ok(has_escapes) => {
                         self.state = ParserState::ObjectExpectColon;
-                        Some(Ok(ParseEvent::new(
-                            loc,
-                            ParseEventKind::Str {
-                                size_in_bytes: after_string - loc - 1,
-                            },
-                        )))
+                        Some(Ok(ParseEvent::str(self.span(loc), true, has_escapes)))
                     }
                     Err(err) => Some(Err(err)),
                 }
             }
 
-            Some(_) => Some(Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "unexpected character while parsing object",
-            })),
+            Some(c) if self.json5() && is_identifier_start(c) => {
+                let loc = self.byte_offset;
+                let mut chars = self.input[loc..]
+                    .char_indices()
+                    .map(|(byte_idx, char)| (loc + byte_idx, char))
+                    .peekable();
+                chars.next(); // consume peeked character
+                self.byte_offset = scan_identifier(&mut chars, self.input.len());
+                self.state = ParserState::ObjectExpectColon;
+                Some(Ok(ParseEvent::str(self.span(loc), false, false)))
+            }
 
-            None => Some(Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "unexpected end of input while parsing object",
-            })),
+            _ => Some(Err(self.unexpected(if allow_end {
+                KEY_OR_END_OBJECT
+            } else {
+                KEY
+            }))),
         }
     }
 
@@ -317,15 +431,7 @@ impl<'a> EventParser<'a> {
                 self.next()
             }
 
-            Some(_) => Some(Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "unexpected character while parsing object",
-            })),
-
-            None => Some(Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "unexpected end of input while parsing object",
-            })),
+            _ => Some(Err(self.unexpected(COLON))),
         }
     }
 
@@ -334,123 +440,274 @@ impl<'a> EventParser<'a> {
         match self.input[self.byte_offset..].chars().next() {
             Some(',') => {
                 self.byte_offset += 1;
-                self.state = ParserState::ObjectExpectKeyValue;
+                self.state = ParserState::ObjectExpectKey;
                 self.next()
             }
 
             Some('}') => {
-                if let Err(err) = self.pop_map() {
-                    return Some(Err(err));
-                }
+                self.container_stack.pop();
                 let loc = self.byte_offset;
                 self.byte_offset += 1;
                 self.update_state();
-                Some(Ok(ParseEvent::new(loc, ParseEventKind::EndObject)))
+                Some(Ok(ParseEvent::new(
+                    self.span(loc),
+                    ParseEventKind::EndObject,
+                )))
             }
 
-            Some(_) => Some(Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "unexpected character while parsing object",
-            })),
-
-            None => Some(Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "unexpected end of input while parsing object",
-            })),
+            _ => Some(Err(self.unexpected(COMMA_OR_END_OBJECT))),
         }
     }
 
-    /// Skip until after the end of a string. Expects the opening double colon to be consumed.
-    fn skip_string(&mut self) -> Result<(), ParseError> {
-        self.skip_trivia()?;
-        for char in self.input[self.byte_offset..].chars() {
+    fn close_containers(&mut self) -> Option<Result<ParseEvent, ParseError>> {
+        let kind = match self.container_stack.pop() {
+            Some((Container::Array, _)) => ParseEventKind::EndArray,
+            Some((Container::Object, _)) => ParseEventKind::EndObject,
+            None => {
+                self.state = ParserState::Done;
+                return None;
+            }
+        };
+        let end = self.input.len();
+        Some(Ok(ParseEvent::new(Span::new(end, end), kind)))
+    }
+
+    /// Continue parsing after `error`, with [ParseOptions::recover_errors]. Returns the error
+    /// event.
+    ///
+    /// An error where a value is expected takes the place of the value. An error where an object
+    /// key is expected skips the key-value pair. Parsing continues at the next `,` or closing
+    /// bracket of the current container, expecting a `,` or the closing bracket, or after the next
+    /// newline, expecting a value or object key, or the closing bracket.
+    fn recover(&mut self, error: ParseError) -> ParseEvent {
+        let byte_offset = error.byte_offset;
+
+        if byte_offset == self.input.len() {
+            self.byte_offset = byte_offset;
+            self.state = ParserState::CloseContainers;
+            return ParseEvent::new(
+                Span::new(byte_offset, byte_offset),
+                ParseEventKind::Error(error),
+            );
+        }
+
+        match self.state {
+            ParserState::TopLevel
+            | ParserState::ArrayExpectValue
+            | ParserState::ObjectExpectColon => self.update_state(),
+            ParserState::ObjectExpectKeyValue | ParserState::ObjectExpectKey => {
+                self.state = ParserState::ObjectExpectComma
+            }
+            ParserState::Done
+            | ParserState::ObjectExpectComma
+            | ParserState::ArrayExpectComma
+            | ParserState::CloseContainers => {}
+        }
+
+        // The states after recovery accept `,`, newlines, and the closing bracket, so the parser
+        // always makes progress.
+        let closing_bracket = match self.container_stack.last() {
+            Some((Container::Array, _)) => ']',
+            Some((Container::Object, _)) => '}',
+            None => {
+                // Nothing to resynchronize with at the top level.
+                self.byte_offset = self.input.len();
+                return ParseEvent::new(
+                    Span::new(byte_offset, byte_offset),
+                    ParseEventKind::Error(error),
+                );
+            }
+        };
+        self.byte_offset = match self.input[byte_offset..].find([',', '\n', closing_bracket]) {
+            Some(idx) => byte_offset + idx,
+            None => self.input.len(),
+        };
+        if self.input[self.byte_offset..].starts_with('\n') {
+            // The next line starts a new element, as if the missing `,` was at the newline.
             self.byte_offset += 1;
-            if char == '"' {
-                return Ok(());
+            self.state = match closing_bracket {
+                ']' => ParserState::TopLevel,
+                _ => ParserState::ObjectExpectKeyValue,
+            };
+        }
+
+        ParseEvent::new(
+            Span::new(byte_offset, byte_offset),
+            ParseEventKind::Error(error),
+        )
+    }
+
+    fn skip_value_(&mut self) -> Result<Option<Span>, ParseError> {
+        loop {
+            while self.skip_trivia()?.is_some() {}
+            let next = self.input[self.byte_offset..].chars().next();
+            match self.state {
+                ParserState::ArrayExpectComma => match next {
+                    Some(',') => {
+                        self.byte_offset += 1;
+                        self.state = ParserState::ArrayExpectValue;
+                    }
+                    _ => return Ok(None),
+                },
+
+                ParserState::ObjectExpectColon => match next {
+                    Some(':') => {
+                        self.byte_offset += 1;
+                        self.state = ParserState::TopLevel;
+                    }
+                    _ => return Err(self.unexpected(COLON)),
+                },
+
+                ParserState::TopLevel | ParserState::ArrayExpectValue => {
+                    let start = self.byte_offset;
+                    match next {
+                        // Just parsed a '['.
+                        Some(']')
+                            if matches!(self.state, ParserState::TopLevel)
+                                && matches!(
+                                    self.container_stack.last(),
+                                    Some((Container::Array, _))
+                                ) =>
+                        {
+                            return Ok(None)
+                        }
+
+                        // After a trailing comma.
+                        Some(']')
+                            if self.json5()
+                                && matches!(self.state, ParserState::ArrayExpectValue) =>
+                        {
+                            return Ok(None)
+                        }
+
+                        Some(bracket @ ('[' | '{')) => {
+                            let container = if bracket == '[' {
+                                Container::Array
+                            } else {
+                                Container::Object
+                            };
+                            self.container_stack.push((container, start));
+                            self.byte_offset += 1;
+                            self.skip_containers(self.container_stack.len() - 1)?;
+                        }
+
+                        _ => {
+                            if let Some(Err(error)) = self.next_event() {
+                                return Err(error);
+                            }
+                        }
+                    }
+                    self.update_state();
+                    return Ok(Some(Span::new(start, self.byte_offset)));
+                }
+
+                _ => return Ok(None),
             }
         }
-        Err(ParseError {
-            byte_offset: self.byte_offset,
-            reason: "unexpected end of input while parsing string",
-        })
+    }
+
+    /// Scans until the container stack has `depth` containers, without parsing the values.
+    fn skip_containers(&mut self, depth: usize) -> Result<(), ParseError> {
+        let start = self.byte_offset;
+        let mut chars = self.input[start..]
+            .char_indices()
+            .map(|(byte_idx, char)| (start + byte_idx, char))
+            .peekable();
+        self.byte_offset = skip_containers(
+            &mut chars,
+            self.input,
+            &mut self.container_stack,
+            depth,
+            &self.options,
+        )?;
+        Ok(())
+    }
+
+    /// Stops parsing after an error in [EventParser::skip_value].
+    fn stop(&mut self) {
+        self.byte_offset = self.input.len();
+        self.container_stack.clear();
+        self.state = ParserState::Done;
+    }
+
+    /// Skip until after the end of a string. Expects the opening quote to be consumed.
+    ///
+    /// Returns whether the string has escape sequences.
+    fn skip_string(&mut self, quote: char) -> Result<bool, ParseError> {
+        let start = self.byte_offset;
+        let mut chars = self.input[start..]
+            .char_indices()
+            .map(|(byte_idx, char)| (start + byte_idx, char));
+        let (closing_quote, has_escapes) =
+            scan_string(&mut chars, self.input.len(), quote, &self.options)?;
+        self.byte_offset = closing_quote + 1;
+        Ok(has_escapes)
+    }
+
+    fn json5(&self) -> bool {
+        self.options.dialect == Dialect::Json5
     }
 
     /// After parsing a value, update the parser state based on the current container.
     fn update_state(&mut self) {
         self.state = match self.container_stack.last() {
-            Some(Container::Array) => ParserState::ArrayExpectComma,
-            Some(Container::Object) => ParserState::ObjectExpectComma,
+            Some((Container::Array, _)) => ParserState::ArrayExpectComma,
+            Some((Container::Object, _)) => ParserState::ObjectExpectComma,
             None => ParserState::Done,
         };
     }
 
-    fn pop_map(&mut self) -> Result<(), ParseError> {
-        match self.container_stack.pop() {
-            Some(Container::Object) => Ok(()),
-
-            _ => Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "unexpected '}'",
-            }),
-        }
+    /// Span of the token from `start` to the current byte offset.
+    fn span(&self, start: usize) -> Span {
+        Span::new(start, self.byte_offset)
     }
 
-    fn pop_array(&mut self) -> Result<(), ParseError> {
-        match self.container_stack.pop() {
-            Some(Container::Array) => Ok(()),
-
-            _ => Err(ParseError {
-                byte_offset: self.byte_offset,
-                reason: "unexpected ']'",
-            }),
-        }
+    fn unexpected(&self, expected: &'static [Token]) -> ParseError {
+        let container_byte_offset = self
+            .container_stack
+            .last()
+            .map(|(_, byte_offset)| *byte_offset);
+        ParseError::unexpected(
+            self.input,
+            self.byte_offset,
+            expected,
+            container_byte_offset,
+        )
     }
 
-    fn skip_trivia(&mut self) -> Result<Option<ParseEvent>, ParseError> {
+    /// Skips whitespace until the next token or comment. Returns the comment event.
+    pub(crate) fn skip_trivia(&mut self) -> Result<Option<ParseEvent>, ParseError> {
         if self.byte_offset == self.input.len() {
             return Ok(None);
         }
-        let mut chars = self.input[self.byte_offset..].char_indices().peekable();
+        let start = self.byte_offset;
+        let mut chars = self.input[start..]
+            .char_indices()
+            .map(|(byte_idx, char)| (start + byte_idx, char))
+            .peekable();
         loop {
             match chars.peek().copied() {
-                Some((byte_idx, '/')) => {
-                    chars.next(); // consume peeked '/'
-                    match chars.next() {
-                        Some((_, '/')) => loop {
-                            match chars.next() {
-                                Some((newline_byte_idx, '\n')) => {
-                                    self.byte_offset += newline_byte_idx;
-                                    return Ok(Some(ParseEvent {
-                                        kind: ParseEventKind::Comment {
-                                            size_in_bytes: newline_byte_idx - byte_idx + 1,
-                                        },
-                                        byte_offset: byte_idx,
-                                    }));
-                                }
-                                Some(_) => {}
-                                None => {
-                                    return Err(ParseError {
-                                        byte_offset: byte_idx,
-                                        reason: "unterminated comment",
-                                    });
-                                }
-                            }
+                Some((byte_offset, c)) if is_comment_start(c, &self.options) => {
+                    chars.next(); // consume peeked character
+                    let (size_in_bytes, kind) =
+                        scan_comment(&mut chars, self.input.len(), byte_offset, c, &self.options)?;
+                    self.byte_offset = byte_offset + size_in_bytes;
+                    return Ok(Some(ParseEvent::new(
+                        Span::new(byte_offset, self.byte_offset),
+                        ParseEventKind::Comment {
+                            size_in_bytes,
+                            kind,
                         },
-                        _ => {
-                            return Err(ParseError {
-                                byte_offset: byte_idx,
-                                reason: "unexpected '/'",
-                            });
-                        }
-                    }
+                    )));
                 }
 
-                Some((_, c)) if c.is_ascii_whitespace() => {
+                Some((byte_offset, c)) if is_whitespace(c) => {
+                    check_whitespace(byte_offset, c, &self.options)?;
                     chars.next(); // consume peeked whitespace
                 }
 
-                Some((byte_idx, _)) => {
-                    self.byte_offset += byte_idx;
+                Some((byte_offset, _)) => {
+                    self.byte_offset = byte_offset;
                     return Ok(None);
                 }
 
@@ -465,8 +722,16 @@ impl<'a> EventParser<'a> {
 
 #[cfg(test)]
 fn collect_events(input: &str) -> (Vec<ParseEventKind>, Option<ParseError>) {
+    collect_events_with_options(input, ParseOptions::default())
+}
+
+#[cfg(test)]
+fn collect_events_with_options(
+    input: &str,
+    options: ParseOptions,
+) -> (Vec<ParseEventKind>, Option<ParseError>) {
     let mut events: Vec<ParseEventKind> = vec![];
-    for event in EventParser::new(input) {
+    for event in EventParser::new(input, options) {
         match event {
             Ok(event) => events.push(event.kind),
             Err(err) => return (events, Some(err)),
@@ -475,6 +740,14 @@ fn collect_events(input: &str) -> (Vec<ParseEventKind>, Option<ParseError>) {
     (events, None)
 }
 
+#[cfg(test)]
+fn recovery_options() -> ParseOptions {
+    ParseOptions {
+        recover_errors: true,
+        ..ParseOptions::default()
+    }
+}
+
 #[test]
 fn event_tests() {
     for (str, events) in crate::test_common::event_tests() {
@@ -484,3 +757,355 @@ fn event_tests() {
         assert_eq!(error, None);
     }
 }
+
+#[test]
+fn number_error_tests() {
+    for (str, byte_offset) in crate::test_common::number_error_tests() {
+        println!("Parsing {:?}", str);
+        let (_, error) = collect_events(&str);
+        assert_eq!(error.unwrap().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn overflow_error_tests() {
+    for (str, byte_offset) in crate::test_common::overflow_error_tests() {
+        println!("Parsing {:?}", str);
+        let (_, error) = collect_events(&str);
+        assert_eq!(error.unwrap().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn lossless_number_tests() {
+    let options = ParseOptions {
+        lossless_numbers: true,
+        ..crate::ParseOptions::default()
+    };
+    for (str, events) in crate::test_common::lossless_number_event_tests() {
+        println!("Parsing {:?}", str);
+        let (events_, error) = collect_events_with_options(&str, options);
+        assert_eq!(events_, events);
+        assert_eq!(error, None);
+    }
+}
+
+#[test]
+fn comment_option_tests() {
+    for (str, events) in crate::test_common::comment_option_event_tests() {
+        println!("Parsing {:?}", str);
+        let (events_, error) =
+            collect_events_with_options(&str, crate::test_common::comment_options());
+        assert_eq!(events_, events);
+        assert_eq!(error, None);
+    }
+}
+
+#[test]
+fn comment_error_tests() {
+    for (str, options, byte_offset) in crate::test_common::comment_error_tests() {
+        println!("Parsing {:?}", str);
+        let (_, error) = collect_events_with_options(&str, options);
+        assert_eq!(error.unwrap().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn comment_byte_offsets() {
+    let events = parse_events("[1, // a\n 2 /* b */]")
+        .map(|ev| ev.unwrap())
+        .filter(|ev| matches!(ev.kind, ParseEventKind::Comment { .. }))
+        .map(|ev| ev.byte_offset)
+        .collect::<Vec<_>>();
+    assert_eq!(events, vec![4, 12]);
+}
+
+#[test]
+fn escape_error_tests() {
+    for (str, byte_offset) in crate::test_common::escape_error_tests() {
+        println!("Parsing {:?}", str);
+        let (_, error) = collect_events(&str);
+        assert_eq!(error.unwrap().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn json5_tests() {
+    for (str, events) in crate::test_common::json5_event_tests() {
+        println!("Parsing {:?}", str);
+        let (events_, error) =
+            collect_events_with_options(&str, crate::test_common::json5_options());
+        assert_eq!(events_, events);
+        assert_eq!(error, None);
+    }
+}
+
+#[test]
+fn json5_only_tests() {
+    for (str, byte_offset) in crate::test_common::json5_only_tests() {
+        println!("Parsing {:?}", str);
+        let (_, error) = collect_events(&str);
+        assert_eq!(error.unwrap().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn json5_error_tests() {
+    for (str, byte_offset) in crate::test_common::json5_error_tests() {
+        println!("Parsing {:?}", str);
+        let (_, error) = collect_events_with_options(&str, crate::test_common::json5_options());
+        assert_eq!(error.unwrap().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn json5_byte_offsets() {
+    let events = parse_events_with_options(
+        "{ab: 'c', d: +0x1, e: NaN,}",
+        crate::test_common::json5_options(),
+    )
+    .map(|ev| ev.unwrap().byte_offset)
+    .collect::<Vec<_>>();
+    assert_eq!(events, vec![0, 1, 6, 10, 13, 19, 22, 26]);
+}
+
+#[test]
+fn strict_tests() {
+    for (str, events) in crate::test_common::event_tests() {
+        if events
+            .iter()
+            .any(|event| matches!(event, ParseEventKind::Comment { .. }))
+        {
+            continue;
+        }
+        println!("Parsing {:?}", str);
+        let (events_, error) =
+            collect_events_with_options(&str, crate::test_common::strict_options());
+        assert_eq!(events_, events);
+        assert_eq!(error, None);
+    }
+}
+
+#[test]
+fn strict_error_tests() {
+    for (str, byte_offset, kind) in crate::test_common::strict_error_tests() {
+        println!("Parsing {:?}", str);
+        let (_, error) = collect_events_with_options(&str, crate::test_common::strict_options());
+        assert_eq!(error, Some(ParseError { byte_offset, kind }));
+    }
+}
+
+#[test]
+fn unexpected_token_tests() {
+    for (str, error) in crate::test_common::unexpected_token_tests() {
+        println!("Parsing {:?}", str);
+        let (_, error_) = collect_events(&str);
+        assert_eq!(error_, Some(error));
+    }
+}
+
+#[test]
+fn recovery_events() {
+    use ParseEventKind::*;
+
+    let events = parse_events_with_options("[1 x, {\"a\": }", recovery_options())
+        .map(|event| {
+            let event = event.unwrap();
+            (event.byte_offset, event.kind)
+        })
+        .collect::<Vec<_>>();
+    let error = |byte_offset, found, expected, container_byte_offset| {
+        Error(ParseError::new(
+            byte_offset,
+            ParseErrorKind::UnexpectedToken {
+                found,
+                expected,
+                container_byte_offset,
+            },
+        ))
+    };
+    assert_eq!(
+        events,
+        vec![
+            (0, StartArray),
+            (1, Int(1)),
+            (3, error(3, Token::Char('x'), COMMA_OR_END_ARRAY, Some(0))),
+            (6, StartObject),
+            (
+                8,
+                Str {
+                    size_in_bytes: 1,
+                    has_escapes: false
+                }
+            ),
+            (12, error(12, Token::EndObject, VALUE, Some(6))),
+            (12, EndObject),
+            (
+                13,
+                error(13, Token::EndOfInput, COMMA_OR_END_ARRAY, Some(0))
+            ),
+            (13, EndArray),
+        ]
+    );
+}
+
+#[test]
+fn recovery_first_error_tests() {
+    // The first error is the error reported without recovery.
+    let inputs = crate::test_common::unexpected_token_tests()
+        .into_iter()
+        .map(|(str, _)| str)
+        .chain(
+            crate::test_common::number_error_tests()
+                .into_iter()
+                .chain(crate::test_common::overflow_error_tests())
+                .chain(crate::test_common::escape_error_tests())
+                .chain(crate::test_common::json5_only_tests())
+                .map(|(str, _)| str),
+        );
+    for str in inputs {
+        println!("Parsing {:?}", str);
+        let (_, error) = collect_events(&str);
+        let first_error = parse_events_with_options(&str, recovery_options())
+            .map(|event| event.unwrap())
+            .find_map(|event| match event.kind {
+                ParseEventKind::Error(error) => Some(error),
+                _ => None,
+            });
+        assert_eq!(first_error, error);
+    }
+}
+
+#[test]
+fn skip_value_tests() {
+    for (str, _) in crate::test_common::ast_tests() {
+        println!("Parsing {:?}", str);
+        let span = crate::parse_spanned_ast(&str).unwrap().span;
+        let mut parser = parse_events(&str);
+        assert_eq!(parser.skip_value(), Ok(Some(span)));
+        assert_eq!(parser.skip_value(), Ok(None));
+        assert_eq!(parser.next(), None);
+    }
+
+    let input = r#"[1, [2, {"a": "]"}], "x", []]"#;
+    let mut parser = parse_events(input);
+    assert_eq!(
+        parser.next().unwrap().unwrap().kind,
+        ParseEventKind::StartArray
+    );
+    assert_eq!(parser.skip_value(), Ok(Some(Span::new(1, 2))));
+    assert_eq!(parser.skip_value(), Ok(Some(Span::new(4, 19))));
+    assert!(matches!(
+        parser.next().unwrap().unwrap().kind,
+        ParseEventKind::Str { .. }
+    ));
+    assert_eq!(
+        parser.next().unwrap().unwrap().kind,
+        ParseEventKind::StartArray
+    );
+    assert_eq!(parser.skip_value(), Ok(None));
+    assert_eq!(
+        parser.next().unwrap().unwrap().kind,
+        ParseEventKind::EndArray
+    );
+    assert_eq!(parser.skip_value(), Ok(None));
+    assert_eq!(
+        parser.next().unwrap().unwrap().kind,
+        ParseEventKind::EndArray
+    );
+    assert_eq!(parser.next(), None);
+
+    // Object members are skipped after the key, comments are skipped.
+    let input = "{a: {'b': [1, /* ] */ 2]}, c: // x\n 'y', d: 3, }";
+    let mut parser = parse_events_with_options(input, crate::test_common::json5_options());
+    assert_eq!(
+        parser.next().unwrap().unwrap().kind,
+        ParseEventKind::StartObject
+    );
+    assert_eq!(parser.skip_value(), Ok(None));
+    assert!(matches!(
+        parser.next().unwrap().unwrap().kind,
+        ParseEventKind::Str { .. }
+    ));
+    assert_eq!(parser.skip_value(), Ok(Some(Span::new(4, 25))));
+    assert!(matches!(
+        parser.next().unwrap().unwrap().kind,
+        ParseEventKind::Str { .. }
+    ));
+    assert_eq!(parser.skip_value(), Ok(Some(Span::new(36, 39))));
+    assert_eq!(parser.skip_value(), Ok(None));
+    assert_eq!(parser.skip_rest_of_container(), Ok(Some(Span::new(0, 48))));
+    assert_eq!(parser.skip_rest_of_container(), Ok(None));
+    assert_eq!(parser.next(), None);
+}
+
+#[test]
+fn skip_rest_of_container_tests() {
+    let input = r#"{"a": [1, 2, [3]], "b": 4}"#;
+    let mut parser = parse_events(input);
+    for _ in 0..4 {
+        parser.next().unwrap().unwrap();
+    }
+    assert_eq!(parser.skip_rest_of_container(), Ok(Some(Span::new(6, 17))));
+    assert!(matches!(
+        parser.next().unwrap().unwrap().kind,
+        ParseEventKind::Str { .. }
+    ));
+    assert_eq!(parser.next().unwrap().unwrap().kind, ParseEventKind::Int(4));
+    assert_eq!(
+        parser.next().unwrap().unwrap().kind,
+        ParseEventKind::EndObject
+    );
+    assert_eq!(parser.next(), None);
+
+    // Trailing input is checked after skipping the top-level value.
+    let mut parser = parse_events("[1, 2] 3");
+    parser.next().unwrap().unwrap();
+    assert_eq!(parser.skip_rest_of_container(), Ok(Some(Span::new(0, 6))));
+    assert_eq!(parser.next().unwrap().unwrap_err().byte_offset, 7);
+}
+
+#[test]
+fn skip_value_error_tests() {
+    let tests = [
+        (r#"[1, [2, 3}]"#, 9),
+        (r#"[1, {"a": "b]"#, 13),
+        (r#"[1, "a\x"]"#, 7),
+        (r#"[1, [2 /* ]]"#, 7),
+        (r#"[1, [2,"#, 7),
+        (r#"[1, tru]"#, 4),
+    ];
+    for (str, byte_offset) in tests {
+        println!("Parsing {:?}", str);
+        let mut parser = parse_events_with_options(str, crate::test_common::comment_options());
+        parser.next().unwrap().unwrap();
+        parser.skip_value().unwrap();
+        assert_eq!(parser.skip_value().unwrap_err().byte_offset, byte_offset);
+        assert_eq!(parser.next(), None);
+    }
+
+    // A missing colon after a key.
+    let mut parser = parse_events(r#"{"a" 1}"#);
+    parser.next().unwrap().unwrap();
+    parser.next().unwrap().unwrap();
+    assert_eq!(parser.skip_value().unwrap_err().byte_offset, 5);
+    assert_eq!(parser.next(), None);
+
+    // A closing bracket after a trailing comma in JSON.
+    let mut parser = parse_events("[1, ]");
+    parser.next().unwrap().unwrap();
+    parser.skip_value().unwrap();
+    assert_eq!(parser.skip_value().unwrap_err().byte_offset, 4);
+
+    // In JSON5, closing brackets are only skipped after a trailing comma in an array.
+    let json5_options = crate::test_common::json5_options();
+    let mut parser = parse_events_with_options(r#"{"a": ]"#, json5_options);
+    parser.next().unwrap().unwrap();
+    parser.next().unwrap().unwrap();
+    assert_eq!(parser.skip_value().unwrap_err().byte_offset, 6);
+    assert_eq!(parser.next(), None);
+
+    let mut parser = parse_events_with_options("]", json5_options);
+    assert_eq!(parser.skip_value().unwrap_err().byte_offset, 0);
+    assert_eq!(parser.next(), None);
+}
@@ -1,107 +1,171 @@
-use crate::{EventListener, ParseError};
+use crate::comment::{is_comment_start, scan_comment};
+use crate::error::{
+    Token, COLON, COMMA_OR_END_ARRAY, COMMA_OR_END_OBJECT, END_OF_INPUT, KEY, KEY_OR_END_OBJECT,
+    VALUE, VALUE_OR_END_ARRAY,
+};
+use crate::escape::scan_string;
+use crate::event_parser::Container;
+use crate::identifier::{is_identifier_start, scan_identifier};
+use crate::number::{is_number_start, scan_number, Number};
+use crate::skip::skip_containers;
+use crate::whitespace::{check_whitespace, is_whitespace};
+use crate::{
+    ControlFlow, Dialect, EventListener, FinishListener, ParseError, ParseErrorKind, ParseOptions,
+};
 
 use std::iter::Peekable;
 use std::str::CharIndices;
 
-/// Parse input to events, call [EventListener] callbacks with the events.
-pub fn parse<L: EventListener>(input: &str, listener: &mut L) {
-    let mut iter = input.char_indices().peekable();
-    let input_size = input.len();
+/// Parse input to events, call [EventListener] callbacks with the events. Returns the result of
+/// [FinishListener::finish].
+pub fn parse<L: FinishListener>(input: &str, listener: L) -> Result<L::Output, L::Error> {
+    parse_with_options(input, listener, ParseOptions::default())
+}
 
-    if !parse_single(&mut iter, input_size, listener) {
+/// Parse input to events with the given options, call [EventListener] callbacks with the events.
+/// Returns the result of [FinishListener::finish].
+pub fn parse_with_options<L: FinishListener>(
+    input: &str,
+    mut listener: L,
+    options: ParseOptions,
+) -> Result<L::Output, L::Error> {
+    push_events(input, &mut listener, options);
+    listener.finish()
+}
+
+/// Like [parse_with_options], but doesn't finish the listener.
+pub(crate) fn push_events<L: EventListener>(input: &str, listener: &mut L, options: ParseOptions) {
+    let mut iter = input.char_indices().peekable();
+    if !parse_single(&mut iter, input, &options, listener, VALUE, None) {
         return;
     }
 
-    skip_trivia(&mut iter, listener);
+    if !skip_trivia(&mut iter, input, &options, listener) {
+        return;
+    }
 
     if let Some((byte_offset, _)) = iter.next() {
         // We should return the parsed object with this error, but it's OK for the purposes of this
         // post.
-        listener.handle_error(ParseError {
+        listener.handle_error(ParseError::unexpected(
+            input,
             byte_offset,
-            reason: "trailing characters after parsing",
-        });
+            END_OF_INPUT,
+            None,
+        ));
     }
 }
 
 macro_rules! skip_trivia {
-    ($iter:expr, $listener:expr) => {
-        if !skip_trivia($iter, $listener) {
+    ($iter:expr, $input:expr, $options:expr, $listener:expr) => {
+        if !skip_trivia($iter, $input, $options, $listener) {
             return false;
         }
     };
 }
 
+/// Parse a value. `expected` are the tokens valid at the value's location and
+/// `container_byte_offset` is the location of the current container, for errors.
 fn parse_single<L: EventListener>(
     iter: &mut Peekable<CharIndices>,
-    input_size: usize,
+    input: &str,
+    options: &ParseOptions,
     listener: &mut L,
+    expected: &'static [Token],
+    container_byte_offset: Option<usize>,
 ) -> bool {
-    skip_trivia!(iter, listener);
+    skip_trivia!(iter, input, options, listener);
 
     let (byte_offset, char) = match iter.next() {
         Some(next) => next,
         None => {
-            listener.handle_error(ParseError {
-                byte_offset: input_size,
-                reason: "unexpected end of input",
-            });
+            listener.handle_error(ParseError::unexpected(
+                input,
+                input.len(),
+                expected,
+                container_byte_offset,
+            ));
             return false;
         }
     };
 
     if char == '[' {
-        listener.handle_start_array(byte_offset);
+        match listener.handle_start_array(byte_offset) {
+            ControlFlow::Continue => {}
+            ControlFlow::SkipValue => {
+                return skip_container(iter, input, options, listener, byte_offset)
+            }
+            ControlFlow::Stop => return false,
+        }
         let mut array_is_empty = true;
         loop {
-            skip_trivia!(iter, listener);
+            skip_trivia!(iter, input, options, listener);
 
             match iter.peek().copied() {
                 Some((comma_byte_offset, ',')) => {
                     if array_is_empty {
-                        listener.handle_error(ParseError {
-                            byte_offset: comma_byte_offset,
-                            reason: "unexpected character while parsing array",
-                        });
+                        listener.handle_error(ParseError::unexpected(
+                            input,
+                            comma_byte_offset,
+                            VALUE_OR_END_ARRAY,
+                            Some(byte_offset),
+                        ));
                         return false;
                     }
 
                     // Consume ','
                     iter.next();
-                    if !parse_single(iter, input_size, listener) {
+
+                    let expected = if options.dialect == Dialect::Json5 {
+                        // Trailing comma, the loop consumes the ']'.
+                        skip_trivia!(iter, input, options, listener);
+                        if let Some((_, ']')) = iter.peek() {
+                            continue;
+                        }
+                        VALUE_OR_END_ARRAY
+                    } else {
+                        VALUE
+                    };
+
+                    if !parse_single(iter, input, options, listener, expected, Some(byte_offset)) {
                         return false;
                     }
                 }
 
-                Some((_, ']')) => {
+                Some((end_byte_offset, ']')) => {
                     // Consume ']'
                     iter.next();
-                    listener.handle_end_array(byte_offset);
-                    return true;
+                    return listener.handle_end_array(end_byte_offset) != ControlFlow::Stop;
                 }
 
-                Some((byte_offset, _)) => {
-                    if !array_is_empty {
-                        // Need to see a ',' before the next element.
-                        listener.handle_error(ParseError {
-                            byte_offset,
-                            reason: "unexpected character while parsing array",
-                        });
-                        return false;
-                    }
-
-                    if !parse_single(iter, byte_offset, listener) {
+                Some(_) if array_is_empty => {
+                    if !parse_single(
+                        iter,
+                        input,
+                        options,
+                        listener,
+                        VALUE_OR_END_ARRAY,
+                        Some(byte_offset),
+                    ) {
                         return false;
                     }
 
                     array_is_empty = false;
                 }
 
-                None => {
-                    listener.handle_error(ParseError {
-                        byte_offset: input_size,
-                        reason: "end of input while parsing array",
-                    });
+                _ => {
+                    // Need to see a ',' before the next element.
+                    let expected = if array_is_empty {
+                        VALUE_OR_END_ARRAY
+                    } else {
+                        COMMA_OR_END_ARRAY
+                    };
+                    listener.handle_error(ParseError::unexpected(
+                        input,
+                        next_byte_offset(iter, input),
+                        expected,
+                        Some(byte_offset),
+                    ));
                     return false;
                 }
             }
@@ -109,7 +173,13 @@ fn parse_single<L: EventListener>(
     }
 
     if char == '{' {
-        listener.handle_start_object(byte_offset);
+        match listener.handle_start_object(byte_offset) {
+            ControlFlow::Continue => {}
+            ControlFlow::SkipValue => {
+                return skip_container(iter, input, options, listener, byte_offset)
+            }
+            ControlFlow::Stop => return false,
+        }
         let mut object_is_empty = true;
 
         enum State {
@@ -122,85 +192,89 @@ fn parse_single<L: EventListener>(
         let mut state = State::Done;
 
         loop {
-            skip_trivia!(iter, listener);
+            skip_trivia!(iter, input, options, listener);
 
             match std::mem::replace(&mut state, State::Done) {
                 State::Done => {
                     match iter.peek().copied() {
-                        Some((byte_offset, ',')) => {
+                        Some((comma_byte_offset, ',')) => {
                             if object_is_empty {
-                                listener.handle_error(ParseError {
-                                    byte_offset,
-                                    reason: "unexpected comma while parsing object",
-                                });
+                                listener.handle_error(ParseError::unexpected(
+                                    input,
+                                    comma_byte_offset,
+                                    KEY_OR_END_OBJECT,
+                                    Some(byte_offset),
+                                ));
                                 return false;
                             }
                             iter.next(); // consume ','
                             state = State::ExpectKey;
                         }
 
-                        Some((_, '}')) => {
+                        Some((end_byte_offset, '}')) => {
                             iter.next(); // consume '}'
-                            listener.handle_end_object(byte_offset);
-                            return true;
-                        }
-
-                        Some((_, '"')) => {
-                            if !parse_single(iter, byte_offset, listener) {
-                                return false;
-                            }
-                            state = State::ExpectColon;
+                            return listener.handle_end_object(end_byte_offset)
+                                != ControlFlow::Stop;
                         }
 
-                        Some((byte_offset, _)) => {
-                            listener.handle_error(ParseError {
-                                byte_offset,
-                                reason: "unexpected char while parsing object",
-                            });
-                            return false;
+                        Some(_) if object_is_empty => {
+                            state = State::ExpectKey;
                         }
 
-                        None => {
-                            listener.handle_error(ParseError {
-                                byte_offset: input_size,
-                                reason: "unexpected end of input while parsing object",
-                            });
+                        _ => {
+                            let expected = if object_is_empty {
+                                KEY_OR_END_OBJECT
+                            } else {
+                                COMMA_OR_END_OBJECT
+                            };
+                            listener.handle_error(ParseError::unexpected(
+                                input,
+                                next_byte_offset(iter, input),
+                                expected,
+                                Some(byte_offset),
+                            ));
                             return false;
                         }
                     }
                 }
 
                 State::ExpectKey => {
-                    if !parse_string(iter, input_size, listener) {
+                    if let Some((_, '}')) = iter.peek() {
+                        if options.dialect == Dialect::Json5 {
+                            // Trailing comma, the `Done` state consumes the '}'.
+                            continue;
+                        }
+                    }
+                    let expected = if object_is_empty || options.dialect == Dialect::Json5 {
+                        KEY_OR_END_OBJECT
+                    } else {
+                        KEY
+                    };
+                    if !parse_key(iter, input, options, listener, expected, byte_offset) {
                         return false;
                     }
                     state = State::ExpectColon;
                 }
 
-                State::ExpectColon => match iter.next() {
+                State::ExpectColon => match iter.peek() {
                     Some((_, ':')) => {
+                        iter.next(); // consume ':'
                         state = State::ExpectValue;
                     }
 
-                    Some((byte_offset, _)) => {
-                        listener.handle_error(ParseError {
-                            byte_offset,
-                            reason: "unexpected char while parsing object",
-                        });
-                        return false;
-                    }
-
-                    None => {
-                        listener.handle_error(ParseError {
-                            byte_offset: input_size,
-                            reason: "unexpected end of input while parsing object",
-                        });
+                    _ => {
+                        listener.handle_error(ParseError::unexpected(
+                            input,
+                            next_byte_offset(iter, input),
+                            COLON,
+                            Some(byte_offset),
+                        ));
                         return false;
                     }
                 },
 
                 State::ExpectValue => {
-                    if !parse_single(iter, input_size, listener) {
+                    if !parse_single(iter, input, options, listener, VALUE, Some(byte_offset)) {
                         return false;
                     }
                     object_is_empty = false;
@@ -215,13 +289,9 @@ fn parse_single<L: EventListener>(
             && next_char(iter) == Some('u')
             && next_char(iter) == Some('e')
         {
-            listener.handle_bool(byte_offset, true);
-            return true;
+            return listener.handle_bool(byte_offset, true) != ControlFlow::Stop;
         }
-        listener.handle_error(ParseError {
-            byte_offset,
-            reason: "unexpected keyword",
-        });
+        listener.handle_error(ParseError::new(byte_offset, ParseErrorKind::InvalidKeyword));
         return false;
     }
 
@@ -231,13 +301,9 @@ fn parse_single<L: EventListener>(
             && next_char(iter) == Some('s')
             && next_char(iter) == Some('e')
         {
-            listener.handle_bool(byte_offset, false);
-            return true;
+            return listener.handle_bool(byte_offset, false) != ControlFlow::Stop;
         }
-        listener.handle_error(ParseError {
-            byte_offset,
-            reason: "unexpected keyword",
-        });
+        listener.handle_error(ParseError::new(byte_offset, ParseErrorKind::InvalidKeyword));
         return false;
     }
 
@@ -246,126 +312,169 @@ fn parse_single<L: EventListener>(
             && next_char(iter) == Some('l')
             && next_char(iter) == Some('l')
         {
-            listener.handle_null(byte_offset);
-            return true;
+            return listener.handle_null(byte_offset) != ControlFlow::Stop;
         }
-        listener.handle_error(ParseError {
-            byte_offset,
-            reason: "unexpected keyword",
-        });
+        listener.handle_error(ParseError::new(byte_offset, ParseErrorKind::InvalidKeyword));
         return false;
     }
 
-    if char.is_ascii_digit() {
-        let mut i: u64 = u64::from((char as u8) - b'0');
-
-        while let Some((_, next)) = iter.peek().copied() {
-            if !next.is_ascii_digit() {
-                break;
+    if is_number_start(char, options) {
+        let control = match scan_number(iter, input, byte_offset, char, options) {
+            Ok((end, Number::Int(i))) => listener.handle_int(byte_offset, end - byte_offset, i),
+            Ok((end, Number::NegInt(i))) => {
+                listener.handle_neg_int(byte_offset, end - byte_offset, i)
             }
-
-            // Consume the digit.
-            iter.next();
-
-            // Ignore overflows for the purposes of this post.
-            i *= 10;
-            i += u64::from((next as u8) - b'0');
-        }
-
-        listener.handle_int(byte_offset, i);
-        return true;
-    }
-
-    if char == '"' {
-        for (byte_offset_, next) in iter.by_ref() {
-            if next == '"' {
-                listener.handle_str(byte_offset + 1, byte_offset_ - byte_offset - 1);
-                return true;
+            Ok((end, Number::Float(f))) => listener.handle_float(byte_offset, end - byte_offset, f),
+            Ok((_, Number::Raw { size_in_bytes })) => {
+                listener.handle_number(byte_offset, size_in_bytes)
             }
-        }
+            Err(err) => {
+                listener.handle_error(err);
+                return false;
+            }
+        };
+        return control != ControlFlow::Stop;
+    }
 
-        listener.handle_error(ParseError {
-            byte_offset: input_size,
-            reason: "unexpected end of input while parsing string",
-        });
-        return false;
+    if char == '"' || (char == '\'' && options.dialect == Dialect::Json5) {
+        return parse_string_body(iter, byte_offset, char, input, options, listener);
     }
 
-    listener.handle_error(ParseError {
+    listener.handle_error(ParseError::unexpected(
+        input,
         byte_offset,
-        reason: "unexpected character",
-    });
+        expected,
+        container_byte_offset,
+    ));
     false
 }
 
-fn parse_string<L: EventListener>(
+/// Parse an object key. `expected` are the tokens valid at the key's location, for errors.
+fn parse_key<L: EventListener>(
     iter: &mut Peekable<CharIndices>,
-    input_size: usize,
+    input: &str,
+    options: &ParseOptions,
     listener: &mut L,
+    expected: &'static [Token],
+    container_byte_offset: usize,
 ) -> bool {
     let (byte_offset, char) = match iter.next() {
         Some(next) => next,
         None => {
-            listener.handle_error(ParseError {
-                byte_offset: input_size,
-                reason: "unexpected end of input",
-            });
+            listener.handle_error(ParseError::unexpected(
+                input,
+                input.len(),
+                expected,
+                Some(container_byte_offset),
+            ));
             return false;
         }
     };
 
-    if char == '"' {
-        for (byte_offset_, next) in iter.by_ref() {
-            if next == '"' {
-                listener.handle_str(byte_offset + 1, byte_offset_ - byte_offset - 1);
-                return true;
-            }
-        }
+    if char == '"' || (char == '\'' && options.dialect == Dialect::Json5) {
+        return parse_string_body(iter, byte_offset, char, input, options, listener);
+    }
+
+    if options.dialect == Dialect::Json5 && is_identifier_start(char) {
+        let end = scan_identifier(iter, input.len());
+        return listener.handle_str(byte_offset, end - byte_offset, false, false)
+            != ControlFlow::Stop;
     }
 
-    listener.handle_error(ParseError {
-        byte_offset: input_size,
-        reason: "unexpected end of input while parsing string",
-    });
+    listener.handle_error(ParseError::unexpected(
+        input,
+        byte_offset,
+        expected,
+        Some(container_byte_offset),
+    ));
     false
 }
 
+/// Parse a string after the opening `quote` at `byte_offset`.
+fn parse_string_body<L: EventListener>(
+    iter: &mut Peekable<CharIndices>,
+    byte_offset: usize,
+    quote: char,
+    input: &str,
+    options: &ParseOptions,
+    listener: &mut L,
+) -> bool {
+    match scan_string(iter, input.len(), quote, options) {
+        Ok((closing_quote, has_escapes)) => {
+            let size_in_bytes = closing_quote - byte_offset - 1;
+            listener.handle_str(byte_offset + 1, size_in_bytes, has_escapes, true)
+                != ControlFlow::Stop
+        }
+        Err(err) => {
+            listener.handle_error(err);
+            false
+        }
+    }
+}
+
+/// Skips the rest of the array or object with the opening bracket at `container_byte_offset`,
+/// without calling the listener, after a callback returned [ControlFlow::SkipValue].
+fn skip_container<L: EventListener>(
+    iter: &mut Peekable<CharIndices>,
+    input: &str,
+    options: &ParseOptions,
+    listener: &mut L,
+    container_byte_offset: usize,
+) -> bool {
+    let container = if input.as_bytes()[container_byte_offset] == b'[' {
+        Container::Array
+    } else {
+        Container::Object
+    };
+    let mut container_stack = vec![(container, container_byte_offset)];
+    match skip_containers(iter, input, &mut container_stack, 0, options) {
+        Ok(_) => true,
+        Err(err) => {
+            listener.handle_error(err);
+            false
+        }
+    }
+}
+
 fn next_char(iter: &mut Peekable<CharIndices>) -> Option<char> {
     iter.next().map(|(_, char)| char)
 }
 
-fn skip_trivia<L: EventListener>(iter: &mut Peekable<CharIndices>, listener: &mut L) -> bool {
-    'outer: while let Some((byte_offset, char)) = iter.peek().copied() {
-        if char.is_ascii_whitespace() {
+/// Byte offset of the next character, or end of input.
+fn next_byte_offset(iter: &mut Peekable<CharIndices>, input: &str) -> usize {
+    iter.peek()
+        .map_or(input.len(), |(byte_offset, _)| *byte_offset)
+}
+
+fn skip_trivia<L: EventListener>(
+    iter: &mut Peekable<CharIndices>,
+    input: &str,
+    options: &ParseOptions,
+    listener: &mut L,
+) -> bool {
+    while let Some((byte_offset, char)) = iter.peek().copied() {
+        if is_whitespace(char) {
+            if let Err(err) = check_whitespace(byte_offset, char, options) {
+                listener.handle_error(err);
+                return false;
+            }
             iter.next(); // consume peeked whitespace
             continue;
         }
 
-        if char == '/' {
-            iter.next(); // consume peeked '/'
-            match iter.next() {
-                Some((_, '/')) => {
-                    for (byte_offset_, char) in iter.by_ref() {
-                        if char == '\n' {
-                            listener.handle_comment(byte_offset, byte_offset_ - byte_offset + 1);
-                            continue 'outer;
-                        }
+        if is_comment_start(char, options) {
+            iter.next(); // consume peeked character
+            match scan_comment(iter, input.len(), byte_offset, char, options) {
+                Ok((size_in_bytes, kind)) => {
+                    if listener.handle_comment(byte_offset, size_in_bytes, kind)
+                        == ControlFlow::Stop
+                    {
+                        return false;
                     }
+                    continue;
                 }
-
-                Some(_) => {
-                    listener.handle_error(ParseError {
-                        byte_offset,
-                        reason: "unexpected '/'",
-                    });
-                    return false;
-                }
-
-                None => {
-                    listener.handle_error(ParseError {
-                        byte_offset,
-                        reason: "unexpected end of input",
-                    });
+                Err(err) => {
+                    listener.handle_error(err);
                     return false;
                 }
             }
@@ -1,175 +1,283 @@
-use crate::{Json, ParseError, ParseEvent, ParseEventKind};
+use crate::error::{Token, VALUE};
+use crate::escape::decode_string;
+use crate::{
+    parse_events_with_options, AstNode, Json, ParseError, ParseErrorKind, ParseEvent,
+    ParseEventKind, ParseOptions, Span, SpannedJson,
+};
 
 /// Parses a stream of [ParseEvent]s to [Json].
+///
+/// [ParseEventKind::Error] events in arrays and after object keys become [Json::Error] nodes.
+/// Other error events are ignored.
 pub fn event_to_tree<I: Iterator<Item = Result<ParseEvent, ParseError>>>(
     parser: &mut I,
     input: &str,
 ) -> Result<Json, ParseError> {
-    let mut container_stack: Vec<Container> = vec![];
-    let mut current_container: Option<Container> = None;
-    let mut parsed_object: Option<Json> = None;
-
-    for event in parser.by_ref() {
-        let ParseEvent { kind, byte_offset } = match event {
-            Ok(event) => event,
-            Err(err) => return Err(err),
-        };
+    build_tree(parser, input)
+}
 
-        match kind {
-            ParseEventKind::StartObject => {
-                if let Some(container) = current_container.take() {
-                    container_stack.push(container);
-                }
-                current_container = Some(Container::new_map());
-            }
+/// Parses a stream of [ParseEvent]s to [SpannedJson]. Error events are handled as in
+/// [event_to_tree].
+pub fn event_to_spanned_tree<I: Iterator<Item = Result<ParseEvent, ParseError>>>(
+    parser: &mut I,
+    input: &str,
+) -> Result<SpannedJson, ParseError> {
+    build_tree(parser, input)
+}
 
-            ParseEventKind::EndObject => {
-                let map = current_container.take().unwrap().into_map().finish();
-                match container_stack.pop() {
-                    Some(mut container) => {
-                        container.add_object(map);
-                        current_container = Some(container)
-                    }
-                    None => {
-                        parsed_object = Some(map);
-                        break;
-                    }
-                }
-            }
+fn build_tree<N: AstNode, I: Iterator<Item = Result<ParseEvent, ParseError>>>(
+    parser: &mut I,
+    input: &str,
+) -> Result<N, ParseError> {
+    let mut builder = TreeBuilder::new();
+    loop {
+        // The parsers report an error before the end of the iterator when the input doesn't have
+        // a value.
+        let event = parser
+            .next()
+            .ok_or_else(|| builder.end_of_input(input.len()))??;
+        if let Some(value) = builder.add_input_event(event, input) {
+            return Ok(value);
+        }
+    }
+}
 
-            ParseEventKind::StartArray => {
-                if let Some(container) = current_container.take() {
-                    container_stack.push(container);
-                }
-                current_container = Some(Container::new_array());
-            }
+/// The decoded string of a [ParseEventKind::Str] event in `input`, or the input text of other
+/// events.
+pub(crate) fn event_text(input: &str, event: &ParseEvent) -> String {
+    match event.kind {
+        ParseEventKind::Str {
+            size_in_bytes,
+            has_escapes,
+        } => decode_string(
+            &input[event.byte_offset..event.byte_offset + size_in_bytes],
+            has_escapes,
+        ),
+        _ => input[event.span.start..event.span.end].to_string(),
+    }
+}
 
-            ParseEventKind::EndArray => {
-                let array = current_container.take().unwrap().into_array();
-                match container_stack.pop() {
-                    Some(mut container) => {
-                        container.add_object(Json::Array(array));
-                        current_container = Some(container)
-                    }
-                    None => {
-                        parsed_object = Some(Json::Array(array));
-                        break;
-                    }
-                }
-            }
+/// Builds a tree from [ParseEvent]s, one event at a time. Used by [event_to_tree] and its async
+/// version.
+pub(crate) struct TreeBuilder<N: AstNode> {
+    container_stack: Vec<Container<N>>,
+    current_container: Option<Container<N>>,
+}
 
-            ParseEventKind::Int(int) => {
-                let object = Json::Int(int);
-                match current_container.as_mut() {
-                    Some(container) => container.add_object(object),
-                    None => {
-                        parsed_object = Some(object);
-                        break;
-                    }
+impl<N: AstNode> TreeBuilder<N> {
+    pub(crate) fn new() -> TreeBuilder<N> {
+        TreeBuilder {
+            container_stack: vec![],
+            current_container: None,
+        }
+    }
+
+    /// Adds the next event. Returns the tree when the event completes the top-level value.
+    ///
+    /// `text` returns the decoded string of a [ParseEventKind::Str] event, or the text of a
+    /// [ParseEventKind::Number] event.
+    pub(crate) fn add_event(
+        &mut self,
+        event: ParseEvent,
+        text: impl FnOnce(&ParseEvent) -> String,
+    ) -> Option<N> {
+        let span = event.span;
+        let object = match event.kind {
+            ParseEventKind::StartObject | ParseEventKind::StartArray => {
+                if let Some(container) = self.current_container.take() {
+                    self.container_stack.push(container);
                 }
+                self.current_container = Some(match event.kind {
+                    ParseEventKind::StartObject => Container::new_map(span.start),
+                    _ => Container::new_array(span.start),
+                });
+                return None;
             }
 
-            ParseEventKind::Str { size_in_bytes } => {
-                let string = input[byte_offset..byte_offset + size_in_bytes].to_string();
-                let object = Json::String(string);
-                match current_container.as_mut() {
-                    Some(container) => container.add_object(object),
-                    None => {
-                        parsed_object = Some(object);
-                        break;
-                    }
-                }
+            ParseEventKind::EndObject | ParseEventKind::EndArray => {
+                let container = self.current_container.take().unwrap();
+                self.current_container = self.container_stack.pop();
+                container.finish(span.end)
             }
 
-            ParseEventKind::Bool(bool) => {
-                let object = Json::Bool(bool);
-                match current_container.as_mut() {
-                    Some(container) => container.add_object(object),
-                    None => {
-                        parsed_object = Some(object);
-                        break;
-                    }
+            ParseEventKind::Comment { .. }
+            | ParseEventKind::StartDocument
+            | ParseEventKind::EndDocument => return None,
+
+            ParseEventKind::Error(_) => match self.current_container.as_mut() {
+                Some(container) => {
+                    container.add_error(span);
+                    return None;
                 }
-            }
+                None => N::scalar(Json::Error, span),
+            },
 
-            ParseEventKind::Null => {
-                let object = Json::Null;
-                match current_container.as_mut() {
-                    Some(container) => container.add_object(object),
-                    None => {
-                        parsed_object = Some(object);
-                        break;
+            ParseEventKind::Str { .. } => {
+                let string = text(&event);
+                match self.current_container.as_mut() {
+                    Some(container) => {
+                        container.add_str(string, span);
+                        return None;
                     }
+                    None => N::scalar(Json::String(string), span),
                 }
             }
 
-            ParseEventKind::Comment { .. } => {}
+            ParseEventKind::Number { .. } => N::scalar(Json::Number(text(&event)), span),
+            ParseEventKind::Int(int) => N::scalar(Json::Int(int), span),
+            ParseEventKind::NegInt(int) => N::scalar(Json::NegInt(int), span),
+            ParseEventKind::Float(float) => N::scalar(Json::Float(float), span),
+            ParseEventKind::Bool(bool) => N::scalar(Json::Bool(bool), span),
+            ParseEventKind::Null => N::scalar(Json::Null, span),
+        };
+
+        match self.current_container.as_mut() {
+            Some(container) => {
+                container.add_object(object);
+                None
+            }
+            None => Some(object),
         }
     }
 
-    Ok(parsed_object.unwrap())
+    /// Adds the next event of `input`, as in [TreeBuilder::add_event].
+    pub(crate) fn add_input_event(&mut self, event: ParseEvent, input: &str) -> Option<N> {
+        self.add_event(event, |event| event_text(input, event))
+    }
+
+    /// The error for an event stream that ends before the tree is complete, at `byte_offset`.
+    pub(crate) fn end_of_input(&self, byte_offset: usize) -> ParseError {
+        ParseError::new(
+            byte_offset,
+            ParseErrorKind::UnexpectedToken {
+                found: Token::EndOfInput,
+                expected: VALUE,
+                container_byte_offset: self.current_container.as_ref().map(Container::start),
+            },
+        )
+    }
 }
 
-pub(crate) enum Container {
-    Array(Vec<Json>),
-    Map(MapInProgress),
+/// Parses input to [Json] with [ParseOptions::recover_errors], returning all errors in the input.
+/// Values that couldn't be parsed are [Json::Error] nodes in the AST.
+///
+/// Recovery is done by [parse_events][crate::parse_events], see
+/// [ParseOptions::recover_errors] for the details.
+pub fn parse_ast_recovering(input: &str, options: ParseOptions) -> (Json, Vec<ParseError>) {
+    let options = ParseOptions {
+        recover_errors: true,
+        ..options
+    };
+    let mut errors: Vec<ParseError> = vec![];
+    let mut parser = parse_events_with_options(input, options).inspect(|event| {
+        if let Ok(ParseEvent {
+            kind: ParseEventKind::Error(error),
+            ..
+        }) = event
+        {
+            errors.push(error.clone());
+        }
+    });
+
+    // The parser does not fail when recovering from errors.
+    let ast = event_to_tree(&mut parser, input).unwrap();
+
+    // Collect the errors after the top-level value.
+    parser.for_each(|_| {});
+
+    (ast, errors)
+}
+
+pub(crate) enum Container<N: AstNode> {
+    Array {
+        /// Byte offset of the opening bracket.
+        start: usize,
+        elements: Vec<N>,
+    },
+    Map(MapInProgress<N>),
 }
 
-pub(crate) struct MapInProgress {
-    built: Vec<(String, Json)>,
-    next: Option<String>,
+pub(crate) struct MapInProgress<N: AstNode> {
+    /// Byte offset of the opening bracket.
+    start: usize,
+    built: Vec<(N::Key, N)>,
+    next: Option<N::Key>,
 }
 
-impl Container {
-    pub(crate) fn new_map() -> Container {
+impl<N: AstNode> Container<N> {
+    pub(crate) fn new_map(start: usize) -> Container<N> {
         Container::Map(MapInProgress {
+            start,
             built: vec![],
             next: None,
         })
     }
 
-    pub(crate) fn new_array() -> Container {
-        Container::Array(vec![])
+    pub(crate) fn new_array(start: usize) -> Container<N> {
+        Container::Array {
+            start,
+            elements: vec![],
+        }
     }
 
-    pub(crate) fn into_map(self) -> MapInProgress {
+    /// Byte offset of the opening bracket.
+    pub(crate) fn start(&self) -> usize {
         match self {
-            Container::Array(_) => panic!(),
-            Container::Map(map) => map,
+            Container::Array { start, .. } => *start,
+            Container::Map(map) => map.start,
         }
     }
 
-    pub(crate) fn into_array(self) -> Vec<Json> {
+    pub(crate) fn add_object(&mut self, object: N) {
         match self {
-            Container::Array(array) => array,
-            Container::Map(_) => panic!(),
+            Container::Array { elements, .. } => elements.push(object),
+            Container::Map(map) => map.add(object),
         }
     }
 
-    pub(crate) fn add_object(&mut self, object: Json) {
+    /// Adds a string value, or in an object without a key for the next value, the key.
+    pub(crate) fn add_str(&mut self, string: String, span: Span) {
         match self {
-            Container::Array(array) => array.push(object),
-            Container::Map(map) => map.add(object),
+            Container::Map(map) if map.next.is_none() => map.next = Some(N::key(string, span)),
+            _ => self.add_object(N::scalar(Json::String(string), span)),
         }
     }
-}
 
-impl MapInProgress {
-    pub(crate) fn add(&mut self, object: Json) {
-        match self.next.take() {
-            Some(key) => {
-                self.built.push((key, object));
-            }
-            None => {
-                self.next = Some(object.into_string());
+    /// Adds a [Json::Error] in place of a value. In an object, an error in place of a key is not
+    /// added.
+    pub(crate) fn add_error(&mut self, span: Span) {
+        let error = N::scalar(Json::Error, span);
+        match self {
+            Container::Array { elements, .. } => elements.push(error),
+            Container::Map(map) => {
+                if let Some(key) = map.next.take() {
+                    map.built.push((key, error));
+                }
             }
         }
     }
 
-    pub(crate) fn finish(self) -> Json {
-        let MapInProgress { built, next } = self;
+    /// Finishes the container. `end` is the byte offset after the closing bracket.
+    pub(crate) fn finish(self, end: usize) -> N {
+        match self {
+            Container::Array { start, elements } => N::array(elements, Span::new(start, end)),
+            Container::Map(map) => map.finish(end),
+        }
+    }
+}
+
+impl<N: AstNode> MapInProgress<N> {
+    /// Adds the value of the last key. Keys are added by [Container::add_str].
+    pub(crate) fn add(&mut self, object: N) {
+        if let Some(key) = self.next.take() {
+            self.built.push((key, object));
+        }
+    }
+
+    pub(crate) fn finish(self, end: usize) -> N {
+        let MapInProgress { start, built, next } = self;
         assert!(next.is_none());
-        Json::Object(built)
+        N::object(built, Span::new(start, end))
     }
 }
 
@@ -180,4 +288,81 @@ fn event_to_tree_tests() {
         let ast_ = event_to_tree(&mut parser, &str).unwrap();
         assert_eq!(ast_, ast);
     }
+
+    // After the end of the events.
+    let mut parser = crate::parse_events("1");
+    assert_eq!(event_to_tree(&mut parser, "1"), Ok(Json::Int(1)));
+    let error = event_to_tree(&mut parser, "1").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "unexpected end of input, expected value at byte offset 1"
+    );
+}
+
+#[test]
+fn recovery_tests() {
+    for (str, ast, error_byte_offsets) in crate::test_common::recovery_tests() {
+        println!("Parsing {:?}", str);
+        let (ast_, errors) = parse_ast_recovering(&str, ParseOptions::default());
+        assert_eq!(ast_, ast);
+        assert_eq!(
+            errors
+                .iter()
+                .map(|error| error.byte_offset)
+                .collect::<Vec<_>>(),
+            error_byte_offsets
+        );
+    }
+}
+
+#[test]
+fn lossless_number_tests() {
+    let options = crate::ParseOptions {
+        lossless_numbers: true,
+        ..crate::ParseOptions::default()
+    };
+    for (str, ast) in crate::test_common::lossless_number_ast_tests() {
+        let mut parser = crate::parse_events_with_options(&str, options);
+        let ast_ = event_to_tree(&mut parser, &str).unwrap();
+        assert_eq!(ast_, ast);
+    }
+}
+
+#[test]
+fn spanned_tests() {
+    for (str, options, texts) in crate::test_common::spanned_tests() {
+        println!("Parsing {:?}", str);
+        let mut parser = crate::parse_events_with_options(&str, options);
+        let ast = event_to_spanned_tree(&mut parser, &str).unwrap();
+        assert_eq!(crate::test_common::span_texts(&str, &ast), texts);
+
+        if options.dialect == crate::Dialect::Json5 {
+            continue;
+        }
+        let mut parser = crate::parse_events_lexgen_with_options(&str, options);
+        let ast = event_to_spanned_tree(&mut parser, &str).unwrap();
+        assert_eq!(crate::test_common::span_texts(&str, &ast), texts);
+    }
+}
+
+#[test]
+fn recovery_spanned_tests() {
+    let options = ParseOptions {
+        recover_errors: true,
+        ..ParseOptions::default()
+    };
+    let tests = [
+        ("[1x, true]", vec!["[1x, true]", "1", "", "true"]),
+        ("[-2.5e3x]", vec!["[-2.5e3x]", "-2.5e3", ""]),
+        (
+            r#"{"a": [1, 2"#,
+            vec![r#"{"a": [1, 2"#, r#""a""#, "[1, 2", "1", "2", ""],
+        ),
+    ];
+    for (str, texts) in tests {
+        println!("Parsing {:?}", str);
+        let mut parser = crate::parse_events_with_options(str, options);
+        let ast = event_to_spanned_tree(&mut parser, str).unwrap();
+        assert_eq!(crate::test_common::span_texts(str, &ast), texts);
+    }
 }
@@ -0,0 +1,319 @@
+use crate::escape::unescape;
+use crate::{
+    CommentKind, ControlFlow, EventListener, FinishListener, Json, JsonWriter, JsonWriterError,
+    ParseError, SerializeOptions,
+};
+
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+
+/// Options for [EventWriter].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EventWriterOptions {
+    /// Number of spaces per indentation level. When `None` the output is minified. `None` by
+    /// default.
+    pub indent: Option<usize>,
+
+    /// Write the comments in the input. Line and hash comments are followed by a newline, also
+    /// when minifying. Enabled by default.
+    pub keep_comments: bool,
+}
+
+/// An [EventListener] that writes the parsed JSON as text to an [io::Write], without building an
+/// AST.
+///
+/// Memory use only depends on the nesting depth of the input, so large inputs can be minified or
+/// re-indented as they are parsed. The output is laid out by a [JsonWriter]. Floats are written as
+/// in [write_json][crate::write_json].
+pub struct EventWriter<'a, W: io::Write> {
+    input: &'a str,
+    writer: JsonWriter<W>,
+    keep_comments: bool,
+    error: Option<WriteError>,
+}
+
+/// An error in [EventWriter].
+#[derive(Debug)]
+pub enum WriteError {
+    Io(io::Error),
+    Parse(ParseError),
+
+    /// Events that don't make a valid JSON value, e.g. from a caller that calls the
+    /// [EventListener] methods directly.
+    Writer(JsonWriterError),
+}
+
+impl Default for EventWriterOptions {
+    fn default() -> Self {
+        EventWriterOptions {
+            indent: None,
+            keep_comments: true,
+        }
+    }
+}
+
+impl<'a, W: io::Write> EventWriter<'a, W> {
+    /// Makes a writer for events parsed from `input`.
+    pub fn new(input: &'a str, out: W, options: EventWriterOptions) -> EventWriter<'a, W> {
+        let serialize_options = SerializeOptions {
+            indent: options.indent,
+            ..SerializeOptions::default()
+        };
+        EventWriter {
+            input,
+            writer: JsonWriter::new(out, serialize_options),
+            keep_comments: options.keep_comments,
+            error: None,
+        }
+    }
+
+    /// Records the error of a write. Stops parsing after an error.
+    fn check(&mut self, result: Result<(), JsonWriterError>) -> ControlFlow {
+        match result {
+            Ok(()) => ControlFlow::Continue,
+            Err(error) => {
+                self.error.get_or_insert_with(|| WriteError::from(error));
+                ControlFlow::Stop
+            }
+        }
+    }
+}
+
+impl<'a, W: io::Write> EventListener for EventWriter<'a, W> {
+    fn handle_start_object(&mut self, _byte_offset: usize) -> ControlFlow {
+        let result = self.writer.begin_object();
+        self.check(result)
+    }
+
+    fn handle_end_object(&mut self, _byte_offset: usize) -> ControlFlow {
+        let result = self.writer.end_object();
+        self.check(result)
+    }
+
+    fn handle_start_array(&mut self, _byte_offset: usize) -> ControlFlow {
+        let result = self.writer.begin_array();
+        self.check(result)
+    }
+
+    fn handle_end_array(&mut self, _byte_offset: usize) -> ControlFlow {
+        let result = self.writer.end_array();
+        self.check(result)
+    }
+
+    fn handle_int(&mut self, _byte_offset: usize, _size_in_bytes: usize, i: u64) -> ControlFlow {
+        let result = self.writer.value(&Json::Int(i));
+        self.check(result)
+    }
+
+    fn handle_neg_int(
+        &mut self,
+        _byte_offset: usize,
+        _size_in_bytes: usize,
+        i: i64,
+    ) -> ControlFlow {
+        let result = self.writer.value(&Json::NegInt(i));
+        self.check(result)
+    }
+
+    fn handle_float(&mut self, _byte_offset: usize, _size_in_bytes: usize, f: f64) -> ControlFlow {
+        let result = self.writer.value(&Json::Float(f));
+        self.check(result)
+    }
+
+    fn handle_number(&mut self, byte_offset: usize, size_in_bytes: usize) -> ControlFlow {
+        let number = &self.input[byte_offset..byte_offset + size_in_bytes];
+        let result = self.writer.raw_value(number);
+        self.check(result)
+    }
+
+    fn handle_str(
+        &mut self,
+        byte_offset: usize,
+        size_in_bytes: usize,
+        has_escapes: bool,
+        _quoted: bool,
+    ) -> ControlFlow {
+        let str = &self.input[byte_offset..byte_offset + size_in_bytes];
+        let str = if has_escapes {
+            Cow::Owned(unescape(str))
+        } else {
+            Cow::Borrowed(str)
+        };
+        let result = if self.writer.expects_key() {
+            self.writer.key(&str)
+        } else {
+            self.writer.string(&str)
+        };
+        self.check(result)
+    }
+
+    fn handle_bool(&mut self, _byte_offset: usize, b: bool) -> ControlFlow {
+        let result = self.writer.value(&Json::Bool(b));
+        self.check(result)
+    }
+
+    fn handle_null(&mut self, _byte_offset: usize) -> ControlFlow {
+        let result = self.writer.value(&Json::Null);
+        self.check(result)
+    }
+
+    fn handle_comment(
+        &mut self,
+        byte_offset: usize,
+        size_in_bytes: usize,
+        kind: CommentKind,
+    ) -> ControlFlow {
+        if self.keep_comments {
+            let comment = &self.input[byte_offset..byte_offset + size_in_bytes];
+            self.writer.raw_comment(comment, kind);
+        }
+        ControlFlow::Continue
+    }
+
+    fn handle_error(&mut self, error: ParseError) {
+        self.error = Some(WriteError::Parse(error));
+    }
+}
+
+impl<'a, W: io::Write> FinishListener for EventWriter<'a, W> {
+    type Output = W;
+    type Error = WriteError;
+
+    /// Writes the comments after the value and returns the output, or the first error.
+    fn finish(self) -> Result<W, WriteError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.writer.finish().map_err(WriteError::from)
+    }
+}
+
+impl From<JsonWriterError> for WriteError {
+    fn from(error: JsonWriterError) -> Self {
+        match error {
+            JsonWriterError::Io(error) => WriteError::Io(error),
+            error => WriteError::Writer(error),
+        }
+    }
+}
+
+impl fmt::Display for WriteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WriteError::Io(error) => write!(f, "I/O error: {}", error),
+            WriteError::Parse(error) => error.fmt(f),
+            WriteError::Writer(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for WriteError {}
+
+#[cfg(test)]
+fn write_events(
+    input: &str,
+    parse_options: crate::ParseOptions,
+    options: EventWriterOptions,
+) -> Result<String, WriteError> {
+    let writer = EventWriter::new(input, vec![], options);
+    let output = crate::event_push_parser::parse_with_options(input, writer, parse_options)?;
+    Ok(String::from_utf8(output).unwrap())
+}
+
+#[test]
+fn minify_reindent_tests() {
+    let minify = EventWriterOptions {
+        indent: None,
+        keep_comments: false,
+    };
+    let reindent = EventWriterOptions {
+        indent: Some(2),
+        keep_comments: false,
+    };
+    for (str, ast) in crate::test_common::ast_tests() {
+        println!("Parsing {:?}", str);
+        let options = crate::ParseOptions::default();
+        assert_eq!(
+            write_events(&str, options, minify).unwrap(),
+            ast.to_string()
+        );
+        assert_eq!(
+            write_events(&str, options, reindent).unwrap(),
+            format!("{:#}", ast)
+        );
+    }
+    for (str, ast) in crate::test_common::json5_ast_tests() {
+        // Infinity and NaN are written as null.
+        if str.contains("Infinity") || str.contains("NaN") {
+            continue;
+        }
+        println!("Parsing {:?}", str);
+        let options = crate::test_common::json5_options();
+        let output = write_events(&str, options, minify).unwrap();
+        assert_eq!(crate::parse_ast(&output).unwrap(), ast);
+    }
+}
+
+#[test]
+fn comment_tests() {
+    let input = "// a\n{ \"a\" /* b */ : # c\n [1, /* d */ 2 // e\n], \"b\": {/* f */}} /* g */";
+    let options = crate::test_common::comment_options();
+
+    let reindent = EventWriterOptions {
+        indent: Some(2),
+        ..EventWriterOptions::default()
+    };
+    assert_eq!(
+        write_events(input, options, reindent).unwrap(),
+        "// a
+{
+  \"a\": /* b */ # c
+  [
+    1,
+    /* d */
+    2
+    // e
+  ],
+  \"b\": {
+    /* f */
+  }
+}
+/* g */"
+    );
+
+    assert_eq!(
+        write_events(input, options, EventWriterOptions::default()).unwrap(),
+        "// a\n{\"a\":/* b */# c\n[1,/* d */2// e\n],\"b\":{/* f */}}/* g */"
+    );
+
+    let drop_comments = EventWriterOptions {
+        keep_comments: false,
+        ..EventWriterOptions::default()
+    };
+    assert_eq!(
+        write_events(input, options, drop_comments).unwrap(),
+        r#"{"a":[1,2],"b":{}}"#
+    );
+}
+
+#[test]
+fn error_test() {
+    let result = write_events(
+        "[1, 2",
+        crate::ParseOptions::default(),
+        EventWriterOptions::default(),
+    );
+    assert!(matches!(result, Err(WriteError::Parse(error)) if error.byte_offset == 5));
+}
+
+#[test]
+fn unexpected_event_test() {
+    // Events out of order are reported as errors.
+    let mut writer = EventWriter::new("[]", vec![], EventWriterOptions::default());
+    assert_eq!(writer.handle_start_array(0), ControlFlow::Continue);
+    assert_eq!(writer.handle_end_object(1), ControlFlow::Stop);
+    assert_eq!(writer.handle_end_array(1), ControlFlow::Continue);
+    let error = writer.finish().unwrap_err();
+    assert_eq!(error.to_string(), "unexpected '}', expected value or ']'");
+}
@@ -0,0 +1,503 @@
+use crate::escape::decode_string;
+use crate::event_listener::EventHandler;
+use crate::json_pointer::parse_index;
+use crate::{
+    parse_events_with_options, ControlFlow, FinishListener, Json, JsonPointer, JsonPointerError,
+    ParseError, ParseEvent, ParseEventKind, ParseOptions,
+};
+
+/// The expected type of a field extracted by an [Extractor]. Values of other types are not
+/// extracted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldType {
+    /// An integer, extracted as [Json::Int] or [Json::NegInt]. Not extracted with
+    /// [ParseOptions::lossless_numbers], use [FieldType::Number] instead.
+    Int,
+
+    /// A number, extracted as [Json::Int], [Json::NegInt], [Json::Float], or with
+    /// [ParseOptions::lossless_numbers], [Json::Number].
+    Number,
+
+    /// A string, extracted as [Json::String].
+    Str,
+
+    /// A boolean, extracted as [Json::Bool].
+    Bool,
+
+    /// Any value other than an array or object.
+    Scalar,
+}
+
+/// Extracts the values at a set of paths in a single pass over the events, without building an
+/// AST.
+///
+/// Fields are added with [Extractor::add_field], and extracted from an input with
+/// [Extractor::extract] using [parse_events][crate::parse_events], or with an
+/// [ExtractorListener] using [parse_events_push][crate::parse_events_push]. Parsing stops as soon
+/// as all of the fields are found, or known to be missing. Arrays and objects that don't contain
+/// any of the fields are skipped without decoding their keys and values.
+#[derive(Debug, Clone)]
+pub struct Extractor {
+    /// Nodes of the path trie. The first node is the root of the input.
+    nodes: Vec<Node>,
+
+    field_types: Vec<FieldType>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Node {
+    children: Vec<Child>,
+
+    /// The fields at the node's path.
+    fields: Vec<usize>,
+
+    /// The fields at the node and its descendants.
+    subtree_fields: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+struct Child {
+    key: String,
+
+    /// The key as an array index.
+    index: Option<usize>,
+
+    node: usize,
+}
+
+/// An [EventListener][crate::EventListener] that extracts the fields of an [Extractor], for
+/// [parse_events_push][crate::parse_events_push]. Returns [ControlFlow::SkipValue] for arrays and
+/// objects that don't contain any of the fields, and [ControlFlow::Stop] when all of the fields
+/// are found.
+pub struct ExtractorListener<'a> {
+    input: &'a str,
+    matcher: Matcher<'a>,
+    error: Option<ParseError>,
+}
+
+/// The state of extracting the fields of an [Extractor] from events.
+struct Matcher<'a> {
+    extractor: &'a Extractor,
+
+    /// Arrays and objects in the path to the current value, with the path trie nodes.
+    stack: Vec<Frame>,
+
+    values: Vec<Option<Json>>,
+
+    /// Whether the fields are found, or known to be missing.
+    done: Vec<bool>,
+
+    num_done: usize,
+}
+
+struct Frame {
+    node: usize,
+
+    is_object: bool,
+
+    /// In an object, whether the next string is a key.
+    expect_key: bool,
+
+    /// In an object, the node of the value of the last key.
+    value_node: Option<usize>,
+
+    /// In an array, index of the next element.
+    next_index: usize,
+}
+
+impl Extractor {
+    pub fn new() -> Extractor {
+        Extractor {
+            nodes: vec![Node::default()],
+            field_types: vec![],
+        }
+    }
+
+    /// Adds a field at `path`, in [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901) syntax:
+    /// the object keys and array indices to the value, each preceded by a `/`, with `~` and `/`
+    /// in keys escaped as `~0` and `~1`. The empty path is the whole input.
+    ///
+    /// Returns the index of the field in the extracted values, or an error if `path` is not a
+    /// valid JSON Pointer.
+    pub fn add_field(
+        &mut self,
+        path: &str,
+        field_type: FieldType,
+    ) -> Result<usize, JsonPointerError> {
+        let pointer = JsonPointer::parse(path)?;
+        let field = self.field_types.len();
+        self.field_types.push(field_type);
+
+        let mut node = 0;
+        self.nodes[node].subtree_fields.push(field);
+        for key in pointer.keys() {
+            let existing = self.nodes[node]
+                .children
+                .iter()
+                .find(|child| child.key == *key)
+                .map(|child| child.node);
+            node = match existing {
+                Some(child) => child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node::default());
+                    self.nodes[node].children.push(Child {
+                        index: parse_index(key),
+                        key: key.clone(),
+                        node: child,
+                    });
+                    child
+                }
+            };
+            self.nodes[node].subtree_fields.push(field);
+        }
+        self.nodes[node].fields.push(field);
+        Ok(field)
+    }
+
+    /// Extracts the fields from `input`. The values are in the order the fields are added, `None`
+    /// when the field is missing or has a different type.
+    ///
+    /// Arrays and objects that don't contain any of the fields are skipped with
+    /// [EventParser::skip_rest_of_container][crate::EventParser::skip_rest_of_container], so
+    /// only the errors that it checks are reported in them.
+    pub fn extract(&self, input: &str) -> Result<Vec<Option<Json>>, ParseError> {
+        self.extract_with_options(input, ParseOptions::default())
+    }
+
+    /// Extracts the fields from `input`, parsed with the given options.
+    pub fn extract_with_options(
+        &self,
+        input: &str,
+        options: ParseOptions,
+    ) -> Result<Vec<Option<Json>>, ParseError> {
+        let mut matcher = Matcher::new(self);
+        let mut parser = parse_events_with_options(input, options);
+        while let Some(event) = parser.next() {
+            match matcher.event(input, event?) {
+                ControlFlow::Continue => {}
+                ControlFlow::SkipValue => {
+                    parser.skip_rest_of_container()?;
+                }
+                ControlFlow::Stop => break,
+            }
+        }
+        Ok(matcher.finish())
+    }
+
+    /// Makes an [ExtractorListener] for extracting the fields from `input`.
+    pub fn listener<'a>(&'a self, input: &'a str) -> ExtractorListener<'a> {
+        ExtractorListener {
+            input,
+            matcher: Matcher::new(self),
+            error: None,
+        }
+    }
+
+    /// The value of `field` for a scalar event, `None` if the event doesn't have the field's type.
+    fn field_value(&self, field: usize, kind: &ParseEventKind, text: &str) -> Option<Json> {
+        let value = match (self.field_types[field], kind) {
+            (FieldType::Int | FieldType::Number | FieldType::Scalar, ParseEventKind::Int(i)) => {
+                Json::Int(*i)
+            }
+            (FieldType::Int | FieldType::Number | FieldType::Scalar, ParseEventKind::NegInt(i)) => {
+                Json::NegInt(*i)
+            }
+            (FieldType::Number | FieldType::Scalar, ParseEventKind::Float(f)) => Json::Float(*f),
+            (FieldType::Number | FieldType::Scalar, ParseEventKind::Number { .. }) => {
+                Json::Number(text.to_string())
+            }
+            (FieldType::Str | FieldType::Scalar, ParseEventKind::Str { has_escapes, .. }) => {
+                Json::String(decode_string(text, *has_escapes))
+            }
+            (FieldType::Bool | FieldType::Scalar, ParseEventKind::Bool(b)) => Json::Bool(*b),
+            (FieldType::Scalar, ParseEventKind::Null) => Json::Null,
+            _ => return None,
+        };
+        Some(value)
+    }
+}
+
+impl Default for Extractor {
+    fn default() -> Self {
+        Extractor::new()
+    }
+}
+
+impl<'a> EventHandler for ExtractorListener<'a> {
+    fn on_event(&mut self, event: ParseEvent) -> ControlFlow {
+        self.matcher.event(self.input, event)
+    }
+
+    fn on_error(&mut self, error: ParseError) {
+        self.error = Some(error);
+    }
+}
+
+impl<'a> FinishListener for ExtractorListener<'a> {
+    type Output = Vec<Option<Json>>;
+    type Error = ParseError;
+
+    /// Returns the extracted values as in [Extractor::extract], or the parse error.
+    fn finish(self) -> Result<Vec<Option<Json>>, ParseError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.matcher.finish()),
+        }
+    }
+}
+
+impl<'a> Matcher<'a> {
+    fn new(extractor: &'a Extractor) -> Matcher<'a> {
+        let num_fields = extractor.field_types.len();
+        Matcher {
+            extractor,
+            stack: vec![],
+            values: (0..num_fields).map(|_| None).collect(),
+            done: vec![false; num_fields],
+            num_done: 0,
+        }
+    }
+
+    /// Handles an event of `input`. Returns [ControlFlow::SkipValue] for an array or object that
+    /// doesn't contain any of the fields, and [ControlFlow::Stop] when all of the fields are done.
+    /// The caller skips the array or object without passing its events.
+    fn event(&mut self, input: &str, event: ParseEvent) -> ControlFlow {
+        let ParseEvent {
+            kind, byte_offset, ..
+        } = event;
+
+        let text = match kind {
+            ParseEventKind::Str { size_in_bytes, .. }
+            | ParseEventKind::Number { size_in_bytes } => {
+                &input[byte_offset..byte_offset + size_in_bytes]
+            }
+            _ => "",
+        };
+
+        match kind {
+            ParseEventKind::Comment { .. }
+            | ParseEventKind::StartDocument
+            | ParseEventKind::EndDocument
+            | ParseEventKind::Error(_) => return ControlFlow::Continue,
+
+            ParseEventKind::Str { has_escapes, .. } => {
+                if let Some(frame) = self.stack.last_mut() {
+                    if frame.expect_key {
+                        let key = decode_string(text, has_escapes);
+                        frame.value_node = self.extractor.nodes[frame.node]
+                            .children
+                            .iter()
+                            .find(|child| child.key == key)
+                            .map(|child| child.node);
+                        frame.expect_key = false;
+                        return ControlFlow::Continue;
+                    }
+                }
+            }
+
+            ParseEventKind::EndObject | ParseEventKind::EndArray => {
+                let frame = self.stack.pop().unwrap();
+                self.finish_node(frame.node);
+                self.end_value();
+                return self.control_flow();
+            }
+
+            _ => {}
+        }
+
+        let node = self.value_node();
+        match kind {
+            ParseEventKind::StartObject | ParseEventKind::StartArray => {
+                let is_object = kind == ParseEventKind::StartObject;
+                match node {
+                    Some(node) if is_object || self.has_index_children(node) => {
+                        // The fields at the node are not arrays or objects.
+                        for field in &self.extractor.nodes[node].fields {
+                            self.finish_field(*field);
+                        }
+                        self.stack.push(Frame {
+                            node,
+                            is_object,
+                            expect_key: is_object,
+                            value_node: None,
+                            next_index: 0,
+                        });
+                    }
+                    Some(node) => {
+                        self.finish_node(node);
+                        self.end_value();
+                        return match self.control_flow() {
+                            ControlFlow::Continue => ControlFlow::SkipValue,
+                            control_flow => control_flow,
+                        };
+                    }
+                    None => {
+                        self.end_value();
+                        return ControlFlow::SkipValue;
+                    }
+                }
+            }
+
+            _ => {
+                if let Some(node) = node {
+                    for field in &self.extractor.nodes[node].fields {
+                        if !self.done[*field] {
+                            self.values[*field] = self.extractor.field_value(*field, &kind, text);
+                        }
+                    }
+                    self.finish_node(node);
+                }
+                self.end_value();
+            }
+        }
+
+        self.control_flow()
+    }
+
+    /// [ControlFlow::Stop] when all of the fields are done.
+    fn control_flow(&self) -> ControlFlow {
+        if self.num_done == self.done.len() {
+            ControlFlow::Stop
+        } else {
+            ControlFlow::Continue
+        }
+    }
+
+    /// The path trie node of the next value.
+    fn value_node(&mut self) -> Option<usize> {
+        let frame = match self.stack.last_mut() {
+            Some(frame) => frame,
+            None => return Some(0),
+        };
+        if frame.is_object {
+            return frame.value_node.take();
+        }
+        let index = frame.next_index;
+        frame.next_index += 1;
+        self.extractor.nodes[frame.node]
+            .children
+            .iter()
+            .find(|child| child.index == Some(index))
+            .map(|child| child.node)
+    }
+
+    /// Updates the current container after a value.
+    fn end_value(&mut self) {
+        if let Some(frame) = self.stack.last_mut() {
+            frame.expect_key = frame.is_object;
+        }
+    }
+
+    fn has_index_children(&self, node: usize) -> bool {
+        self.extractor.nodes[node]
+            .children
+            .iter()
+            .any(|child| child.index.is_some())
+    }
+
+    /// Marks the fields at `node` and its descendants as done, after parsing the node's value.
+    fn finish_node(&mut self, node: usize) {
+        for field in &self.extractor.nodes[node].subtree_fields {
+            self.finish_field(*field);
+        }
+    }
+
+    fn finish_field(&mut self, field: usize) {
+        if !self.done[field] {
+            self.done[field] = true;
+            self.num_done += 1;
+        }
+    }
+
+    fn finish(self) -> Vec<Option<Json>> {
+        self.values
+    }
+}
+
+#[cfg(test)]
+fn extract_push(extractor: &Extractor, input: &str) -> Result<Vec<Option<Json>>, ParseError> {
+    crate::parse_events_push(input, extractor.listener(input))
+}
+
+#[test]
+fn extract_tests() {
+    let mut extractor = Extractor::new();
+    extractor.add_field("/timestamp", FieldType::Int).unwrap();
+    extractor.add_field("/request/id", FieldType::Str).unwrap();
+    extractor.add_field("/level", FieldType::Str).unwrap();
+    extractor
+        .add_field("/items/1/x", FieldType::Number)
+        .unwrap();
+    extractor.add_field("/missing", FieldType::Bool).unwrap();
+    extractor.add_field("/level", FieldType::Int).unwrap();
+    extractor.add_field("/a~1b", FieldType::Scalar).unwrap();
+
+    let input = r#"{
+        "timestamp": 123,
+        "x": {"timestamp": 1, "level": "debug"},
+        "level": "info",
+        "request": {"id": "ab", "tags": [1, 2]},
+        "items": [{"x": 1}, {"x": 2.5}],
+        "a/b": null
+    }"#;
+    let expected = vec![
+        Some(Json::Int(123)),
+        Some(Json::String(String::from("ab"))),
+        Some(Json::String(String::from("info"))),
+        Some(Json::Float(2.5)),
+        None,
+        None,
+        Some(Json::Null),
+    ];
+    let values = extractor.extract(input);
+    assert_eq!(extract_push(&extractor, input), values);
+    assert_eq!(values, Ok(expected));
+
+    // Values that are not scalars are not extracted.
+    let input = r#"{"timestamp": [1], "request": 2, "items": {"1": {"x": 3}}}"#;
+    let expected = vec![None, None, None, Some(Json::Int(3)), None, None, None];
+    let values = extractor.extract(input);
+    assert_eq!(extract_push(&extractor, input), values);
+    assert_eq!(values, Ok(expected));
+}
+
+#[test]
+fn invalid_path_test() {
+    let mut extractor = Extractor::new();
+    let error = extractor.add_field("a/b", FieldType::Int).unwrap_err();
+    assert_eq!(error.kind, crate::JsonPointerErrorKind::MissingSlash);
+    let error = extractor.add_field("/a/~2", FieldType::Int).unwrap_err();
+    assert_eq!(error.byte_offset, 3);
+
+    // The invalid paths are not added.
+    assert_eq!(extractor.add_field("/a", FieldType::Int), Ok(0));
+    assert_eq!(
+        extractor.extract(r#"{"a": 1}"#),
+        Ok(vec![Some(Json::Int(1))])
+    );
+}
+
+#[test]
+fn stop_tests() {
+    let mut extractor = Extractor::new();
+    extractor.add_field("/a", FieldType::Int).unwrap();
+    extractor.add_field("/b/0", FieldType::Str).unwrap();
+
+    // Parsing stops after the fields are found, before the error.
+    let input = r#"{"a": 1, "b": ["x", 2}"#;
+    let expected = vec![Some(Json::Int(1)), Some(Json::String(String::from("x")))];
+    let values = extractor.extract(input);
+    assert_eq!(extract_push(&extractor, input), values);
+    assert_eq!(values, Ok(expected));
+
+    // An array at the top level doesn't have the fields.
+    let input = "[1, 2";
+    assert_eq!(extractor.extract(input), Ok(vec![None, None]));
+    assert_eq!(extract_push(&extractor, input), Ok(vec![None, None]));
+
+    // Errors before the fields are found are reported.
+    let input = r#"{"a": 1, "b": [}"#;
+    assert_eq!(extractor.extract(input).unwrap_err().byte_offset, 15);
+    assert_eq!(extract_push(&extractor, input).unwrap_err().byte_offset, 15);
+}
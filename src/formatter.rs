@@ -0,0 +1,575 @@
+use crate::escape::decode_string;
+use crate::{
+    parse_events_with_options, CommentKind, ParseError, ParseEvent, ParseEventKind, ParseOptions,
+};
+
+use std::fmt::Write;
+
+/// Options for [format][crate::format_json].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    /// Number of spaces per indentation level. 2 by default.
+    pub indent: usize,
+
+    /// Maximum line width for compact arrays. 80 by default.
+    pub line_width: usize,
+
+    /// Sort object members by key. Comments move with the members. Disabled by default.
+    pub sort_keys: bool,
+
+    /// Print arrays of numbers, strings, booleans and nulls on one line when they fit the line
+    /// width, or with as many elements per line as fit. Arrays with comments are not compacted.
+    /// Enabled by default.
+    pub compact_arrays: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        FormatOptions {
+            indent: 2,
+            line_width: 80,
+            sort_keys: false,
+            compact_arrays: true,
+        }
+    }
+}
+
+/// Formats input, keeping the comments.
+///
+/// Comments on the same line after a value stay after the value, other comments stay before the
+/// next value, or before the closing bracket. Comments between an object key and value stay
+/// between the key and value.
+///
+/// The input is parsed with [parse_events][crate::parse_events] and `parse_options`. Returns the
+/// first parse error when the input is not valid.
+///
+/// Formatting is idempotent: formatting the output again gives the same output.
+pub fn format(
+    input: &str,
+    parse_options: ParseOptions,
+    format_options: FormatOptions,
+) -> Result<String, ParseError> {
+    let parse_options = ParseOptions {
+        recover_errors: false,
+        ..parse_options
+    };
+    let root = build_tree(input, parse_options)?;
+
+    let mut printer = Printer {
+        out: String::with_capacity(input.len()),
+        options: format_options,
+    };
+
+    let element = &root.elements[0];
+    for comment in &element.leading_comments {
+        printer.out.push_str(comment.text);
+        printer.out.push('\n');
+    }
+    printer.value(&element.value, 0);
+    printer.trailing_comments(&element.trailing_comments);
+    printer.out.push('\n');
+    for comment in &root.comments {
+        printer.out.push_str(comment.text);
+        printer.out.push('\n');
+    }
+
+    Ok(printer.out)
+}
+
+struct Comment<'a> {
+    /// Text of the comment, without the newline at the end of line comments.
+    text: &'a str,
+
+    kind: CommentKind,
+}
+
+/// An array element, an object member, or the top-level value.
+struct Element<'a> {
+    /// Comments before the element, on their own lines.
+    leading_comments: Vec<Comment<'a>>,
+
+    key: Option<Key<'a>>,
+
+    value: Value<'a>,
+
+    /// Comments after the element, on the same line.
+    trailing_comments: Vec<Comment<'a>>,
+}
+
+struct Key<'a> {
+    /// The key as it appears in the input.
+    text: &'a str,
+
+    /// The decoded key, for sorting.
+    sort_key: String,
+
+    /// Comments between the key and the value.
+    comments: Vec<Comment<'a>>,
+}
+
+enum Value<'a> {
+    /// A value other than an array or object, as it appears in the input.
+    Scalar(&'a str),
+    Array(Container<'a>),
+    Object(Container<'a>),
+}
+
+struct Container<'a> {
+    elements: Vec<Element<'a>>,
+
+    /// Comments after the last element, on their own lines.
+    comments: Vec<Comment<'a>>,
+}
+
+/// An array or object being built, or the top level.
+struct Frame<'a> {
+    object: bool,
+
+    elements: Vec<Element<'a>>,
+
+    /// Comments that are not trailing comments of the previous element. They become leading
+    /// comments of the next element, or the comments before the closing bracket.
+    comments: Vec<Comment<'a>>,
+
+    /// The key of the member being built.
+    key: Option<Key<'a>>,
+}
+
+impl<'a> Frame<'a> {
+    fn new(object: bool) -> Frame<'a> {
+        Frame {
+            object,
+            elements: vec![],
+            comments: vec![],
+            key: None,
+        }
+    }
+
+    fn add_value(&mut self, value: Value<'a>) {
+        self.elements.push(Element {
+            leading_comments: std::mem::take(&mut self.comments),
+            key: self.key.take(),
+            value,
+            trailing_comments: vec![],
+        });
+    }
+}
+
+/// Builds the tree of elements with comments attached. The returned frame is the top level, with
+/// one element.
+fn build_tree(input: &str, options: ParseOptions) -> Result<Frame<'_>, ParseError> {
+    let mut stack: Vec<Frame> = vec![Frame::new(false)];
+
+    // Byte offset after the last value or trailing comment, and whether the next comment can be
+    // a trailing comment of the last value: it's on the same line, and there's no line comment
+    // before it.
+    let mut last_end = 0;
+    let mut trailing_allowed = false;
+
+    for event in parse_events_with_options(input, options) {
+        let ParseEvent {
+            kind,
+            byte_offset,
+            span,
+        } = event?;
+
+        let value = match kind {
+            ParseEventKind::Comment {
+                size_in_bytes,
+                kind,
+            } => {
+                let end = byte_offset + size_in_bytes;
+                let comment = Comment {
+                    text: input[byte_offset..end].trim_end_matches(['\n', '\r']),
+                    kind,
+                };
+                let frame = stack.last_mut().unwrap();
+                if trailing_allowed && !input[last_end..byte_offset].contains('\n') {
+                    let element = frame.elements.last_mut().unwrap();
+                    element.trailing_comments.push(comment);
+                    last_end = end;
+                    trailing_allowed = kind == CommentKind::Block;
+                } else if let Some(key) = frame.key.as_mut() {
+                    key.comments.push(comment);
+                } else {
+                    frame.comments.push(comment);
+                }
+                continue;
+            }
+
+            ParseEventKind::StartObject | ParseEventKind::StartArray => {
+                stack.push(Frame::new(kind == ParseEventKind::StartObject));
+                trailing_allowed = false;
+                continue;
+            }
+
+            ParseEventKind::EndObject | ParseEventKind::EndArray => {
+                let frame = stack.pop().unwrap();
+                last_end = span.end;
+                let container = Container {
+                    elements: frame.elements,
+                    comments: frame.comments,
+                };
+                if frame.object {
+                    Value::Object(container)
+                } else {
+                    Value::Array(container)
+                }
+            }
+
+            ParseEventKind::Str {
+                size_in_bytes,
+                has_escapes,
+            } => {
+                last_end = span.end;
+                let frame = stack.last_mut().unwrap();
+                if frame.object && frame.key.is_none() {
+                    let str = &input[byte_offset..byte_offset + size_in_bytes];
+                    frame.key = Some(Key {
+                        text: &input[span.start..span.end],
+                        sort_key: decode_string(str, has_escapes),
+                        comments: vec![],
+                    });
+                    trailing_allowed = false;
+                    continue;
+                }
+                Value::Scalar(&input[span.start..span.end])
+            }
+
+            ParseEventKind::Int(_)
+            | ParseEventKind::NegInt(_)
+            | ParseEventKind::Float(_)
+            | ParseEventKind::Number { .. }
+            | ParseEventKind::Bool(_)
+            | ParseEventKind::Null => {
+                last_end = span.end;
+                Value::Scalar(&input[span.start..span.end])
+            }
+
+            // Not generated without `recover_errors`.
+            ParseEventKind::Error(error) => return Err(error),
+
+            // Not generated by `parse_events`.
+            ParseEventKind::StartDocument | ParseEventKind::EndDocument => continue,
+        };
+
+        stack.last_mut().unwrap().add_value(value);
+        trailing_allowed = true;
+    }
+
+    Ok(stack.pop().unwrap())
+}
+
+struct Printer {
+    out: String,
+    options: FormatOptions,
+}
+
+impl Printer {
+    fn newline(&mut self, level: usize) {
+        self.out.push('\n');
+        for _ in 0..level * self.options.indent {
+            self.out.push(' ');
+        }
+    }
+
+    /// Number of characters in the current line.
+    fn column(&self) -> usize {
+        let line_start = self.out.rfind('\n').map_or(0, |i| i + 1);
+        self.out[line_start..].chars().count()
+    }
+
+    fn trailing_comments(&mut self, comments: &[Comment]) {
+        for comment in comments {
+            self.out.push(' ');
+            self.out.push_str(comment.text);
+        }
+    }
+
+    fn value(&mut self, value: &Value, level: usize) {
+        match value {
+            Value::Scalar(text) => self.out.push_str(text),
+
+            Value::Array(array) => {
+                if array.elements.is_empty() && array.comments.is_empty() {
+                    self.out.push_str("[]");
+                } else if self.options.compact_arrays && is_compact(array) {
+                    self.compact_array(array, level);
+                } else {
+                    let elements: Vec<&Element> = array.elements.iter().collect();
+                    self.out.push('[');
+                    self.elements(&elements, &array.comments, level + 1);
+                    self.newline(level);
+                    self.out.push(']');
+                }
+            }
+
+            Value::Object(object) => {
+                if object.elements.is_empty() && object.comments.is_empty() {
+                    self.out.push_str("{}");
+                    return;
+                }
+                let mut members: Vec<&Element> = object.elements.iter().collect();
+                if self.options.sort_keys {
+                    members.sort_by(|a, b| {
+                        let a = &a.key.as_ref().unwrap().sort_key;
+                        let b = &b.key.as_ref().unwrap().sort_key;
+                        a.cmp(b)
+                    });
+                }
+                self.out.push('{');
+                self.elements(&members, &object.comments, level + 1);
+                self.newline(level);
+                self.out.push('}');
+            }
+        }
+    }
+
+    /// Prints the elements of an array or object, one per line, and the comments before the
+    /// closing bracket.
+    fn elements(&mut self, elements: &[&Element], comments: &[Comment], level: usize) {
+        for (i, element) in elements.iter().enumerate() {
+            for comment in &element.leading_comments {
+                self.newline(level);
+                self.out.push_str(comment.text);
+            }
+            self.newline(level);
+            if let Some(key) = &element.key {
+                self.out.push_str(key.text);
+                self.out.push(':');
+                let mut at_line_start = false;
+                for comment in &key.comments {
+                    self.out.push(' ');
+                    self.out.push_str(comment.text);
+                    if comment.kind != CommentKind::Block {
+                        self.newline(level + 1);
+                        at_line_start = true;
+                    }
+                }
+                if !at_line_start {
+                    self.out.push(' ');
+                }
+            }
+            self.value(&element.value, level);
+            if i + 1 != elements.len() {
+                self.out.push(',');
+            }
+            self.trailing_comments(&element.trailing_comments);
+        }
+        for comment in comments {
+            self.newline(level);
+            self.out.push_str(comment.text);
+        }
+    }
+
+    /// Prints an array of scalars on one line if it fits, otherwise with as many elements per
+    /// line as fit.
+    fn compact_array(&mut self, array: &Container, level: usize) {
+        let texts: Vec<&str> = array
+            .elements
+            .iter()
+            .map(|element| match element.value {
+                Value::Scalar(text) => text,
+                _ => unreachable!(),
+            })
+            .collect();
+
+        // Brackets, separators, and a comma after the array.
+        let one_line_width =
+            texts.iter().map(|text| text.chars().count()).sum::<usize>() + 2 * texts.len() + 1;
+        if self.column() + one_line_width <= self.options.line_width {
+            self.out.push('[');
+            for (i, text) in texts.iter().enumerate() {
+                if i != 0 {
+                    self.out.push_str(", ");
+                }
+                self.out.push_str(text);
+            }
+            self.out.push(']');
+            return;
+        }
+
+        self.out.push('[');
+        self.newline(level + 1);
+        let mut line_empty = true;
+        for (i, text) in texts.iter().enumerate() {
+            let comma = if i + 1 != texts.len() { "," } else { "" };
+            let width = text.chars().count() + comma.len();
+            if !line_empty && self.column() + 1 + width > self.options.line_width {
+                self.newline(level + 1);
+                line_empty = true;
+            }
+            if !line_empty {
+                self.out.push(' ');
+            }
+            write!(self.out, "{}{}", text, comma).unwrap();
+            line_empty = false;
+        }
+        self.newline(level);
+        self.out.push(']');
+    }
+}
+
+/// Whether an array can be printed with multiple elements per line: it has only scalars, and no
+/// comments.
+fn is_compact(array: &Container) -> bool {
+    array.comments.is_empty()
+        && array.elements.iter().all(|element| {
+            matches!(element.value, Value::Scalar(_))
+                && element.leading_comments.is_empty()
+                && element.trailing_comments.is_empty()
+        })
+}
+
+#[test]
+fn format_test() {
+    let input = r#"// config
+{"b": [1,2,3], /* after b */
+  "a": {"x": null, // after x
+  "y": [{"z": true}, []]},
+  "c": /* before c value */ "s",
+  "d": [
+
+  ]
+  // end of object
+} // after object
+/* end */"#;
+    let expected = r#"// config
+{
+  "b": [1, 2, 3], /* after b */
+  "a": {
+    "x": null, // after x
+    "y": [
+      {
+        "z": true
+      },
+      []
+    ]
+  },
+  "c": /* before c value */ "s",
+  "d": []
+  // end of object
+} // after object
+/* end */
+"#;
+    let options = FormatOptions::default();
+    assert_eq!(
+        format(input, ParseOptions::default(), options).unwrap(),
+        expected
+    );
+
+    let sorted = format(
+        input,
+        ParseOptions::default(),
+        FormatOptions {
+            sort_keys: true,
+            indent: 4,
+            ..options
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        sorted,
+        r#"// config
+{
+    "a": {
+        "x": null, // after x
+        "y": [
+            {
+                "z": true
+            },
+            []
+        ]
+    },
+    "b": [1, 2, 3], /* after b */
+    "c": /* before c value */ "s",
+    "d": []
+    // end of object
+} // after object
+/* end */
+"#
+    );
+}
+
+#[test]
+fn compact_array_test() {
+    let options = FormatOptions {
+        line_width: 20,
+        ..FormatOptions::default()
+    };
+    let format = |input: &str, options| format(input, ParseOptions::default(), options).unwrap();
+    assert_eq!(format("[1, 2, 3]", options), "[1, 2, 3]\n");
+    assert_eq!(
+        format("[100, 200, 300, 400, 500, 600]", options),
+        "[\n  100, 200, 300,\n  400, 500, 600\n]\n"
+    );
+    assert_eq!(format("[[1, 2], [3]]", options), "[\n  [1, 2],\n  [3]\n]\n");
+    assert_eq!(
+        format(
+            "[1, 2]",
+            FormatOptions {
+                compact_arrays: false,
+                ..options
+            }
+        ),
+        "[\n  1,\n  2\n]\n"
+    );
+    assert_eq!(format("[1, // a\n 2]", options), "[\n  1, // a\n  2\n]\n");
+}
+
+#[test]
+fn format_error_tests() {
+    for (str, error) in crate::test_common::unexpected_token_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(
+            format(&str, ParseOptions::default(), FormatOptions::default()),
+            Err(error)
+        );
+    }
+}
+
+#[test]
+fn idempotency_tests() {
+    let default = ParseOptions::default();
+    let inputs = crate::test_common::ast_tests()
+        .into_iter()
+        .map(|(str, _)| (str, default))
+        .chain(
+            crate::test_common::comment_option_event_tests()
+                .into_iter()
+                .map(|(str, _)| (str, crate::test_common::comment_options())),
+        )
+        .chain(
+            crate::test_common::json5_ast_tests()
+                .into_iter()
+                .map(|(str, _)| (str, crate::test_common::json5_options())),
+        );
+    let format_options = [
+        FormatOptions::default(),
+        FormatOptions {
+            sort_keys: true,
+            compact_arrays: false,
+            ..FormatOptions::default()
+        },
+        FormatOptions {
+            line_width: 10,
+            ..FormatOptions::default()
+        },
+    ];
+    for (str, parse_options) in inputs {
+        for format_options in format_options {
+            println!("Formatting {:?} with {:?}", str, format_options);
+            let formatted = format(&str, parse_options, format_options).unwrap();
+            assert_eq!(
+                format(&formatted, parse_options, format_options).unwrap(),
+                formatted
+            );
+            if !format_options.sort_keys {
+                assert_eq!(
+                    crate::parse_ast_with_options(&formatted, parse_options),
+                    crate::parse_ast_with_options(&str, parse_options)
+                );
+            }
+        }
+    }
+}
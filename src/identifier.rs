@@ -0,0 +1,22 @@
+use std::iter::Peekable;
+
+/// Whether `char` starts a JSON5 identifier.
+pub(crate) fn is_identifier_start(char: char) -> bool {
+    char.is_alphabetic() || char == '_' || char == '$'
+}
+
+/// Scans the rest of a JSON5 identifier. Expects the first character to be consumed.
+///
+/// Returns the byte offset after the identifier.
+pub(crate) fn scan_identifier<I: Iterator<Item = (usize, char)>>(
+    iter: &mut Peekable<I>,
+    input_size: usize,
+) -> usize {
+    while let Some((byte_offset, char)) = iter.peek().copied() {
+        if !(is_identifier_start(char) || char.is_alphanumeric()) {
+            return byte_offset;
+        }
+        iter.next();
+    }
+    input_size
+}
@@ -0,0 +1,1004 @@
+use crate::escape::{decode_string, scan_string};
+use crate::event_listener::EventHandler;
+use crate::event_parser::EventParser;
+use crate::event_to_tree::TreeBuilder;
+use crate::{
+    parse_events_with_options, ControlFlow, Dialect, FinishListener, Json, ParseError, ParseEvent,
+    ParseEventKind, ParseOptions, Span, SpannedJson, SpannedJsonKind,
+};
+
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::fmt;
+
+/// A parsed [JSONPath](https://www.rfc-editor.org/rfc/rfc9535) query, evaluated on parse events
+/// without building an AST.
+///
+/// Supported syntax:
+///
+/// - Child segments: `.name`, `.*`, and bracketed selectors: `['name']`, `["name"]`, `[*]`,
+///   `[1]`, `[1:5:2]`, or a union of them as in `['a', 'b', 0]`.
+/// - Descendant segments: `..name`, `..*`, `..[...]`.
+/// - Filter selectors comparing a value relative to the current node with a literal, as in
+///   `[?@.price < 10]` or `[?(@['name'] == "a")]`, or testing that a value exists, as in
+///   `[?@.tags]`. Literals are numbers, strings, `true`, `false` and `null`.
+///
+/// Negative array indices, slice bounds and steps need the length of the array, and are not
+/// supported.
+///
+/// Arrays and objects are built to an AST from their events only when a filter selects from them,
+/// or with [JsonPathOptions::materialize], when they are matched. Matches are reported when the
+/// matched value ends, so a match inside another match is reported first.
+///
+/// Arrays and objects that can't have matches are skipped with
+/// [EventParser::skip_rest_of_container], or with [ControlFlow::SkipValue] in the listener, so
+/// only the errors that they check are reported in them.
+#[derive(Debug)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+/// Options for evaluating a [JsonPath].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct JsonPathOptions {
+    /// Options for parsing the input. [ParseOptions::recover_errors] is not supported.
+    pub parse_options: ParseOptions,
+
+    /// Parse the matched values to [Json]. Disabled by default.
+    pub materialize: bool,
+}
+
+/// A value matched by a [JsonPath].
+#[derive(Debug, PartialEq)]
+pub struct JsonPathMatch {
+    /// Location of the value, as in [SpannedJson::span].
+    pub span: Span,
+
+    /// The value, with [JsonPathOptions::materialize].
+    pub value: Option<Json>,
+}
+
+/// An error in a [JsonPath] query.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPathError {
+    /// Byte offset of the error in the query.
+    pub byte_offset: usize,
+
+    pub kind: JsonPathErrorKind,
+}
+
+/// Kinds of [JsonPathError]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonPathErrorKind {
+    InvalidSyntax,
+
+    /// A negative array index, slice bound or step, which are not supported.
+    NegativeIndex,
+}
+
+/// An iterator of the [JsonPathMatch]es in an input, parsed with
+/// [parse_events][crate::parse_events]. Stops after the first parse error.
+pub struct JsonPathQuery<'a> {
+    parser: EventParser<'a>,
+    evaluator: Evaluator<'a>,
+    done: bool,
+}
+
+/// An [EventListener][crate::EventListener] that collects the [JsonPathMatch]es in an input, for
+/// [parse_events_push][crate::parse_events_push].
+pub struct JsonPathListener<'a> {
+    evaluator: Evaluator<'a>,
+    error: Option<ParseError>,
+}
+
+#[derive(Debug)]
+struct Segment {
+    /// Whether the selectors select the descendants of the current nodes, instead of the
+    /// children.
+    descendant: bool,
+
+    selectors: Vec<Selector>,
+}
+
+#[derive(Debug)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(usize),
+    Slice {
+        start: usize,
+        end: Option<usize>,
+        step: usize,
+    },
+    Filter(Filter),
+}
+
+/// A filter selector: selects the children for which the value at `path` exists, and when
+/// `comparison` is set, compares true with the literal.
+#[derive(Debug)]
+struct Filter {
+    path: Vec<Key>,
+    comparison: Option<(Comparison, Literal)>,
+}
+
+/// An object key or array index.
+#[derive(Debug, PartialEq)]
+enum Key {
+    Name(String),
+    Index(usize),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug)]
+enum Literal {
+    Number(f64),
+    Str(String),
+    Bool(bool),
+    Null,
+}
+
+impl JsonPath {
+    /// Parses a JSONPath query.
+    pub fn parse(path: &str) -> Result<JsonPath, JsonPathError> {
+        PathParser { path, pos: 0 }.parse()
+    }
+
+    /// Evaluates the query on `input`.
+    pub fn query<'a>(&'a self, input: &'a str, options: JsonPathOptions) -> JsonPathQuery<'a> {
+        let evaluator = Evaluator::new(self, input, options);
+        JsonPathQuery {
+            parser: parse_events_with_options(input, evaluator.options.parse_options),
+            evaluator,
+            done: false,
+        }
+    }
+
+    /// Makes a [JsonPathListener] for evaluating the query on `input`.
+    pub fn listener<'a>(
+        &'a self,
+        input: &'a str,
+        options: JsonPathOptions,
+    ) -> JsonPathListener<'a> {
+        JsonPathListener {
+            evaluator: Evaluator::new(self, input, options),
+            error: None,
+        }
+    }
+
+    /// The states of a child with `key` of a node in `states`. A state is the number of segments
+    /// matched by the path to the node, a node with all of the segments matched is a match.
+    /// `child` is the value of the child, for filters.
+    fn child_states(&self, states: &[usize], key: &Key, child: Option<&SpannedJson>) -> Vec<usize> {
+        let mut child_states = vec![];
+        for &state in states {
+            let segment = match self.segments.get(state) {
+                Some(segment) => segment,
+                None => continue,
+            };
+            if segment.descendant && !child_states.contains(&state) {
+                child_states.push(state);
+            }
+            if segment
+                .selectors
+                .iter()
+                .any(|selector| selector.matches(key, child))
+                && !child_states.contains(&(state + 1))
+            {
+                child_states.push(state + 1);
+            }
+        }
+        child_states
+    }
+
+    /// Whether the children of a node in `states` are selected by filters.
+    fn has_filter(&self, states: &[usize]) -> bool {
+        states.iter().any(|state| {
+            self.segments.get(*state).is_some_and(|segment| {
+                segment
+                    .selectors
+                    .iter()
+                    .any(|selector| matches!(selector, Selector::Filter(_)))
+            })
+        })
+    }
+
+    fn is_match(&self, states: &[usize]) -> bool {
+        states.contains(&self.segments.len())
+    }
+}
+
+impl Selector {
+    fn matches(&self, key: &Key, child: Option<&SpannedJson>) -> bool {
+        match (self, key) {
+            (Selector::Name(name), Key::Name(key)) => name == key,
+            (Selector::Wildcard, _) => true,
+            (Selector::Index(index), Key::Index(key)) => index == key,
+            (Selector::Slice { start, end, step }, Key::Index(index)) => {
+                *step != 0
+                    && index >= start
+                    && end.is_none_or(|end| *index < end)
+                    && (index - start) % step == 0
+            }
+            (Selector::Filter(filter), _) => child.is_some_and(|child| filter.matches(child)),
+            _ => false,
+        }
+    }
+}
+
+impl Filter {
+    fn matches(&self, node: &SpannedJson) -> bool {
+        let mut value = Some(node);
+        for key in &self.path {
+            value = value.and_then(|value| match (&value.kind, key) {
+                (SpannedJsonKind::Object(members), Key::Name(name)) => members
+                    .iter()
+                    .find(|(member_key, _)| member_key.key == *name)
+                    .map(|(_, member)| member),
+                (SpannedJsonKind::Array(elements), Key::Index(index)) => elements.get(*index),
+                _ => None,
+            });
+        }
+        let ordering = match (value, &self.comparison) {
+            (None, None) => return false,
+            (Some(_), None) => return true,
+            (None, Some(_)) => None,
+            (Some(value), Some((_, literal))) => compare(value, literal),
+        };
+        let (comparison, _) = self.comparison.as_ref().unwrap();
+        match comparison {
+            Comparison::Eq => ordering == Some(Ordering::Equal),
+            Comparison::Ne => ordering != Some(Ordering::Equal),
+            Comparison::Lt => ordering == Some(Ordering::Less),
+            Comparison::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Comparison::Gt => ordering == Some(Ordering::Greater),
+            Comparison::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        }
+    }
+}
+
+/// Compares numbers and strings, and booleans and nulls for equality.
+fn compare(value: &SpannedJson, literal: &Literal) -> Option<Ordering> {
+    let number = match &value.kind {
+        SpannedJsonKind::Int(i) => Some(*i as f64),
+        SpannedJsonKind::NegInt(i) => Some(*i as f64),
+        SpannedJsonKind::Float(f) => Some(*f),
+        SpannedJsonKind::Number(number) => number.parse().ok(),
+        _ => None,
+    };
+    match (&value.kind, literal) {
+        (_, Literal::Number(literal)) => number?.partial_cmp(literal),
+        (SpannedJsonKind::String(str), Literal::Str(literal)) => Some(str.cmp(literal)),
+        (SpannedJsonKind::Bool(b), Literal::Bool(literal)) if b == literal => Some(Ordering::Equal),
+        (SpannedJsonKind::Null, Literal::Null) => Some(Ordering::Equal),
+        _ => None,
+    }
+}
+
+impl<'a> Iterator for JsonPathQuery<'a> {
+    type Item = Result<JsonPathMatch, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(match_) = self.evaluator.matches.pop_front() {
+                return Some(Ok(match_));
+            }
+            if self.done {
+                return None;
+            }
+            match self.parser.next() {
+                Some(Ok(event)) => {
+                    if self.evaluator.event(event) == ControlFlow::SkipValue {
+                        if let Err(error) = self.parser.skip_rest_of_container() {
+                            self.done = true;
+                            return Some(Err(error));
+                        }
+                    }
+                }
+                Some(Err(error)) => {
+                    self.done = true;
+                    return Some(Err(error));
+                }
+                None => self.done = true,
+            }
+        }
+    }
+}
+
+impl<'a> EventHandler for JsonPathListener<'a> {
+    fn on_event(&mut self, event: ParseEvent) -> ControlFlow {
+        self.evaluator.event(event)
+    }
+
+    fn on_error(&mut self, error: ParseError) {
+        self.error = Some(error);
+    }
+}
+
+impl<'a> FinishListener for JsonPathListener<'a> {
+    type Output = Vec<JsonPathMatch>;
+    type Error = ParseError;
+
+    /// Returns the matches, or the parse error.
+    fn finish(self) -> Result<Vec<JsonPathMatch>, ParseError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.evaluator.matches.into()),
+        }
+    }
+}
+
+/// The state of evaluating a [JsonPath] on events.
+struct Evaluator<'a> {
+    path: &'a JsonPath,
+    input: &'a str,
+    options: JsonPathOptions,
+
+    /// Arrays and objects in the path to the current value.
+    stack: Vec<Frame>,
+
+    /// An array or object that is built to an AST from its events, with its states. Arrays and
+    /// objects with filters are evaluated on the AST, and with [JsonPathOptions::materialize],
+    /// matched arrays and objects too.
+    tree: Option<(TreeBuilder<SpannedJson>, Vec<usize>)>,
+
+    /// Matches that are not reported yet.
+    matches: VecDeque<JsonPathMatch>,
+}
+
+struct Frame {
+    /// The states of the array or object, without the state of a match.
+    states: Vec<usize>,
+
+    is_object: bool,
+
+    /// In an object, whether the next string is a key.
+    expect_key: bool,
+
+    /// In an object, the last key. Not decoded when there are no states.
+    key: String,
+
+    /// In an array, index of the next element.
+    next_index: usize,
+
+    /// Byte offset of the array or object when it's a match.
+    match_start: Option<usize>,
+}
+
+impl<'a> Evaluator<'a> {
+    fn new(path: &'a JsonPath, input: &'a str, options: JsonPathOptions) -> Evaluator<'a> {
+        Evaluator {
+            path,
+            input,
+            options: JsonPathOptions {
+                parse_options: ParseOptions {
+                    recover_errors: false,
+                    ..options.parse_options
+                },
+                ..options
+            },
+            stack: vec![],
+            tree: None,
+            matches: VecDeque::new(),
+        }
+    }
+
+    /// Handles an event. Returns [ControlFlow::SkipValue] for an array or object that doesn't have
+    /// any matches, the caller skips it without passing its events.
+    fn event(&mut self, event: ParseEvent) -> ControlFlow {
+        let input = self.input;
+
+        if let Some((builder, _)) = self.tree.as_mut() {
+            if let Some(tree) = builder.add_input_event(event, input) {
+                let (_, states) = self.tree.take().unwrap();
+                self.evaluate_tree(&tree, &states);
+                self.end_value();
+            }
+            return ControlFlow::Continue;
+        }
+
+        let span = event.span;
+        match event.kind {
+            ParseEventKind::Comment { .. }
+            | ParseEventKind::StartDocument
+            | ParseEventKind::EndDocument
+            | ParseEventKind::Error(_) => return ControlFlow::Continue,
+
+            ParseEventKind::Str {
+                size_in_bytes,
+                has_escapes,
+            } => {
+                if let Some(frame) = self.stack.last_mut() {
+                    if frame.expect_key {
+                        if !frame.states.is_empty() {
+                            let start = event.byte_offset;
+                            let key = &input[start..start + size_in_bytes];
+                            frame.key = decode_string(key, has_escapes);
+                        }
+                        frame.expect_key = false;
+                        return ControlFlow::Continue;
+                    }
+                }
+            }
+
+            ParseEventKind::EndObject | ParseEventKind::EndArray => {
+                let frame = self.stack.pop().unwrap();
+                if let Some(start) = frame.match_start {
+                    self.add_match(Span::new(start, span.end), None);
+                }
+                self.end_value();
+                return ControlFlow::Continue;
+            }
+
+            _ => {}
+        }
+
+        let states = self.value_states();
+        let is_match = self.path.is_match(&states);
+
+        match event.kind {
+            ParseEventKind::StartObject | ParseEventKind::StartArray => {
+                let is_object = event.kind == ParseEventKind::StartObject;
+                if self.path.has_filter(&states) || (is_match && self.options.materialize) {
+                    let mut builder = TreeBuilder::new();
+                    builder.add_input_event(event, input);
+                    self.tree = Some((builder, states));
+                    return ControlFlow::Continue;
+                }
+                let states: Vec<usize> = states
+                    .into_iter()
+                    .filter(|state| *state != self.path.segments.len())
+                    .collect();
+                if states.is_empty() && !is_match {
+                    self.end_value();
+                    return ControlFlow::SkipValue;
+                }
+                self.stack.push(Frame {
+                    states,
+                    is_object,
+                    expect_key: is_object,
+                    key: String::new(),
+                    next_index: 0,
+                    match_start: if is_match { Some(span.start) } else { None },
+                });
+            }
+
+            _ => {
+                if is_match {
+                    // A tree builder makes the node of a scalar event right away.
+                    let value = if self.options.materialize {
+                        TreeBuilder::new().add_input_event(event, input)
+                    } else {
+                        None
+                    };
+                    self.add_match(span, value);
+                }
+                self.end_value();
+            }
+        }
+        ControlFlow::Continue
+    }
+
+    /// The states of the next value.
+    fn value_states(&mut self) -> Vec<usize> {
+        let path = self.path;
+        let frame = match self.stack.last_mut() {
+            Some(frame) => frame,
+            None => return vec![0],
+        };
+        let key = if frame.is_object {
+            Key::Name(std::mem::take(&mut frame.key))
+        } else {
+            frame.next_index += 1;
+            Key::Index(frame.next_index - 1)
+        };
+        path.child_states(&frame.states, &key, None)
+    }
+
+    /// Updates the current container after a value.
+    fn end_value(&mut self) {
+        if let Some(frame) = self.stack.last_mut() {
+            frame.expect_key = frame.is_object;
+        }
+    }
+
+    /// Evaluates the query on `node` in `states`.
+    fn evaluate_tree(&mut self, node: &SpannedJson, states: &[usize]) {
+        match &node.kind {
+            SpannedJsonKind::Array(elements) => {
+                for (index, element) in elements.iter().enumerate() {
+                    let key = Key::Index(index);
+                    let child_states = self.path.child_states(states, &key, Some(element));
+                    self.evaluate_tree(element, &child_states);
+                }
+            }
+            SpannedJsonKind::Object(members) => {
+                for (key, value) in members {
+                    let key = Key::Name(key.key.clone());
+                    let child_states = self.path.child_states(states, &key, Some(value));
+                    self.evaluate_tree(value, &child_states);
+                }
+            }
+            _ => {}
+        }
+        if self.path.is_match(states) {
+            let value = self.options.materialize.then(|| node.clone().into_json());
+            self.add_match(node.span, value);
+        }
+    }
+
+    fn add_match(&mut self, span: Span, value: Option<Json>) {
+        self.matches.push_back(JsonPathMatch { span, value });
+    }
+}
+
+/// A recursive descent parser for [JsonPath].
+struct PathParser<'p> {
+    path: &'p str,
+
+    /// Byte offset of the next character in `path`.
+    pos: usize,
+}
+
+impl<'p> PathParser<'p> {
+    fn parse(mut self) -> Result<JsonPath, JsonPathError> {
+        self.expect('$')?;
+        let mut segments = vec![];
+        while self.pos != self.path.len() {
+            let descendant = self.path[self.pos..].starts_with("..");
+            let dotted = if descendant {
+                self.pos += 2;
+                self.peek() != Some('[')
+            } else {
+                self.eat('.')
+            };
+
+            let selectors = if !dotted {
+                self.expect('[')?;
+                self.bracketed_selectors()?
+            } else if self.eat('*') {
+                vec![Selector::Wildcard]
+            } else {
+                vec![Selector::Name(self.name()?)]
+            };
+            segments.push(Segment {
+                descendant,
+                selectors,
+            });
+        }
+        Ok(JsonPath { segments })
+    }
+
+    /// Parses the selectors in brackets, after the `[`.
+    fn bracketed_selectors(&mut self) -> Result<Vec<Selector>, JsonPathError> {
+        let mut selectors = vec![];
+        loop {
+            self.skip_whitespace();
+            selectors.push(self.selector()?);
+            self.skip_whitespace();
+            if self.eat(']') {
+                return Ok(selectors);
+            }
+            self.expect(',')?;
+        }
+    }
+
+    fn selector(&mut self) -> Result<Selector, JsonPathError> {
+        match self.peek() {
+            Some('\'' | '"') => Ok(Selector::Name(self.string()?)),
+            Some('*') => {
+                self.pos += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some('?') => {
+                self.pos += 1;
+                Ok(Selector::Filter(self.filter()?))
+            }
+            _ => {
+                let start = self.index()?;
+                self.skip_whitespace();
+                if !self.eat(':') {
+                    return match start {
+                        Some(index) => Ok(Selector::Index(index)),
+                        None => Err(self.error(JsonPathErrorKind::InvalidSyntax)),
+                    };
+                }
+                self.skip_whitespace();
+                let end = self.index()?;
+                self.skip_whitespace();
+                let step = if self.eat(':') {
+                    self.skip_whitespace();
+                    self.index()?
+                } else {
+                    None
+                };
+                Ok(Selector::Slice {
+                    start: start.unwrap_or(0),
+                    end,
+                    step: step.unwrap_or(1),
+                })
+            }
+        }
+    }
+
+    /// Parses a filter after the `?`.
+    fn filter(&mut self) -> Result<Filter, JsonPathError> {
+        self.skip_whitespace();
+        let parenthesized = self.eat('(');
+        self.skip_whitespace();
+        self.expect('@')?;
+
+        let mut path = vec![];
+        loop {
+            if self.eat('.') {
+                path.push(Key::Name(self.name()?));
+            } else if self.eat('[') {
+                self.skip_whitespace();
+                let key = match self.peek() {
+                    Some('\'' | '"') => Key::Name(self.string()?),
+                    _ => match self.index()? {
+                        Some(index) => Key::Index(index),
+                        None => return Err(self.error(JsonPathErrorKind::InvalidSyntax)),
+                    },
+                };
+                path.push(key);
+                self.skip_whitespace();
+                self.expect(']')?;
+            } else {
+                break;
+            }
+        }
+
+        self.skip_whitespace();
+        let comparison = self.comparison();
+        let comparison = match comparison {
+            Some(comparison) => {
+                self.skip_whitespace();
+                Some((comparison, self.literal()?))
+            }
+            None => None,
+        };
+
+        if parenthesized {
+            self.skip_whitespace();
+            self.expect(')')?;
+        }
+        Ok(Filter { path, comparison })
+    }
+
+    fn comparison(&mut self) -> Option<Comparison> {
+        let operators = [
+            ("==", Comparison::Eq),
+            ("!=", Comparison::Ne),
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+        ];
+        for (operator, comparison) in operators {
+            if self.path[self.pos..].starts_with(operator) {
+                self.pos += operator.len();
+                return Some(comparison);
+            }
+        }
+        None
+    }
+
+    fn literal(&mut self) -> Result<Literal, JsonPathError> {
+        if let Some('\'' | '"') = self.peek() {
+            return Ok(Literal::Str(self.string()?));
+        }
+        let rest = &self.path[self.pos..];
+        let size_in_bytes = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.')))
+            .unwrap_or(rest.len());
+        let literal = match &rest[..size_in_bytes] {
+            "true" => Literal::Bool(true),
+            "false" => Literal::Bool(false),
+            "null" => Literal::Null,
+            number => match number.parse() {
+                Ok(number) => Literal::Number(number),
+                Err(_) => return Err(self.error(JsonPathErrorKind::InvalidSyntax)),
+            },
+        };
+        self.pos += size_in_bytes;
+        Ok(literal)
+    }
+
+    /// Parses a member name in dot notation.
+    fn name(&mut self) -> Result<String, JsonPathError> {
+        let rest = &self.path[self.pos..];
+        let size_in_bytes = rest
+            .char_indices()
+            .find(|(i, c)| {
+                let valid = c.is_alphabetic() || *c == '_' || !c.is_ascii();
+                !(valid || (*i != 0 && c.is_ascii_digit()))
+            })
+            .map_or(rest.len(), |(i, _)| i);
+        if size_in_bytes == 0 {
+            return Err(self.error(JsonPathErrorKind::InvalidSyntax));
+        }
+        self.pos += size_in_bytes;
+        Ok(rest[..size_in_bytes].to_string())
+    }
+
+    /// Parses a quoted string, with the escape sequences of JSON5 strings.
+    fn string(&mut self) -> Result<String, JsonPathError> {
+        let quote = self.peek().unwrap();
+        let start = self.pos + 1;
+        let mut chars = self.path[start..]
+            .char_indices()
+            .map(|(i, c)| (start + i, c));
+        let options = ParseOptions {
+            dialect: Dialect::Json5,
+            ..ParseOptions::default()
+        };
+        match scan_string(&mut chars, self.path.len(), quote, &options) {
+            Ok((closing_quote, has_escapes)) => {
+                self.pos = closing_quote + 1;
+                Ok(decode_string(&self.path[start..closing_quote], has_escapes))
+            }
+            Err(error) => Err(JsonPathError {
+                byte_offset: error.byte_offset,
+                kind: JsonPathErrorKind::InvalidSyntax,
+            }),
+        }
+    }
+
+    /// Parses an array index, `None` if there are no digits.
+    fn index(&mut self) -> Result<Option<usize>, JsonPathError> {
+        if self.peek() == Some('-') {
+            return Err(self.error(JsonPathErrorKind::NegativeIndex));
+        }
+        let rest = &self.path[self.pos..];
+        let size_in_bytes = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        if size_in_bytes == 0 {
+            return Ok(None);
+        }
+        match rest[..size_in_bytes].parse() {
+            Ok(index) => {
+                self.pos += size_in_bytes;
+                Ok(Some(index))
+            }
+            Err(_) => Err(self.error(JsonPathErrorKind::InvalidSyntax)),
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = &self.path[self.pos..];
+        self.pos += rest.len() - rest.trim_start_matches([' ', '\t', '\n', '\r']).len();
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.path[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, char: char) -> bool {
+        if self.peek() == Some(char) {
+            self.pos += char.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, char: char) -> Result<(), JsonPathError> {
+        if self.eat(char) {
+            Ok(())
+        } else {
+            Err(self.error(JsonPathErrorKind::InvalidSyntax))
+        }
+    }
+
+    fn error(&self, kind: JsonPathErrorKind) -> JsonPathError {
+        JsonPathError {
+            byte_offset: self.pos,
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for JsonPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte offset {}", self.kind, self.byte_offset)
+    }
+}
+
+impl std::error::Error for JsonPathError {}
+
+impl fmt::Display for JsonPathErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonPathErrorKind::InvalidSyntax => f.write_str("invalid JSONPath syntax"),
+            JsonPathErrorKind::NegativeIndex => f.write_str("negative indices are not supported"),
+        }
+    }
+}
+
+/// Evaluates `path` on `input` with the iterator and the listener, and returns the matched input
+/// texts.
+#[cfg(test)]
+fn query_texts<'a>(path: &str, input: &'a str) -> Result<Vec<&'a str>, ParseError> {
+    let path = JsonPath::parse(path).unwrap();
+    let options = JsonPathOptions::default();
+    let matches: Result<Vec<JsonPathMatch>, ParseError> = path.query(input, options).collect();
+    let listener = path.listener(input, options);
+    assert_eq!(crate::parse_events_push(input, listener), matches);
+    matches.map(|matches| {
+        matches
+            .iter()
+            .map(|match_| &input[match_.span.start..match_.span.end])
+            .collect()
+    })
+}
+
+#[test]
+fn parse_error_tests() {
+    let tests = [
+        ("", 0, JsonPathErrorKind::InvalidSyntax),
+        ("a", 0, JsonPathErrorKind::InvalidSyntax),
+        ("$a", 1, JsonPathErrorKind::InvalidSyntax),
+        ("$.", 2, JsonPathErrorKind::InvalidSyntax),
+        ("$.1", 2, JsonPathErrorKind::InvalidSyntax),
+        ("$...a", 3, JsonPathErrorKind::InvalidSyntax),
+        ("$[", 2, JsonPathErrorKind::InvalidSyntax),
+        ("$[1", 3, JsonPathErrorKind::InvalidSyntax),
+        ("$['a]", 5, JsonPathErrorKind::InvalidSyntax),
+        ("$[1,]", 4, JsonPathErrorKind::InvalidSyntax),
+        ("$[-1]", 2, JsonPathErrorKind::NegativeIndex),
+        ("$[1:-1]", 4, JsonPathErrorKind::NegativeIndex),
+        ("$[?@.a <]", 8, JsonPathErrorKind::InvalidSyntax),
+        ("$[?(@.a]", 7, JsonPathErrorKind::InvalidSyntax),
+        ("$[?a]", 3, JsonPathErrorKind::InvalidSyntax),
+    ];
+    for (path, byte_offset, kind) in tests {
+        println!("Parsing {:?}", path);
+        assert_eq!(
+            JsonPath::parse(path).unwrap_err(),
+            JsonPathError { byte_offset, kind }
+        );
+    }
+}
+
+#[test]
+fn query_tests() {
+    let input = r#"{
+        "store": {
+            "book": [
+                {"title": "A", "price": 8.95, "tags": ["x"]},
+                {"title": "B", "price": 12, "isbn": "1"},
+                {"title": "C", "price": 22.99, "isbn": "2"}
+            ],
+            "bicycle": {"color": "red", "price": 19.95}
+        },
+        "ab": [0, 1, 2, 3, 4, 5]
+    }"#;
+    let tests: &[(&str, &[&str])] = &[
+        ("$.store.bicycle.color", &[r#""red""#]),
+        ("$['store']['bicycle'][\"price\"]", &["19.95"]),
+        ("$.store.book[1].title", &[r#""B""#]),
+        ("$.store.book[5]", &[]),
+        ("$.missing.a", &[]),
+        ("$.store.bicycle.*", &[r#""red""#, "19.95"]),
+        ("$.store.book[*].title", &[r#""A""#, r#""B""#, r#""C""#]),
+        ("$.store.book[0, 2].price", &["8.95", "22.99"]),
+        ("$.ab[1:5:2]", &["1", "3"]),
+        ("$.ab[4:]", &["4", "5"]),
+        ("$.ab[:2]", &["0", "1"]),
+        ("$.ab[::0]", &[]),
+        ("$..price", &["8.95", "12", "22.99", "19.95"]),
+        ("$..book[2].isbn", &[r#""2""#]),
+        ("$..tags[0]", &[r#""x""#]),
+        (
+            "$..[0]",
+            &[
+                r#""x""#,
+                "{\"title\": \"A\", \"price\": 8.95, \"tags\": [\"x\"]}",
+                "0",
+            ],
+        ),
+        ("$.store.book[?@.isbn].title", &[r#""B""#, r#""C""#]),
+        ("$.store.book[?(@.price < 15)].title", &[r#""A""#, r#""B""#]),
+        (
+            "$.store.book[?@['price'] >= 12].title",
+            &[r#""B""#, r#""C""#],
+        ),
+        ("$.store.book[?@.title == 'C'].price", &["22.99"]),
+        ("$.store.book[?@.title != \"C\"].price", &["8.95", "12"]),
+        ("$.store.book[?@.tags[0] == 'x'].title", &[r#""A""#]),
+        ("$.store.book[?@.title > 1]", &[]),
+        ("$.ab[?@ > 3]", &["4", "5"]),
+        ("$..[?@ > 20]", &["22.99"]),
+    ];
+    for (path, expected) in tests {
+        println!("Query {:?}", path);
+        assert_eq!(query_texts(path, input), Ok(expected.to_vec()));
+    }
+
+    // Matches are reported when the value ends.
+    let input = r#"{"a": {"a": [1]}}"#;
+    assert_eq!(query_texts("$..a", input), Ok(vec!["[1]", r#"{"a": [1]}"#]));
+    assert_eq!(query_texts("$", "  [1] "), Ok(vec!["[1]"]));
+    assert_eq!(
+        query_texts("$.*", "[true, null, false]"),
+        Ok(vec!["true", "null", "false"])
+    );
+}
+
+#[test]
+fn materialize_test() {
+    let path = JsonPath::parse("$.a[?@.b == null]").unwrap();
+    let input = r#"{"a": [{"b": null}, {"b": 1}, {"b": null, "c": "x"}]}"#;
+    let options = JsonPathOptions {
+        materialize: true,
+        ..JsonPathOptions::default()
+    };
+    let matches: Vec<JsonPathMatch> = path.query(input, options).map(Result::unwrap).collect();
+    assert_eq!(
+        matches,
+        vec![
+            JsonPathMatch {
+                span: Span::new(7, 18),
+                value: Some(Json::Object(vec![(String::from("b"), Json::Null)])),
+            },
+            JsonPathMatch {
+                span: Span::new(30, 51),
+                value: Some(Json::Object(vec![
+                    (String::from("b"), Json::Null),
+                    (String::from("c"), Json::String(String::from("x")))
+                ])),
+            },
+        ]
+    );
+
+    // Matches in a match, with a parse error after them.
+    let path = JsonPath::parse("$..a").unwrap();
+    let input = r#"{"a": {"a": [1]}, "b": }"#;
+    let mut query = path.query(input, options);
+    let inner = || Json::Array(vec![Json::Int(1)]);
+    assert_eq!(
+        query.next(),
+        Some(Ok(JsonPathMatch {
+            span: Span::new(12, 15),
+            value: Some(inner()),
+        }))
+    );
+    assert_eq!(
+        query.next(),
+        Some(Ok(JsonPathMatch {
+            span: Span::new(6, 16),
+            value: Some(Json::Object(vec![(String::from("a"), inner())])),
+        }))
+    );
+    assert_eq!(query.next().unwrap().unwrap_err().byte_offset, 23);
+    let listener = path.listener(input, options);
+    assert_eq!(
+        crate::parse_events_push(input, listener)
+            .unwrap_err()
+            .byte_offset,
+        23
+    );
+}
+
+#[test]
+fn parse_error_query_tests() {
+    // Matches before the error are reported.
+    let input = r#"{"a": 1, "b": [}"#;
+    let path = JsonPath::parse("$.a").unwrap();
+    let mut query = path.query(input, JsonPathOptions::default());
+    assert_eq!(query.next().unwrap().unwrap().span, Span::new(6, 7));
+    assert_eq!(query.next().unwrap().unwrap_err().byte_offset, 15);
+    assert!(query.next().is_none());
+
+    assert_eq!(query_texts("$.a", input).unwrap_err().byte_offset, 15);
+}
@@ -0,0 +1,340 @@
+use crate::escape::decode_string;
+use crate::{
+    parse_events_with_options, Json, ParseError, ParseEvent, ParseEventKind, ParseOptions, Span,
+};
+
+use std::fmt;
+
+/// A parsed [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901): the object keys and array
+/// indices to a value, each preceded by a `/`, with `~` and `/` in keys escaped as `~0` and `~1`.
+/// The empty pointer is the whole document.
+///
+/// Array indices are decimal without leading zeros. The index `-`, past the end of an array,
+/// never has a value. In objects with duplicate keys the pointer selects the first member.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPointer {
+    keys: Vec<String>,
+}
+
+/// An error in a [JsonPointer].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPointerError {
+    /// Byte offset of the error in the pointer.
+    pub byte_offset: usize,
+
+    pub kind: JsonPointerErrorKind,
+}
+
+/// Kinds of [JsonPointerError]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonPointerErrorKind {
+    /// A pointer that is not empty and doesn't start with a `/`.
+    MissingSlash,
+
+    /// A `~` that is not followed by `0` or `1`.
+    InvalidEscape,
+}
+
+impl JsonPointer {
+    /// Parses a JSON Pointer.
+    pub fn parse(pointer: &str) -> Result<JsonPointer, JsonPointerError> {
+        if !pointer.is_empty() && !pointer.starts_with('/') {
+            return Err(JsonPointerError {
+                byte_offset: 0,
+                kind: JsonPointerErrorKind::MissingSlash,
+            });
+        }
+
+        let mut keys = vec![];
+        let mut key_start = 1;
+        for key in pointer.split('/').skip(1) {
+            let mut unescaped = String::with_capacity(key.len());
+            let mut chars = key.char_indices();
+            while let Some((i, char)) = chars.next() {
+                if char != '~' {
+                    unescaped.push(char);
+                    continue;
+                }
+                match chars.next() {
+                    Some((_, '0')) => unescaped.push('~'),
+                    Some((_, '1')) => unescaped.push('/'),
+                    _ => {
+                        return Err(JsonPointerError {
+                            byte_offset: key_start + i,
+                            kind: JsonPointerErrorKind::InvalidEscape,
+                        })
+                    }
+                }
+            }
+            keys.push(unescaped);
+            key_start += key.len() + 1;
+        }
+        Ok(JsonPointer { keys })
+    }
+
+    /// The unescaped object keys and array indices of the pointer.
+    pub fn keys(&self) -> &[String] {
+        &self.keys
+    }
+
+    /// The value at the pointer in `json`.
+    pub fn get<'a>(&self, json: &'a Json) -> Option<&'a Json> {
+        let mut value = json;
+        for key in &self.keys {
+            value = match value {
+                Json::Object(members) => &members.iter().find(|(k, _)| k == key)?.1,
+                Json::Array(elements) => elements.get(parse_index(key)?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+
+    /// The value at the pointer in `json`, mutably.
+    pub fn get_mut<'a>(&self, json: &'a mut Json) -> Option<&'a mut Json> {
+        let mut value = json;
+        for key in &self.keys {
+            value = match value {
+                Json::Object(members) => &mut members.iter_mut().find(|(k, _)| k == key)?.1,
+                Json::Array(elements) => elements.get_mut(parse_index(key)?)?,
+                _ => return None,
+            };
+        }
+        Some(value)
+    }
+}
+
+impl Json {
+    /// The value at the [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901) `pointer`, as in
+    /// [JsonPointer::get].
+    pub fn pointer(&self, pointer: &str) -> Result<Option<&Json>, JsonPointerError> {
+        Ok(JsonPointer::parse(pointer)?.get(self))
+    }
+
+    /// The value at the [JSON Pointer](https://www.rfc-editor.org/rfc/rfc6901) `pointer`, as in
+    /// [JsonPointer::get_mut].
+    pub fn pointer_mut(&mut self, pointer: &str) -> Result<Option<&mut Json>, JsonPointerError> {
+        Ok(JsonPointer::parse(pointer)?.get_mut(self))
+    }
+}
+
+/// Finds the location of the value at `pointer` in `input`, as in [SpannedJson::span], without
+/// building an AST.
+///
+/// Arrays and objects that are not on the path to the value, and the value itself, are skipped
+/// with [EventParser::skip_rest_of_container][crate::EventParser::skip_rest_of_container], which
+/// only checks strings, comments and bracket nesting. Parsing stops when the value ends or when
+/// the array or object that should have it ends, so errors after that are not reported.
+///
+/// [SpannedJson::span]: crate::SpannedJson::span
+pub fn find_pointer(input: &str, pointer: &JsonPointer) -> Result<Option<Span>, ParseError> {
+    find_pointer_with_options(input, pointer, ParseOptions::default())
+}
+
+/// Finds the location of the value at `pointer` in `input`, with the given options.
+/// [ParseOptions::recover_errors] is not supported.
+pub fn find_pointer_with_options(
+    input: &str,
+    pointer: &JsonPointer,
+    options: ParseOptions,
+) -> Result<Option<Span>, ParseError> {
+    let options = ParseOptions {
+        recover_errors: false,
+        ..options
+    };
+    let mut parser = parse_events_with_options(input, options);
+    let keys = pointer.keys();
+
+    // Number of keys matched by the path to the innermost array or object on the path to the
+    // value, `None` before the top-level value.
+    let mut depth: Option<usize> = None;
+    let mut in_object = false;
+    let mut expect_key = false;
+    let mut next_index = 0;
+
+    // In an object, whether the last key is on the path.
+    let mut key_matches = false;
+
+    while let Some(event) = parser.next() {
+        let ParseEvent {
+            kind,
+            byte_offset,
+            span,
+        } = event?;
+
+        let on_path = match kind {
+            ParseEventKind::Comment { .. }
+            | ParseEventKind::StartDocument
+            | ParseEventKind::EndDocument
+            | ParseEventKind::Error(_) => continue,
+
+            // The array or object on the path ended without the value.
+            ParseEventKind::EndObject | ParseEventKind::EndArray => return Ok(None),
+
+            ParseEventKind::Str {
+                size_in_bytes,
+                has_escapes,
+            } if expect_key => {
+                let depth = depth.unwrap();
+                let key = &input[byte_offset..byte_offset + size_in_bytes];
+                key_matches = decode_string(key, has_escapes) == keys[depth];
+                expect_key = false;
+                continue;
+            }
+
+            _ => match depth {
+                None => true,
+                Some(_) if in_object => key_matches,
+                Some(depth) => {
+                    next_index += 1;
+                    parse_index(&keys[depth]) == Some(next_index - 1)
+                }
+            },
+        };
+        let matched = depth.map_or(0, |depth| depth + 1);
+
+        let is_container = matches!(
+            kind,
+            ParseEventKind::StartObject | ParseEventKind::StartArray
+        );
+        if on_path && matched == keys.len() {
+            if is_container {
+                // The parser is in the array or object after its start event.
+                return parser.skip_rest_of_container();
+            }
+            return Ok(Some(span));
+        }
+
+        if on_path {
+            if !is_container {
+                return Ok(None);
+            }
+            depth = Some(matched);
+            in_object = kind == ParseEventKind::StartObject;
+            next_index = 0;
+        } else if is_container {
+            parser.skip_rest_of_container()?;
+        }
+        expect_key = in_object;
+    }
+
+    Ok(None)
+}
+
+/// Parses a key as an array index, in decimal without leading zeros.
+pub(crate) fn parse_index(key: &str) -> Option<usize> {
+    if key.is_empty() || (key.len() > 1 && key.starts_with('0')) {
+        return None;
+    }
+    if !key.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    key.parse().ok()
+}
+
+impl fmt::Display for JsonPointerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at byte offset {}", self.kind, self.byte_offset)
+    }
+}
+
+impl std::error::Error for JsonPointerError {}
+
+impl fmt::Display for JsonPointerErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonPointerErrorKind::MissingSlash => f.write_str("JSON Pointer must start with '/'"),
+            JsonPointerErrorKind::InvalidEscape => {
+                f.write_str("'~' must be followed by '0' or '1' in JSON Pointer")
+            }
+        }
+    }
+}
+
+#[test]
+fn parse_tests() {
+    let tests: &[(&str, &[&str])] = &[
+        ("", &[]),
+        ("/", &[""]),
+        ("/a/0/b", &["a", "0", "b"]),
+        ("/a~1b/m~0n/~01", &["a/b", "m~n", "~1"]),
+        ("//x/", &["", "x", ""]),
+    ];
+    for (pointer, keys) in tests {
+        assert_eq!(JsonPointer::parse(pointer).unwrap().keys(), *keys);
+    }
+
+    let tests = [
+        ("a", 0, JsonPointerErrorKind::MissingSlash),
+        ("/a~", 2, JsonPointerErrorKind::InvalidEscape),
+        ("/a/b~2", 4, JsonPointerErrorKind::InvalidEscape),
+    ];
+    for (pointer, byte_offset, kind) in tests {
+        assert_eq!(
+            JsonPointer::parse(pointer).unwrap_err(),
+            JsonPointerError { byte_offset, kind }
+        );
+    }
+}
+
+#[test]
+fn pointer_tests() {
+    let input = r#"{"a": [{"b": 1}, {"b": [true, null]}], "c/d": "x", "a": 2, "": {"01": 3}}"#;
+    let tests: &[(&str, Option<&str>)] = &[
+        ("", Some(input)),
+        ("/a", Some(r#"[{"b": 1}, {"b": [true, null]}]"#)),
+        ("/a/0/b", Some("1")),
+        ("/a/1/b/0", Some("true")),
+        ("/a/1/b/1", Some("null")),
+        ("/c~1d", Some(r#""x""#)),
+        ("//01", Some("3")),
+        ("/a/2", None),
+        ("/a/-", None),
+        ("/a/01", None),
+        ("/a/0/b/0", None),
+        ("/a/b", None),
+        ("/x", None),
+        ("//1", None),
+    ];
+    for (pointer, expected) in tests {
+        println!("Pointer {:?}", pointer);
+        let json_pointer = JsonPointer::parse(pointer).unwrap();
+        let span = find_pointer(input, &json_pointer).unwrap();
+        assert_eq!(span.map(|span| &input[span.start..span.end]), *expected);
+
+        let ast = crate::parse_ast(input).unwrap();
+        let expected_value = expected.map(|value| crate::parse_ast(value).unwrap());
+        assert_eq!(ast.pointer(pointer).unwrap(), expected_value.as_ref());
+    }
+
+    let mut ast = crate::parse_ast(input).unwrap();
+    *ast.pointer_mut("/a/0/b").unwrap().unwrap() = Json::Null;
+    assert_eq!(ast.pointer("/a/0/b"), Ok(Some(&Json::Null)));
+    assert_eq!(ast.pointer_mut("/x"), Ok(None));
+    assert_eq!(
+        ast.pointer("a").unwrap_err().kind,
+        JsonPointerErrorKind::MissingSlash
+    );
+}
+
+#[test]
+fn find_pointer_error_tests() {
+    // Errors after the value are not reported.
+    let pointer = JsonPointer::parse("/a").unwrap();
+    let span = find_pointer(r#"{"a": [1], "b": }"#, &pointer).unwrap();
+    assert_eq!(span, Some(Span::new(6, 9)));
+
+    // In skipped values, only string, comment and bracket nesting errors are reported.
+    let span = find_pointer(r#"{"b": [1,, 2], "a": 1}"#, &pointer).unwrap();
+    assert_eq!(span, Some(Span::new(20, 21)));
+    let error = find_pointer(r#"{"b": [1}, "a": 1}"#, &pointer).unwrap_err();
+    assert_eq!(error.byte_offset, 8);
+
+    // JSON5 keys.
+    let options = ParseOptions {
+        dialect: crate::Dialect::Json5,
+        ..ParseOptions::default()
+    };
+    let span = find_pointer_with_options("{b: 1, a: 'x', }", &pointer, options).unwrap();
+    assert_eq!(span, Some(Span::new(10, 13)));
+}
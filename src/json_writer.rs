@@ -0,0 +1,491 @@
+use crate::error::{Token, END_OF_INPUT, KEY_OR_END_OBJECT, VALUE, VALUE_OR_END_ARRAY};
+use crate::serializer::{write_str, write_value, IoAdapter};
+use crate::{CommentKind, Json, SerializeOptions};
+
+use std::fmt::{self, Write};
+use std::io;
+
+/// Writes JSON text to an [io::Write], one value, object key or bracket at a time.
+///
+/// The writer checks that the calls make a single valid JSON value, as the parsers check the input,
+/// and adds the commas and colons. Calls that would make the output invalid return
+/// [JsonWriterError::UnexpectedCall] without writing anything. After a call fails with an I/O
+/// error the output may end in the middle of a token, and the later calls return
+/// [JsonWriterError::Failed]. With [SerializeOptions::indent] the output is pretty-printed.
+///
+/// Comments are written before the next value, object key or closing bracket, on their own lines,
+/// or after an object key when the next call is the value.
+pub struct JsonWriter<W: io::Write> {
+    out: IoAdapter<W>,
+    options: SerializeOptions,
+
+    /// Open arrays and objects.
+    container_stack: Vec<Container>,
+
+    /// Whether the top-level value is started.
+    started: bool,
+
+    /// Comments that are written before the next value or closing bracket, with the delimiters
+    /// and without the newline at the end. The separator before the next value is not known
+    /// until the next call.
+    comments: Vec<(String, CommentKind)>,
+
+    /// Whether a write failed with an I/O error.
+    failed: bool,
+}
+
+/// An error in [JsonWriter].
+#[derive(Debug)]
+pub enum JsonWriterError {
+    Io(io::Error),
+
+    /// A call that would make the output invalid.
+    UnexpectedCall {
+        /// The token written by the call: [Token::String] for object keys, [Token::Value] for
+        /// values other than arrays and objects, [Token::EndOfInput] for
+        /// [finish][JsonWriter::finish].
+        found: Token,
+
+        /// Tokens that would be valid.
+        expected: &'static [Token],
+    },
+
+    /// A comment with the end delimiter in the text: `*/` in a block comment, or a newline in a
+    /// line or hash comment.
+    InvalidComment,
+
+    /// A call after a call that failed with an I/O error.
+    Failed,
+}
+
+struct Container {
+    is_object: bool,
+
+    /// Number of values written to the container. Object keys are counted as values.
+    num_values: usize,
+}
+
+impl<W: io::Write> JsonWriter<W> {
+    pub fn new(out: W, options: SerializeOptions) -> JsonWriter<W> {
+        JsonWriter {
+            out: IoAdapter { out, error: None },
+            options,
+            container_stack: vec![],
+            started: false,
+            comments: vec![],
+            failed: false,
+        }
+    }
+
+    /// Whether the next call should be [key][JsonWriter::key] or
+    /// [end_object][JsonWriter::end_object].
+    pub fn expects_key(&self) -> bool {
+        self.expected() == KEY_OR_END_OBJECT
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), JsonWriterError> {
+        self.start_container(true)
+    }
+
+    pub fn end_object(&mut self) -> Result<(), JsonWriterError> {
+        self.end_container(Token::EndObject)
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), JsonWriterError> {
+        self.start_container(false)
+    }
+
+    pub fn end_array(&mut self) -> Result<(), JsonWriterError> {
+        self.end_container(Token::EndArray)
+    }
+
+    /// Writes an object key, escaped as in [write_json][crate::write_json].
+    pub fn key(&mut self, key: &str) -> Result<(), JsonWriterError> {
+        self.check(Token::String)?;
+        self.write(|writer| {
+            writer.start_value()?;
+            write_str(&mut writer.out, key, &writer.options)
+        })
+    }
+
+    /// Writes a value as in [write_json][crate::write_json].
+    pub fn value(&mut self, json: &Json) -> Result<(), JsonWriterError> {
+        let found = match json {
+            Json::Array(_) => Token::StartArray,
+            Json::Object(_) => Token::StartObject,
+            _ => Token::Value,
+        };
+        self.check(found)?;
+        self.write(|writer| {
+            writer.start_value()?;
+            let level = writer.container_stack.len();
+            write_value(&mut writer.out, json, &writer.options, level)
+        })
+    }
+
+    /// Writes a string value, escaped as in [write_json][crate::write_json].
+    pub fn string(&mut self, str: &str) -> Result<(), JsonWriterError> {
+        self.check(Token::Value)?;
+        self.write(|writer| {
+            writer.start_value()?;
+            write_str(&mut writer.out, str, &writer.options)
+        })
+    }
+
+    /// Writes `text` as a value, without checking it. `text` should be a single JSON value, e.g. a
+    /// number that doesn't fit into [Json].
+    pub fn raw_value(&mut self, text: &str) -> Result<(), JsonWriterError> {
+        self.check(Token::Value)?;
+        self.write(|writer| {
+            writer.start_value()?;
+            writer.out.write_str(text)
+        })
+    }
+
+    /// Adds a comment with the text between the delimiters, e.g. `" a "` for `/* a */`.
+    pub fn comment(&mut self, text: &str, kind: CommentKind) -> Result<(), JsonWriterError> {
+        if self.failed {
+            return Err(JsonWriterError::Failed);
+        }
+        let comment = match kind {
+            CommentKind::Line | CommentKind::Hash if text.contains(['\n', '\r']) => {
+                return Err(JsonWriterError::InvalidComment)
+            }
+            CommentKind::Block if text.contains("*/") => {
+                return Err(JsonWriterError::InvalidComment)
+            }
+            CommentKind::Line => format!("//{}", text),
+            CommentKind::Hash => format!("#{}", text),
+            CommentKind::Block => format!("/*{}*/", text),
+        };
+        self.comments.push((comment, kind));
+        Ok(())
+    }
+
+    /// Adds a comment with the delimiters, without checking it.
+    pub(crate) fn raw_comment(&mut self, comment: &str, kind: CommentKind) {
+        let comment = comment.trim_end_matches(['\n', '\r']);
+        self.comments.push((comment.to_string(), kind));
+    }
+
+    /// Writes the comments after the value and returns the output. Fails if the value is not
+    /// complete.
+    pub fn finish(mut self) -> Result<W, JsonWriterError> {
+        self.check(Token::EndOfInput)?;
+        self.write(|writer| {
+            for (comment, kind) in std::mem::take(&mut writer.comments) {
+                if writer.options.indent.is_some() {
+                    writer.out.write_char('\n')?;
+                }
+                writer.write_comment(&comment, kind)?;
+            }
+            Ok(())
+        })?;
+        Ok(self.out.out)
+    }
+
+    /// Tokens valid at the current location.
+    fn expected(&self) -> &'static [Token] {
+        match self.container_stack.last() {
+            None if self.started => END_OF_INPUT,
+            None => VALUE,
+            Some(container) if container.is_object && container.num_values % 2 == 1 => VALUE,
+            Some(container) if container.is_object => KEY_OR_END_OBJECT,
+            Some(_) => VALUE_OR_END_ARRAY,
+        }
+    }
+
+    fn check(&self, found: Token) -> Result<(), JsonWriterError> {
+        if self.failed {
+            return Err(JsonWriterError::Failed);
+        }
+        let expected = self.expected();
+        let is_value = matches!(found, Token::Value | Token::StartObject | Token::StartArray);
+        if expected.contains(&found) || (is_value && expected.contains(&Token::Value)) {
+            Ok(())
+        } else {
+            Err(JsonWriterError::UnexpectedCall { found, expected })
+        }
+    }
+
+    /// Writes the separator and comments before a value or object key.
+    fn start_value(&mut self) -> fmt::Result {
+        let level = self.container_stack.len();
+        let after_key = match self.container_stack.last_mut() {
+            None => {
+                self.started = true;
+                false
+            }
+            Some(container) => {
+                container.num_values += 1;
+                container.is_object && container.num_values % 2 == 0
+            }
+        };
+
+        if after_key {
+            self.out.write_char(':')?;
+            if self.options.indent.is_some() {
+                self.out.write_char(' ')?;
+            }
+            for (comment, kind) in std::mem::take(&mut self.comments) {
+                self.write_comment(&comment, kind)?;
+                if self.options.indent.is_some() {
+                    match kind {
+                        CommentKind::Block => self.out.write_char(' ')?,
+                        CommentKind::Line | CommentKind::Hash => self.write_newline(level)?,
+                    }
+                }
+            }
+            return Ok(());
+        }
+
+        if let Some(container) = self.container_stack.last() {
+            if container.num_values != 1 {
+                self.out.write_char(',')?;
+            }
+            self.write_newline(level)?;
+        }
+        for (comment, kind) in std::mem::take(&mut self.comments) {
+            self.write_comment(&comment, kind)?;
+            self.write_newline(level)?;
+        }
+        Ok(())
+    }
+
+    fn start_container(&mut self, is_object: bool) -> Result<(), JsonWriterError> {
+        self.check(if is_object {
+            Token::StartObject
+        } else {
+            Token::StartArray
+        })?;
+        self.write(|writer| {
+            writer.start_value()?;
+            writer.out.write_char(if is_object { '{' } else { '[' })
+        })?;
+        self.container_stack.push(Container {
+            is_object,
+            num_values: 0,
+        });
+        Ok(())
+    }
+
+    /// Writes the comments at the end of the container and the closing bracket.
+    fn end_container(&mut self, found: Token) -> Result<(), JsonWriterError> {
+        self.check(found)?;
+        let container = self.container_stack.last().unwrap();
+        let (is_object, num_values) = (container.is_object, container.num_values);
+        let level = self.container_stack.len() - 1;
+        self.write(|writer| {
+            let is_empty = num_values == 0 && writer.comments.is_empty();
+            for (comment, kind) in std::mem::take(&mut writer.comments) {
+                writer.write_newline(level + 1)?;
+                writer.write_comment(&comment, kind)?;
+            }
+            if !is_empty {
+                writer.write_newline(level)?;
+            }
+            writer.out.write_char(if is_object { '}' } else { ']' })
+        })?;
+        self.container_stack.pop();
+        Ok(())
+    }
+
+    /// Writes the comment. When minifying, line and hash comments are followed by a newline.
+    fn write_comment(&mut self, comment: &str, kind: CommentKind) -> fmt::Result {
+        self.out.write_str(comment)?;
+        if self.options.indent.is_none() && kind != CommentKind::Block {
+            self.out.write_char('\n')?;
+        }
+        Ok(())
+    }
+
+    /// When indenting, writes a newline and indentation for `level`.
+    fn write_newline(&mut self, level: usize) -> fmt::Result {
+        if let Some(indent) = self.options.indent {
+            self.out.write_char('\n')?;
+            for _ in 0..level * indent {
+                self.out.write_char(' ')?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Calls `write`, returns the I/O error if it fails. The writer fails the later calls after
+    /// an I/O error, as the output may be incomplete.
+    fn write(
+        &mut self,
+        write: impl FnOnce(&mut Self) -> fmt::Result,
+    ) -> Result<(), JsonWriterError> {
+        write(self).map_err(|fmt::Error| {
+            self.failed = true;
+            JsonWriterError::Io(self.out.error.take().unwrap())
+        })
+    }
+}
+
+impl fmt::Display for JsonWriterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonWriterError::Io(error) => write!(f, "I/O error: {}", error),
+            JsonWriterError::UnexpectedCall { found, expected } => {
+                write!(f, "unexpected {}", found)?;
+                for (i, token) in expected.iter().enumerate() {
+                    let separator = if i == 0 { ", expected" } else { " or" };
+                    write!(f, "{} {}", separator, token)?;
+                }
+                Ok(())
+            }
+            JsonWriterError::InvalidComment => f.write_str("comment delimiter in comment text"),
+            JsonWriterError::Failed => f.write_str("an earlier write failed"),
+        }
+    }
+}
+
+impl std::error::Error for JsonWriterError {}
+
+#[test]
+fn writer_test() {
+    let options = SerializeOptions {
+        indent: Some(2),
+        ..SerializeOptions::default()
+    };
+    let mut writer = JsonWriter::new(vec![], options);
+    writer.comment(" a", CommentKind::Line).unwrap();
+    writer.begin_object().unwrap();
+    writer.key("a\n").unwrap();
+    writer.comment(" b ", CommentKind::Block).unwrap();
+    writer.begin_array().unwrap();
+    writer.value(&Json::Int(1)).unwrap();
+    writer.raw_value("1.50").unwrap();
+    writer.value(&Json::Array(vec![Json::Null])).unwrap();
+    writer.comment(" c", CommentKind::Hash).unwrap();
+    writer.end_array().unwrap();
+    writer.key("b").unwrap();
+    writer.string("x").unwrap();
+    writer.end_object().unwrap();
+    let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+    assert_eq!(
+        output,
+        r#"// a
+{
+  "a\n": /* b */ [
+    1,
+    1.50,
+    [
+      null
+    ]
+    # c
+  ],
+  "b": "x"
+}"#
+    );
+
+    let mut writer = JsonWriter::new(vec![], SerializeOptions::default());
+    writer.begin_array().unwrap();
+    writer.begin_object().unwrap();
+    writer.end_object().unwrap();
+    writer.string("a").unwrap();
+    writer.end_array().unwrap();
+    assert_eq!(writer.finish().unwrap(), br#"[{},"a"]"#);
+}
+
+#[test]
+fn misuse_tests() {
+    fn unexpected(result: Result<(), JsonWriterError>) -> (Token, &'static [Token]) {
+        match result {
+            Err(JsonWriterError::UnexpectedCall { found, expected }) => (found, expected),
+            _ => panic!(),
+        }
+    }
+
+    let mut writer = JsonWriter::new(vec![], SerializeOptions::default());
+    assert_eq!(unexpected(writer.key("a")), (Token::String, VALUE));
+    assert_eq!(unexpected(writer.end_array()), (Token::EndArray, VALUE));
+    writer.begin_object().unwrap();
+    assert_eq!(
+        unexpected(writer.value(&Json::Null)),
+        (Token::Value, KEY_OR_END_OBJECT)
+    );
+    assert_eq!(
+        unexpected(writer.end_array()),
+        (Token::EndArray, KEY_OR_END_OBJECT)
+    );
+    writer.key("a").unwrap();
+    assert_eq!(unexpected(writer.end_object()), (Token::EndObject, VALUE));
+    assert_eq!(unexpected(writer.key("b")), (Token::String, VALUE));
+    writer.begin_array().unwrap();
+    assert_eq!(
+        unexpected(writer.key("b")),
+        (Token::String, VALUE_OR_END_ARRAY)
+    );
+    writer.end_array().unwrap();
+    writer.end_object().unwrap();
+    assert_eq!(
+        unexpected(writer.begin_array()),
+        (Token::StartArray, END_OF_INPUT)
+    );
+    assert!(matches!(
+        writer.comment("*/", CommentKind::Block),
+        Err(JsonWriterError::InvalidComment)
+    ));
+    assert!(matches!(
+        writer.comment("\n", CommentKind::Line),
+        Err(JsonWriterError::InvalidComment)
+    ));
+
+    // Nothing is written by the failed calls.
+    assert_eq!(writer.finish().unwrap(), br#"{"a":[]}"#);
+
+    let mut writer = JsonWriter::new(vec![], SerializeOptions::default());
+    writer.begin_array().unwrap();
+    let error = writer.finish().err().unwrap();
+    assert_eq!(
+        error.to_string(),
+        "unexpected end of input, expected value or ']'"
+    );
+}
+
+#[test]
+fn io_error_test() {
+    /// Fails the writes after `num_writes` writes.
+    struct FailingWriter {
+        num_writes: usize,
+        out: Vec<u8>,
+    }
+
+    impl io::Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.num_writes == 0 {
+                return Err(io::Error::other("failed"));
+            }
+            self.num_writes -= 1;
+            self.out.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    // The `,` before the object is written, the `{` fails.
+    let out = FailingWriter {
+        num_writes: 3,
+        out: vec![],
+    };
+    let mut writer = JsonWriter::new(out, SerializeOptions::default());
+    writer.begin_array().unwrap();
+    writer.value(&Json::Int(1)).unwrap();
+    assert!(matches!(writer.begin_object(), Err(JsonWriterError::Io(_))));
+
+    // The later calls fail without writing.
+    assert!(matches!(
+        writer.begin_object(),
+        Err(JsonWriterError::Failed)
+    ));
+    assert!(matches!(writer.end_array(), Err(JsonWriterError::Failed)));
+    assert!(matches!(
+        writer.comment(" a ", CommentKind::Block),
+        Err(JsonWriterError::Failed)
+    ));
+    assert!(matches!(writer.finish(), Err(JsonWriterError::Failed)));
+}
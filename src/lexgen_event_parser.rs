@@ -1,14 +1,33 @@
+use crate::comment::check_comment;
+use crate::error::{
+    Token, COLON, COMMA_OR_END_ARRAY, COMMA_OR_END_OBJECT, END_OF_INPUT, KEY_OR_END_OBJECT, VALUE,
+    VALUE_OR_END_ARRAY,
+};
+use crate::escape::scan_string;
 use crate::event_parser::Container;
-use crate::{ParseError, ParseEvent, ParseEventKind};
-
-use std::str::FromStr;
+use crate::number::Number;
+use crate::whitespace::check_whitespace;
+use crate::{
+    CommentKind, ParseError, ParseErrorKind, ParseEvent, ParseEventKind, ParseOptions, Span,
+};
 
 use lexgen_util::{LexerError, LexerErrorKind};
 
 /// Parses input to [ParseEvent]s, using [lexgen].
-pub fn parse_events(input: &str) -> LexgenIteratorAdapter {
+pub fn parse_events(input: &str) -> LexgenIteratorAdapter<'_> {
+    parse_events_with_options(input, ParseOptions::default())
+}
+
+/// Parses input to [ParseEvent]s with the given options, using [lexgen].
+pub fn parse_events_with_options(input: &str, options: ParseOptions) -> LexgenIteratorAdapter<'_> {
     LexgenIteratorAdapter {
-        lexer: Lexer::new(input),
+        lexer: Lexer::new_with_state(
+            input,
+            LexerState {
+                options,
+                ..LexerState::default()
+            },
+        ),
     }
 }
 
@@ -25,109 +44,145 @@ impl<'a> Iterator for LexgenIteratorAdapter<'a> {
             Err(LexerError {
                 location: loc,
                 kind,
-            }) => Err(ParseError {
-                byte_offset: loc.byte_idx,
-                reason: match kind {
-                    LexerErrorKind::InvalidToken => "invalid token",
-                    LexerErrorKind::Custom(reason) => reason,
-                },
+            }) => Err(match kind {
+                LexerErrorKind::InvalidToken => {
+                    ParseError::new(loc.byte_idx, ParseErrorKind::InvalidToken)
+                }
+                LexerErrorKind::Custom(err) => err,
             }),
         })
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Default)]
 struct LexerState {
-    container_stack: Vec<Container>,
+    /// Containers the lexer is in, with byte offsets of the opening brackets.
+    container_stack: Vec<(Container, usize)>,
+    options: ParseOptions,
+
+    /// Nesting depth of the block comment being lexed.
+    block_comment_depth: u32,
+
+    /// The rule to switch back to after lexing a block comment.
+    block_comment_resume: Option<LexerRule>,
 }
 
 lexgen::lexer! {
     Lexer(LexerState) -> ParseEvent;
 
-    type Error = &'static str;
+    type Error = ParseError;
+
+    let json_whitespace = [' ' '\t' '\n' '\r'];
+
+    let other_whitespace = $$whitespace # $json_whitespace;
+
+    let line_comment = "//" (_ # '\n')* '\n'?;
 
-    let comment = "//" (_ # '\n')* '\n';
+    let hash_comment = '#' (_ # '\n')* '\n'?;
+
+    let number = '-'? ['0'-'9']+ ('.' ['0'-'9']+)? (['e' 'E'] ['+' '-']? ['0'-'9']+)?;
+
+    let string = '"' ((_ # ['"' '\\']) | '\\' _)* '"';
 
     rule Init {
-        $$ascii_whitespace,
+        $json_whitespace,
+
+        $other_whitespace =? whitespace,
+
+        $line_comment =? line_comment,
 
-        $comment => comment,
+        $hash_comment =? |lexer| hash_comment(lexer, LexerRule::Init),
+
+        "/*" =? |lexer| block_comment(lexer, LexerRule::Init),
 
         '[' => |lexer| {
-            let (loc, _) = lexer.match_loc();
-            lexer.state().container_stack.push(Container::Array);
-            lexer.return_(ParseEvent::new(loc.byte_idx, ParseEventKind::StartArray))
+            let span = match_span(lexer);
+            lexer.state().container_stack.push((Container::Array, span.start));
+            lexer.return_(ParseEvent::new(span, ParseEventKind::StartArray))
         },
 
         ']' =? |lexer| {
-            let (loc, _) = lexer.match_loc();
-            lexer.reset_match();
-            if let Some(Container::Array) = lexer.state().container_stack.pop() {
+            let span = match_span(lexer);
+            if let Some((Container::Array, _)) = lexer.state().container_stack.last() {
+                lexer.state().container_stack.pop();
+                lexer.reset_match();
                 update_state(lexer);
-                lexer.return_(Ok(ParseEvent::new(loc.byte_idx, ParseEventKind::EndArray)))
+                lexer.return_(Ok(ParseEvent::new(span, ParseEventKind::EndArray)))
             } else {
-                lexer.return_(Err("unexpected ']'"))
+                unexpected(lexer, LexerRule::Init)
             }
         },
 
         '{' => |lexer| {
-            let (loc, _) = lexer.match_loc();
-            lexer.state().container_stack.push(Container::Object);
+            let span = match_span(lexer);
+            lexer.state().container_stack.push((Container::Object, span.start));
             lexer.switch::<()>(LexerRule::ObjectExpectKeyValue);
-            lexer.return_(ParseEvent::new(loc.byte_idx, ParseEventKind::StartObject))
+            lexer.return_(ParseEvent::new(span, ParseEventKind::StartObject))
         },
 
         "true" => |lexer| {
-            let (loc, _) = lexer.match_loc();
+            let span = match_span(lexer);
             update_state(lexer);
-            lexer.return_(ParseEvent::new(loc.byte_idx, ParseEventKind::Bool(true)))
+            lexer.return_(ParseEvent::new(span, ParseEventKind::Bool(true)))
         },
 
         "false" => |lexer| {
-            let (loc, _) = lexer.match_loc();
+            let span = match_span(lexer);
             update_state(lexer);
-            lexer.return_(ParseEvent::new(loc.byte_idx, ParseEventKind::Bool(false)))
+            lexer.return_(ParseEvent::new(span, ParseEventKind::Bool(false)))
         },
 
         "null" => |lexer| {
-            let (loc, _) = lexer.match_loc();
+            let span = match_span(lexer);
             update_state(lexer);
-            lexer.return_(ParseEvent::new(loc.byte_idx, ParseEventKind::Null))
+            lexer.return_(ParseEvent::new(span, ParseEventKind::Null))
         },
 
-        // Ignore overflows.
-        ['0'-'9']+ => |lexer| {
-            let (loc, _) = lexer.match_loc();
+        $number =? |lexer| {
+            let span = match_span(lexer);
+            let options = lexer.state().options;
+            let number = Number::parse(lexer.match_(), span.start, &options);
             update_state(lexer);
-            lexer.return_(ParseEvent::new(loc.byte_idx, ParseEventKind::Int(u64::from_str(lexer.match_()).unwrap())))
+            lexer.return_(number.map(|number| ParseEvent::new(span, number.into())))
         },
 
-        '"' (_ # '"')* '"' => |lexer| {
-            let (match_start, match_end) = lexer.match_loc();
+        $string =? |lexer| {
+            let event = string(lexer);
             update_state(lexer);
-            lexer.return_(ParseEvent::new(
-                match_start.byte_idx + 1,
-                ParseEventKind::Str {
-                    size_in_bytes: match_end.byte_idx - match_start.byte_idx - 2
-                },
-            ))
+            lexer.return_(event)
         },
+
+        $ =? |lexer| unexpected(lexer, LexerRule::Init),
+
+        _ =? |lexer| unexpected(lexer, LexerRule::Init),
     }
 
     rule Done {
-        $$ascii_whitespace,
+        $json_whitespace,
+
+        $other_whitespace =? whitespace,
 
-        $comment => comment,
+        $line_comment =? line_comment,
+
+        $hash_comment =? |lexer| hash_comment(lexer, LexerRule::Done),
+
+        "/*" =? |lexer| block_comment(lexer, LexerRule::Done),
 
         $,
 
-        _ =? |lexer| lexer.return_(Err("trailing characters")),
+        _ =? |lexer| unexpected(lexer, LexerRule::Done),
     }
 
     rule ArrayExpectComma {
-        $$ascii_whitespace,
+        $json_whitespace,
+
+        $other_whitespace =? whitespace,
+
+        $line_comment =? line_comment,
 
-        $comment => comment,
+        $hash_comment =? |lexer| hash_comment(lexer, LexerRule::ArrayExpectComma),
+
+        "/*" =? |lexer| block_comment(lexer, LexerRule::ArrayExpectComma),
 
         ',' => |lexer| {
             lexer.reset_match();
@@ -135,60 +190,79 @@ lexgen::lexer! {
         },
 
         ']' => |lexer| {
-            let (loc, _) = lexer.match_loc();
+            let span = match_span(lexer);
             let state = lexer.state().container_stack.pop();
-            debug_assert_eq!(state, Some(Container::Array));
+            debug_assert_eq!(state.map(|(container, _)| container), Some(Container::Array));
             update_state(lexer);
-            lexer.return_(ParseEvent::new(loc.byte_idx, ParseEventKind::EndArray))
+            lexer.return_(ParseEvent::new(span, ParseEventKind::EndArray))
         },
 
-        _ =? |lexer|
-            lexer.return_(Err("unexpected character or end of input while parsing array")),
+        _ =? |lexer| unexpected(lexer, LexerRule::ArrayExpectComma),
+
+        $ =? |lexer| unexpected(lexer, LexerRule::ArrayExpectComma),
     }
 
     rule ObjectExpectKeyValue {
-        $$ascii_whitespace,
+        $json_whitespace,
 
-        $comment => comment,
+        $other_whitespace =? whitespace,
+
+        $line_comment =? line_comment,
+
+        $hash_comment =? |lexer| hash_comment(lexer, LexerRule::ObjectExpectKeyValue),
+
+        "/*" =? |lexer| block_comment(lexer, LexerRule::ObjectExpectKeyValue),
 
         '}' => |lexer| {
-            let (loc, _) = lexer.match_loc();
+            let span = match_span(lexer);
             let state = lexer.state().container_stack.pop();
-            debug_assert_eq!(state, Some(Container::Object));
+            debug_assert_eq!(state.map(|(container, _)| container), Some(Container::Object));
             update_state(lexer);
-            lexer.return_(ParseEvent::new(loc.byte_idx, ParseEventKind::EndObject))
+            lexer.return_(ParseEvent::new(span, ParseEventKind::EndObject))
         },
 
-        '"' (_ # '"')* '"' => |lexer| {
-            let (match_start, match_end) = lexer.match_loc();
+        $string =? |lexer| {
+            let event = string(lexer);
             lexer.switch::<()>(LexerRule::ObjectExpectColon);
-            lexer.return_(ParseEvent::new(match_start.byte_idx + 1, ParseEventKind::Str {
-                size_in_bytes: match_end.byte_idx - match_start.byte_idx - 2
-            }))
+            lexer.return_(event)
         },
 
-        _ =? |lexer|
-            lexer.return_(Err("unexpected character or end of input while parsing object")),
+        _ =? |lexer| unexpected(lexer, LexerRule::ObjectExpectKeyValue),
+
+        $ =? |lexer| unexpected(lexer, LexerRule::ObjectExpectKeyValue),
     }
 
     rule ObjectExpectColon {
-        $$ascii_whitespace,
+        $json_whitespace,
+
+        $other_whitespace =? whitespace,
+
+        $line_comment =? line_comment,
+
+        $hash_comment =? |lexer| hash_comment(lexer, LexerRule::ObjectExpectColon),
 
-        $comment => comment,
+        "/*" =? |lexer| block_comment(lexer, LexerRule::ObjectExpectColon),
 
         ':' => |lexer| {
             lexer.reset_match();
             lexer.switch(LexerRule::Init)
         },
 
-        _ =? |lexer|
-            lexer.return_(Err("unexpected character or end of input while parsing object")),
+        _ =? |lexer| unexpected(lexer, LexerRule::ObjectExpectColon),
+
+        $ =? |lexer| unexpected(lexer, LexerRule::ObjectExpectColon),
     }
 
     rule ObjectExpectComma {
-        $$ascii_whitespace,
+        $json_whitespace,
+
+        $other_whitespace =? whitespace,
+
+        $line_comment =? line_comment,
+
+        $hash_comment =? |lexer| hash_comment(lexer, LexerRule::ObjectExpectComma),
 
-        $comment => comment,
+        "/*" =? |lexer| block_comment(lexer, LexerRule::ObjectExpectComma),
 
         ',' => |lexer| {
             lexer.reset_match();
@@ -196,39 +270,191 @@ lexgen::lexer! {
         },
 
         '}' => |lexer| {
-            let (loc, _) = lexer.match_loc();
+            let span = match_span(lexer);
             let state = lexer.state().container_stack.pop();
-            debug_assert_eq!(state, Some(Container::Object));
+            debug_assert_eq!(state.map(|(container, _)| container), Some(Container::Object));
             update_state(lexer);
-            lexer.return_(ParseEvent::new(loc.byte_idx, ParseEventKind::EndObject))
+            lexer.return_(ParseEvent::new(span, ParseEventKind::EndObject))
         },
+
+        _ =? |lexer| unexpected(lexer, LexerRule::ObjectExpectComma),
+
+        $ =? |lexer| unexpected(lexer, LexerRule::ObjectExpectComma),
+    }
+
+    rule BlockComment {
+        "*/" =? |lexer| {
+            let state = lexer.state();
+            state.block_comment_depth -= 1;
+            if state.block_comment_depth != 0 {
+                return lexer.continue_();
+            }
+            let resume = state.block_comment_resume.take().unwrap();
+            lexer.switch::<()>(resume);
+            comment(lexer, CommentKind::Block)
+        },
+
+        "/*" => |lexer| {
+            let state = lexer.state();
+            if state.options.nested_block_comments {
+                state.block_comment_depth += 1;
+            }
+            lexer.continue_()
+        },
+
+        $ =? |lexer| {
+            let (loc, _) = lexer.match_loc();
+            lexer.return_(Err(ParseError::new(loc.byte_idx, ParseErrorKind::UnterminatedComment)))
+        },
+
+        _ => |lexer| lexer.continue_(),
     }
 }
 
 fn comment<I: Clone + Iterator<Item = char>>(
     lexer: &mut Lexer<'_, I>,
-) -> lexgen_util::SemanticActionResult<ParseEvent> {
-    let (match_start, match_end) = lexer.match_loc();
-    lexer.return_(ParseEvent::new(
-        match_start.byte_idx,
+    kind: CommentKind,
+) -> lexgen_util::SemanticActionResult<Result<ParseEvent, ParseError>> {
+    let span = match_span(lexer);
+    lexer.return_(Ok(ParseEvent::new(
+        span,
         ParseEventKind::Comment {
-            size_in_bytes: match_end.byte_idx - match_start.byte_idx,
+            size_in_bytes: span.end - span.start,
+            kind,
         },
-    ))
+    )))
+}
+
+/// Skips a whitespace character other than the JSON whitespace characters, if allowed.
+fn whitespace<I: Clone + Iterator<Item = char>>(
+    lexer: &mut Lexer<'_, I>,
+) -> lexgen_util::SemanticActionResult<Result<ParseEvent, ParseError>> {
+    let (loc, _) = lexer.match_loc();
+    let char = lexer.match_().chars().next().unwrap();
+    let options = lexer.state().options;
+    if let Err(err) = check_whitespace(loc.byte_idx, char, &options) {
+        return lexer.return_(Err(err));
+    }
+    lexer.reset_match();
+    lexer.continue_()
+}
+
+fn line_comment<I: Clone + Iterator<Item = char>>(
+    lexer: &mut Lexer<'_, I>,
+) -> lexgen_util::SemanticActionResult<Result<ParseEvent, ParseError>> {
+    let (loc, _) = lexer.match_loc();
+    if let Err(err) = check_comment(loc.byte_idx, &lexer.state().options) {
+        return lexer.return_(Err(err));
+    }
+    if !lexer.state().options.line_comments {
+        return lexer.return_(Err(ParseError::new(
+            loc.byte_idx,
+            ParseErrorKind::InvalidComment,
+        )));
+    }
+    comment(lexer, CommentKind::Line)
+}
+
+/// Generates a hash comment event, or an error if hash comments are disabled. `rule` is the
+/// current rule, for errors.
+fn hash_comment<I: Clone + Iterator<Item = char>>(
+    lexer: &mut Lexer<'_, I>,
+    rule: LexerRule,
+) -> lexgen_util::SemanticActionResult<Result<ParseEvent, ParseError>> {
+    let (loc, _) = lexer.match_loc();
+    if !lexer.state().options.hash_comments {
+        return unexpected(lexer, rule);
+    }
+    if let Err(err) = check_comment(loc.byte_idx, &lexer.state().options) {
+        return lexer.return_(Err(err));
+    }
+    comment(lexer, CommentKind::Hash)
+}
+
+/// Starts lexing a block comment, continues with `resume` after the comment.
+fn block_comment<I: Clone + Iterator<Item = char>>(
+    lexer: &mut Lexer<'_, I>,
+    resume: LexerRule,
+) -> lexgen_util::SemanticActionResult<Result<ParseEvent, ParseError>> {
+    let (loc, _) = lexer.match_loc();
+    if let Err(err) = check_comment(loc.byte_idx, &lexer.state().options) {
+        return lexer.return_(Err(err));
+    }
+    let state = lexer.state();
+    if !state.options.block_comments {
+        return lexer.return_(Err(ParseError::new(
+            loc.byte_idx,
+            ParseErrorKind::InvalidComment,
+        )));
+    }
+    state.block_comment_depth = 1;
+    state.block_comment_resume = Some(resume);
+    lexer.switch(LexerRule::BlockComment)
+}
+
+/// Validates escape sequences in the matched string and generates the string event.
+fn string<I: Clone + Iterator<Item = char>>(
+    lexer: &mut Lexer<'_, I>,
+) -> Result<ParseEvent, ParseError> {
+    let span = match_span(lexer);
+    let options = lexer.state().options;
+    let start = span.start + 1;
+    let mut chars = lexer.match_()[1..]
+        .char_indices()
+        .map(|(byte_idx, char)| (start + byte_idx, char));
+    let (_, has_escapes) = scan_string(&mut chars, span.end, '"', &options)?;
+    Ok(ParseEvent::str(span, true, has_escapes))
+}
+
+/// Span of the matched token.
+fn match_span<I: Clone + Iterator<Item = char>>(lexer: &Lexer<'_, I>) -> Span {
+    let (start, end) = lexer.match_loc();
+    Span::new(start.byte_idx, end.byte_idx)
+}
+
+/// Generates an [ParseErrorKind::UnexpectedToken] error for the matched token, or end of input.
+/// `rule` is the current rule.
+fn unexpected<I: Clone + Iterator<Item = char>>(
+    lexer: &mut Lexer<'_, I>,
+    rule: LexerRule,
+) -> lexgen_util::SemanticActionResult<Result<ParseEvent, ParseError>> {
+    let (loc, _) = lexer.match_loc();
+    let found = Token::at(lexer.match_(), 0);
+    let container = lexer.state().container_stack.last().copied();
+    let expected: &'static [Token] = match rule {
+        LexerRule::Init => match container {
+            Some((Container::Array, _)) => VALUE_OR_END_ARRAY,
+            _ => VALUE,
+        },
+        LexerRule::Done => END_OF_INPUT,
+        LexerRule::ArrayExpectComma => COMMA_OR_END_ARRAY,
+        LexerRule::ObjectExpectKeyValue => KEY_OR_END_OBJECT,
+        LexerRule::ObjectExpectColon => COLON,
+        LexerRule::ObjectExpectComma => COMMA_OR_END_OBJECT,
+        LexerRule::BlockComment => &[],
+    };
+    lexer.return_(Err(ParseError::new(
+        loc.byte_idx,
+        ParseErrorKind::UnexpectedToken {
+            found,
+            expected,
+            container_byte_offset: container.map(|(_, byte_offset)| byte_offset),
+        },
+    )))
 }
 
 /// After parsing a value, update the parser state based on the current container.
 fn update_state<I: Clone + Iterator<Item = char>>(lexer: &mut Lexer<'_, I>) {
     let current_container = lexer.state().container_stack.last().copied();
     lexer.switch::<()>(match current_container {
-        Some(Container::Array) => LexerRule::ArrayExpectComma,
-        Some(Container::Object) => LexerRule::ObjectExpectComma,
+        Some((Container::Array, _)) => LexerRule::ArrayExpectComma,
+        Some((Container::Object, _)) => LexerRule::ObjectExpectComma,
         None => LexerRule::Done,
     });
 }
 
 #[cfg(test)]
-fn collect_events(input: &str) -> (Vec<ParseEventKind>, Option<LexerError<&'static str>>) {
+fn collect_events(input: &str) -> (Vec<ParseEventKind>, Option<LexerError<ParseError>>) {
     let mut events: Vec<ParseEventKind> = vec![];
     for event in Lexer::new(input) {
         match event {
@@ -248,3 +474,77 @@ fn event_tests() {
         assert_eq!(error, None);
     }
 }
+
+#[test]
+fn overflow_error_tests() {
+    for (str, byte_offset) in crate::test_common::overflow_error_tests() {
+        println!("Parsing {:?}", str);
+        let error = parse_events(&str).find_map(Result::err).unwrap();
+        assert_eq!(error.byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn lossless_number_tests() {
+    let options = ParseOptions {
+        lossless_numbers: true,
+        ..crate::ParseOptions::default()
+    };
+    for (str, events) in crate::test_common::lossless_number_event_tests() {
+        println!("Parsing {:?}", str);
+        let events_ = parse_events_with_options(&str, options)
+            .map(|ev| ev.unwrap().kind)
+            .collect::<Vec<_>>();
+        assert_eq!(events_, events);
+    }
+}
+
+#[test]
+fn comment_option_tests() {
+    for (str, events) in crate::test_common::comment_option_event_tests() {
+        println!("Parsing {:?}", str);
+        let events_ = parse_events_with_options(&str, crate::test_common::comment_options())
+            .map(|ev| ev.unwrap().kind)
+            .collect::<Vec<_>>();
+        assert_eq!(events_, events);
+    }
+}
+
+#[test]
+fn comment_error_tests() {
+    for (str, options, byte_offset) in crate::test_common::comment_error_tests() {
+        println!("Parsing {:?}", str);
+        let error = parse_events_with_options(&str, options)
+            .find_map(Result::err)
+            .unwrap();
+        assert_eq!(error.byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn escape_error_tests() {
+    for (str, byte_offset) in crate::test_common::escape_error_tests() {
+        println!("Parsing {:?}", str);
+        let error = parse_events(&str).find_map(Result::err).unwrap();
+        assert_eq!(error.byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn strict_error_tests() {
+    for (str, byte_offset, kind) in crate::test_common::strict_error_tests() {
+        println!("Parsing {:?}", str);
+        let error = parse_events_with_options(&str, crate::test_common::strict_options())
+            .find_map(Result::err);
+        assert_eq!(error, Some(ParseError { byte_offset, kind }));
+    }
+}
+
+#[test]
+fn unexpected_token_tests() {
+    for (str, error) in crate::test_common::unexpected_token_tests() {
+        println!("Parsing {:?}", str);
+        let error_ = parse_events(&str).find_map(Result::err);
+        assert_eq!(error_, Some(error));
+    }
+}
@@ -6,15 +6,56 @@ mod event;
 /// Defines the listener type, for the "push" parsing.
 mod event_listener;
 
+/// Defines the parse error types.
+mod error;
+
+/// Defines the parser options.
+mod options;
+
 /// Defines the AST without comments and locations.
 mod simple_ast;
 
+/// Defines the AST with source locations.
+mod spanned_ast;
+
+/// Implements scanning and decoding string escape sequences, shared by the parsers.
+mod escape;
+
+/// Implements scanning and parsing numbers, shared by the parsers.
+mod number;
+
+/// Implements scanning comments, shared by the parsers.
+mod comment;
+
+/// Implements scanning JSON5 identifiers, shared by the parsers.
+mod identifier;
+
+/// Implements checking whitespace, shared by the parsers.
+mod whitespace;
+
+/// Implements skipping arrays and objects without parsing their values, shared by the parsers.
+mod skip;
+
 /// Implements an event parser.
 mod event_parser;
 
 /// Implements "push" event parser.
 mod event_push_parser;
 
+/// Implements a "push" event parser that is fed the input in chunks.
+mod chunked_parser;
+
+/// Implements an event parser that reads the input from an `io::Read`.
+mod reader_parser;
+
+/// Implements an event parser that reads the input from a `futures_io::AsyncRead`.
+#[cfg(feature = "async")]
+mod async_parser;
+
+/// Implements parsing inputs with multiple documents: JSON Lines, RFC 7464 JSON text sequences, and
+/// concatenated JSON.
+mod document_parser;
+
 /// Implement an event parser using `lexgen`.
 mod lexgen_event_parser;
 
@@ -24,6 +65,15 @@ mod listener_impl;
 /// Implements an AST parser.
 mod simple_parser;
 
+/// Implements extracting the values at a set of paths from events, without building an AST.
+mod extractor;
+
+/// Implements JSON Pointer lookups in [Json] and in the input text.
+mod json_pointer;
+
+/// Implements evaluating JSONPath queries on events, without building an AST.
+mod json_path;
+
 /// Implements a parser that extracts timestamps from events, without building an AST.
 mod timestamp_parser;
 
@@ -33,37 +83,92 @@ mod event_to_tree;
 /// Implements collecting parse events from a "push" event parser.
 mod push_to_events;
 
+/// Implements a lossless concrete syntax tree built from parse events.
+mod cst;
+
+/// Implements serializing [Json] to text.
+mod serializer;
+
+/// Implements writing JSON text one value at a time.
+mod json_writer;
+
+/// Implements an event listener that writes JSON text.
+mod event_writer;
+
+/// Implements formatting JSON with comments.
+mod formatter;
+
+/// Implements mapping byte offsets to lines and columns.
+mod line_index;
+
+/// Implements rendering parse errors for humans.
+mod diagnostic;
+
 /// Implements input generation for benchmarks.
 mod input_gen;
 
 #[cfg(test)]
 mod test_common;
 
-pub use event::{ParseEvent, ParseEventKind};
-pub use event_listener::EventListener;
-pub use event_parser::parse_events;
-pub use event_push_parser::parse as parse_events_push;
-pub use event_to_tree::event_to_tree;
-pub use lexgen_event_parser::parse_events as parse_events_lexgen;
+#[cfg(feature = "async")]
+pub use async_parser::{
+    event_to_tree as event_to_tree_async, parse_events as parse_events_async,
+    parse_events_with_options as parse_events_async_with_options, AsyncReaderParser,
+};
+pub use chunked_parser::ChunkedParser;
+pub use cst::{
+    event_to_cst, GreenElement, GreenNode, GreenToken, SyntaxElement, SyntaxKind, SyntaxNode,
+    SyntaxToken,
+};
+pub use diagnostic::render_diagnostic;
+pub use document_parser::{
+    parse_ast_documents, parse_documents, parse_documents_with_options, DocumentParser, Framing,
+    JsonDocuments,
+};
+pub use error::{ParseError, ParseErrorKind, Token};
+pub use event::{CommentKind, ParseEvent, ParseEventKind};
+pub use event_listener::{ControlFlow, EventListener, FinishListener};
+pub use event_parser::{parse_events, parse_events_with_options, EventParser};
+pub use event_push_parser::{
+    parse as parse_events_push, parse_with_options as parse_events_push_with_options,
+};
+pub use event_to_tree::{event_to_spanned_tree, event_to_tree, parse_ast_recovering};
+pub use event_writer::{EventWriter, EventWriterOptions, WriteError};
+pub use extractor::{Extractor, ExtractorListener, FieldType};
+pub use formatter::{format as format_json, FormatOptions};
+pub use json_path::{
+    JsonPath, JsonPathError, JsonPathErrorKind, JsonPathListener, JsonPathMatch, JsonPathOptions,
+    JsonPathQuery,
+};
+pub use json_pointer::{
+    find_pointer, find_pointer_with_options, JsonPointer, JsonPointerError, JsonPointerErrorKind,
+};
+pub use json_writer::{JsonWriter, JsonWriterError};
+pub use lexgen_event_parser::{
+    parse_events as parse_events_lexgen,
+    parse_events_with_options as parse_events_lexgen_with_options,
+};
+pub use line_index::{LineCol, LineIndex};
 pub use listener_impl::AstBuilderListener;
+pub use options::{Dialect, ParseOptions};
 pub use push_to_events::PushToEvents;
+pub use reader_parser::{
+    parse_events as parse_events_reader,
+    parse_events_with_options as parse_events_reader_with_options, ReaderParser,
+};
+pub use serializer::{write_json, write_json_io, SerializeOptions};
 pub use simple_ast::Json;
-pub use simple_parser::parse as parse_ast;
+pub use simple_parser::{
+    parse as parse_ast, parse_spanned as parse_spanned_ast,
+    parse_spanned_with_options as parse_spanned_ast_with_options,
+    parse_with_options as parse_ast_with_options,
+};
+pub use spanned_ast::{AstNode, Span, SpannedJson, SpannedJsonKind, SpannedKey};
 pub use timestamp_parser::{parse_timestamp, TimestampParserListener};
 
 #[doc(hidden)]
 pub use input_gen::gen_input;
 
-/// A parse error, common for both event and AST parsers.
-#[derive(Debug, PartialEq, Eq)]
-pub struct ParseError {
-    /// Byte offset of the parse error in the input.
-    pub byte_offset: usize,
-
-    /// The error message.
-    pub reason: &'static str,
-}
-
 #[test]
 fn event_ast_eq() {
     for input_size in [10, 100, 1_000, 2_000, 5_000, 10_000] {
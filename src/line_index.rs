@@ -0,0 +1,111 @@
+/// Maps byte offsets in an input to lines and columns.
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    input: &'a str,
+
+    /// Byte offsets of the line starts. The first line starts at 0.
+    line_starts: Vec<usize>,
+}
+
+/// A location in an input. Lines and columns start from 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineCol {
+    pub line: usize,
+
+    /// Column in bytes, for UTF-8 encoded text.
+    pub col: usize,
+
+    /// Column in UTF-16 code units, as used by e.g. the Language Server Protocol.
+    pub col_utf16: usize,
+}
+
+impl<'a> LineIndex<'a> {
+    /// Builds the index. Lines are terminated by `\n`.
+    pub fn new(input: &'a str) -> LineIndex<'a> {
+        let mut line_starts = vec![0];
+        line_starts.extend(
+            input
+                .bytes()
+                .enumerate()
+                .filter(|(_, byte)| *byte == b'\n')
+                .map(|(byte_offset, _)| byte_offset + 1),
+        );
+        LineIndex { input, line_starts }
+    }
+
+    /// The indexed input.
+    pub fn input(&self) -> &'a str {
+        self.input
+    }
+
+    /// Number of lines in the input. An input without newlines has one line.
+    pub fn line_count(&self) -> usize {
+        self.line_starts.len()
+    }
+
+    /// Location of `byte_offset`, which should be at a character boundary and not greater than
+    /// the input size.
+    pub fn line_col(&self, byte_offset: usize) -> LineCol {
+        let line = self
+            .line_starts
+            .partition_point(|start| *start <= byte_offset)
+            - 1;
+        let line_start = self.line_starts[line];
+        LineCol {
+            line,
+            col: byte_offset - line_start,
+            col_utf16: self.input[line_start..byte_offset].encode_utf16().count(),
+        }
+    }
+
+    /// Byte offset of the start of `line`.
+    pub fn line_start(&self, line: usize) -> usize {
+        self.line_starts[line]
+    }
+
+    /// Text of `line`, without the line terminator. `\r\n` terminators are also removed.
+    pub fn line(&self, line: usize) -> &'a str {
+        let start = self.line_starts[line];
+        let end = match self.line_starts.get(line + 1) {
+            Some(next_start) => next_start - 1,
+            None => self.input.len(),
+        };
+        let line = &self.input[start..end];
+        line.strip_suffix('\r').unwrap_or(line)
+    }
+}
+
+#[test]
+fn line_col_test() {
+    let index = LineIndex::new("ab\n\"é😀\"\r\n\nx");
+    assert_eq!(index.line_count(), 4);
+    assert_eq!(
+        index.line_col(0),
+        LineCol {
+            line: 0,
+            col: 0,
+            col_utf16: 0
+        }
+    );
+    assert_eq!(index.line_col(2).line, 0);
+    assert_eq!(
+        index.line_col(10),
+        LineCol {
+            line: 1,
+            col: 7,
+            col_utf16: 4
+        }
+    );
+    assert_eq!(index.line_col(13).line, 2);
+    assert_eq!(
+        index.line_col(15),
+        LineCol {
+            line: 3,
+            col: 1,
+            col_utf16: 1
+        }
+    );
+    assert_eq!(index.line(1), "\"é😀\"");
+    assert_eq!(index.line(2), "");
+    assert_eq!(index.line(3), "x");
+}
@@ -1,133 +1,73 @@
-use crate::event_to_tree::Container;
-use crate::{EventListener, Json, ParseError};
+use crate::event_listener::EventHandler;
+use crate::event_to_tree::TreeBuilder;
+use crate::{AstNode, ControlFlow, FinishListener, Json, ParseError, ParseEvent, SpannedJson};
 
-/// An [EventListener] that builds [Json].
-pub struct AstBuilderListener<'a> {
+/// An [EventListener][crate::EventListener] that builds [Json], or with
+/// [AstBuilderListener::new_spanned], [SpannedJson].
+pub struct AstBuilderListener<'a, N: AstNode = Json> {
     input: &'a str,
-    container_stack: Vec<Container>,
-    current_container: Option<Container>,
-    parsed_object: Option<Json>,
+    builder: TreeBuilder<N>,
+    parsed_object: Option<N>,
     error: Option<ParseError>,
 }
 
 impl<'a> AstBuilderListener<'a> {
     pub fn new(input: &'a str) -> AstBuilderListener<'a> {
-        AstBuilderListener {
-            input,
-            container_stack: vec![],
-            current_container: None,
-            parsed_object: None,
-            error: None,
-        }
+        AstBuilderListener::new_generic(input)
     }
 }
 
-impl<'a> EventListener for AstBuilderListener<'a> {
-    fn handle_start_object(&mut self, _byte_offset: usize) -> bool {
-        if let Some(container) = self.current_container.take() {
-            self.container_stack.push(container);
-        }
-        self.current_container = Some(Container::new_map());
-        true
-    }
-
-    fn handle_end_object(&mut self, _byte_offset: usize) -> bool {
-        let map = self.current_container.take().unwrap().into_map().finish();
-        match self.container_stack.pop() {
-            Some(mut container) => {
-                container.add_object(map);
-                self.current_container = Some(container)
-            }
-            None => {
-                self.parsed_object = Some(map);
-            }
-        }
-        true
+impl<'a> AstBuilderListener<'a, SpannedJson> {
+    pub fn new_spanned(input: &'a str) -> AstBuilderListener<'a, SpannedJson> {
+        AstBuilderListener::new_generic(input)
     }
+}
 
-    fn handle_start_array(&mut self, _byte_offset: usize) -> bool {
-        if let Some(container) = self.current_container.take() {
-            self.container_stack.push(container);
+impl<'a, N: AstNode> AstBuilderListener<'a, N> {
+    fn new_generic(input: &'a str) -> AstBuilderListener<'a, N> {
+        AstBuilderListener {
+            input,
+            builder: TreeBuilder::new(),
+            parsed_object: None,
+            error: None,
         }
-        self.current_container = Some(Container::new_array());
-        true
     }
+}
 
-    fn handle_end_array(&mut self, _byte_offset: usize) -> bool {
-        let array = self.current_container.take().unwrap().into_array();
-        match self.container_stack.pop() {
-            Some(mut container) => {
-                container.add_object(Json::Array(array));
-                self.current_container = Some(container)
-            }
-            None => {
-                self.parsed_object = Some(Json::Array(array));
-            }
+impl<'a, N: AstNode> EventHandler for AstBuilderListener<'a, N> {
+    fn on_event(&mut self, event: ParseEvent) -> ControlFlow {
+        if let Some(object) = self.builder.add_input_event(event, self.input) {
+            self.parsed_object = Some(object);
         }
-        true
+        ControlFlow::Continue
     }
 
-    fn handle_int(&mut self, _byte_offset: usize, i: u64) -> bool {
-        let object = Json::Int(i);
-        match self.current_container.as_mut() {
-            Some(container) => container.add_object(object),
-            None => {
-                self.parsed_object = Some(object);
-            }
-        }
-        true
+    fn on_error(&mut self, error: ParseError) {
+        self.error = Some(error);
     }
+}
 
-    fn handle_str(&mut self, byte_offset: usize, size_in_bytes: usize) -> bool {
-        let string = self.input[byte_offset..byte_offset + size_in_bytes].to_string();
-        let object = Json::String(string);
-        match self.current_container.as_mut() {
-            Some(container) => container.add_object(object),
-            None => {
-                self.parsed_object = Some(object);
-            }
-        }
-        true
-    }
+impl<'a, N: AstNode> FinishListener for AstBuilderListener<'a, N> {
+    type Output = N;
+    type Error = ParseError;
 
-    fn handle_bool(&mut self, _byte_offset: usize, b: bool) -> bool {
-        let object = Json::Bool(b);
-        match self.current_container.as_mut() {
-            Some(container) => container.add_object(object),
-            None => {
-                self.parsed_object = Some(object);
-            }
+    /// Returns the AST, or the parse error.
+    fn finish(self) -> Result<N, ParseError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.parsed_object.unwrap()),
         }
-        true
-    }
-
-    fn handle_null(&mut self, _byte_offset: usize) -> bool {
-        let object = Json::Null;
-        match self.current_container.as_mut() {
-            Some(container) => container.add_object(object),
-            None => {
-                self.parsed_object = Some(object);
-            }
-        }
-        true
-    }
-
-    fn handle_error(&mut self, error: crate::ParseError) {
-        self.error = Some(error);
     }
 }
 
 #[cfg(test)]
 fn parse(input: &str) -> Result<Json, ParseError> {
-    let mut listener = AstBuilderListener::new(input);
-    crate::event_push_parser::parse(input, &mut listener);
-    if let Some(err) = listener.error {
-        return Err(err);
-    }
-    if let Some(value) = listener.parsed_object {
-        return Ok(value);
-    }
-    panic!()
+    parse_with_options(input, crate::ParseOptions::default())
+}
+
+#[cfg(test)]
+fn parse_with_options(input: &str, options: crate::ParseOptions) -> Result<Json, ParseError> {
+    crate::event_push_parser::parse_with_options(input, AstBuilderListener::new(input), options)
 }
 
 #[test]
@@ -137,3 +77,25 @@ fn test_push_parser() {
         assert_eq!(parse(&str).unwrap(), ast);
     }
 }
+
+#[test]
+fn lossless_number_tests() {
+    let options = crate::ParseOptions {
+        lossless_numbers: true,
+        ..crate::ParseOptions::default()
+    };
+    for (str, ast) in crate::test_common::lossless_number_ast_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(parse_with_options(&str, options).unwrap(), ast);
+    }
+}
+
+#[test]
+fn spanned_tests() {
+    for (str, options, texts) in crate::test_common::spanned_tests() {
+        println!("Parsing {:?}", str);
+        let listener = AstBuilderListener::new_spanned(&str);
+        let ast = crate::event_push_parser::parse_with_options(&str, listener, options).unwrap();
+        assert_eq!(crate::test_common::span_texts(&str, &ast), texts);
+    }
+}
@@ -0,0 +1,326 @@
+use crate::{Dialect, Json, ParseError, ParseErrorKind, ParseEventKind, ParseOptions};
+
+use std::iter::Peekable;
+
+/// A number parsed by [scan_number].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Number {
+    Int(u64),
+    NegInt(i64),
+    Float(f64),
+
+    /// A number that is not parsed, with [ParseOptions::lossless_numbers].
+    Raw {
+        size_in_bytes: usize,
+    },
+}
+
+/// Whether `char` starts a number in the dialect of `options`.
+pub(crate) fn is_number_start(char: char, options: &ParseOptions) -> bool {
+    char == '-'
+        || char.is_ascii_digit()
+        || (options.dialect == Dialect::Json5 && matches!(char, '+' | '.' | 'I' | 'N'))
+}
+
+/// Scans a number. `first` is the first character of the number at `byte_offset`, which should be
+/// consumed. `first` should be a character accepted by [is_number_start].
+///
+/// In [Dialect::Strict], integer parts with leading zeros are errors.
+///
+/// Numbers with a fraction or exponent, `-0`, and JSON5 `Infinity` and `NaN` are parsed as floats.
+/// Other numbers are parsed as integers. With [ParseOptions::lossless_numbers] numbers are not
+/// parsed, and the returned number is [Number::Raw].
+///
+/// Returns the byte offset after the number, and the number.
+pub(crate) fn scan_number<I: Iterator<Item = (usize, char)>>(
+    iter: &mut Peekable<I>,
+    input: &str,
+    byte_offset: usize,
+    first: char,
+    options: &ParseOptions,
+) -> Result<(usize, Number), ParseError> {
+    let json5 = options.dialect == Dialect::Json5;
+    let negative = first == '-';
+
+    // The first character after the sign.
+    let (first_byte_offset, first) = if first == '-' || first == '+' {
+        match iter.next() {
+            Some(next) => next,
+            None => return Err(expected_digit(input.len())),
+        }
+    } else {
+        (byte_offset, first)
+    };
+
+    if json5 && (first == 'I' || first == 'N') {
+        let (rest, value) = if first == 'I' {
+            ("nfinity", f64::INFINITY)
+        } else {
+            ("aN", f64::NAN)
+        };
+        for expected in rest.chars() {
+            if !matches!(iter.next(), Some((_, char)) if char == expected) {
+                return Err(ParseError::new(
+                    first_byte_offset,
+                    ParseErrorKind::InvalidKeyword,
+                ));
+            }
+        }
+        let end = end_offset(iter, input);
+        if options.lossless_numbers {
+            return Ok((end, raw(byte_offset, end)));
+        }
+        return Ok((end, Number::Float(if negative { -value } else { value })));
+    }
+
+    if json5 && first == '0' && matches!(iter.peek(), Some((_, 'x' | 'X'))) {
+        iter.next(); // consume 'x'
+        let i = scan_hex_digits(iter, input)?;
+        let end = end_offset(iter, input);
+        if options.lossless_numbers {
+            return Ok((end, raw(byte_offset, end)));
+        }
+        if negative && i == Some(0) {
+            return Ok((end, Number::Float(-0.0)));
+        }
+        return Ok((end, make_int(i, negative, byte_offset)?));
+    }
+
+    // `None` when the integer part does not fit into `u64`.
+    let mut i: Option<u64> = Some(0);
+
+    let mut float = false;
+
+    if first.is_ascii_digit() {
+        if options.dialect == Dialect::Strict
+            && first == '0'
+            && matches!(iter.peek(), Some((_, '0'..='9')))
+        {
+            return Err(leading_zero(first_byte_offset));
+        }
+
+        i = Some(u64::from((first as u8) - b'0'));
+
+        while let Some((_, next)) = iter.peek().copied() {
+            if !next.is_ascii_digit() {
+                break;
+            }
+
+            // Consume the digit.
+            iter.next();
+
+            i = i
+                .and_then(|i| i.checked_mul(10))
+                .and_then(|i| i.checked_add(u64::from((next as u8) - b'0')));
+        }
+
+        if let Some((_, '.')) = iter.peek() {
+            iter.next(); // consume '.'
+            float = true;
+            if json5 {
+                // Trailing decimal point.
+                while let Some((_, '0'..='9')) = iter.peek() {
+                    iter.next();
+                }
+            } else {
+                skip_digits(iter, input)?;
+            }
+        }
+    } else if first == '.' && json5 {
+        // Leading decimal point.
+        float = true;
+        skip_digits(iter, input)?;
+    } else {
+        return Err(expected_digit(first_byte_offset));
+    }
+
+    if let Some((_, 'e' | 'E')) = iter.peek() {
+        iter.next(); // consume 'e'
+        float = true;
+        if let Some((_, '+' | '-')) = iter.peek() {
+            iter.next(); // consume sign
+        }
+        skip_digits(iter, input)?;
+    }
+
+    let end = end_offset(iter, input);
+
+    if options.lossless_numbers {
+        return Ok((end, raw(byte_offset, end)));
+    }
+
+    let number = if float || (negative && i == Some(0)) {
+        parse_float(&input[byte_offset..end], byte_offset)?
+    } else {
+        make_int(i, negative, byte_offset)?
+    };
+
+    Ok((end, number))
+}
+
+/// Byte offset of the next character, or end of input.
+fn end_offset<I: Iterator<Item = (usize, char)>>(iter: &mut Peekable<I>, input: &str) -> usize {
+    match iter.peek() {
+        Some((byte_offset, _)) => *byte_offset,
+        None => input.len(),
+    }
+}
+
+fn raw(start: usize, end: usize) -> Number {
+    Number::Raw {
+        size_in_bytes: end - start,
+    }
+}
+
+/// Scans one or more hexadecimal digits. Returns `None` if the number does not fit into `u64`.
+fn scan_hex_digits<I: Iterator<Item = (usize, char)>>(
+    iter: &mut Peekable<I>,
+    input: &str,
+) -> Result<Option<u64>, ParseError> {
+    let mut i: Option<u64> = match iter.next() {
+        Some((_, digit)) if digit.is_ascii_hexdigit() => {
+            Some(u64::from(digit.to_digit(16).unwrap()))
+        }
+        Some((byte_offset, _)) => return Err(expected_digit(byte_offset)),
+        None => return Err(expected_digit(input.len())),
+    };
+    while let Some((_, next)) = iter.peek().copied() {
+        let digit = match next.to_digit(16) {
+            Some(digit) => digit,
+            None => break,
+        };
+        iter.next();
+        i = i
+            .and_then(|i| i.checked_mul(16))
+            .and_then(|i| i.checked_add(u64::from(digit)));
+    }
+    Ok(i)
+}
+
+/// Skips one or more digits.
+fn skip_digits<I: Iterator<Item = (usize, char)>>(
+    iter: &mut Peekable<I>,
+    input: &str,
+) -> Result<(), ParseError> {
+    match iter.next() {
+        Some((_, digit)) if digit.is_ascii_digit() => {}
+        Some((byte_offset, _)) => return Err(expected_digit(byte_offset)),
+        None => return Err(expected_digit(input.len())),
+    }
+    while let Some((_, next)) = iter.peek() {
+        if !next.is_ascii_digit() {
+            break;
+        }
+        iter.next();
+    }
+    Ok(())
+}
+
+fn parse_float(str: &str, byte_offset: usize) -> Result<Number, ParseError> {
+    let f = str.parse::<f64>().unwrap();
+    if f.is_infinite() {
+        return Err(ParseError::new(
+            byte_offset,
+            ParseErrorKind::NumberOutOfRange,
+        ));
+    }
+    Ok(Number::Float(f))
+}
+
+/// Makes an integer from the magnitude, which is `None` if it overflowed `u64`.
+fn make_int(i: Option<u64>, negative: bool, byte_offset: usize) -> Result<Number, ParseError> {
+    let overflow = ParseError::new(byte_offset, ParseErrorKind::IntegerOverflow);
+    let i = i.ok_or(overflow.clone())?;
+    if !negative {
+        return Ok(Number::Int(i));
+    }
+    if i > i64::MIN.unsigned_abs() {
+        return Err(overflow);
+    }
+    // `i64::MIN` wraps to itself.
+    Ok(Number::NegInt((i as i64).wrapping_neg()))
+}
+
+fn leading_zero(byte_offset: usize) -> ParseError {
+    ParseError::new(byte_offset, ParseErrorKind::LeadingZero)
+}
+
+fn expected_digit(byte_offset: usize) -> ParseError {
+    ParseError::new(byte_offset, ParseErrorKind::ExpectedDigit)
+}
+
+impl Number {
+    /// Parses a number at `byte_offset` matched by the number grammar. See [scan_number] for how
+    /// numbers are classified.
+    pub(crate) fn parse(
+        str: &str,
+        byte_offset: usize,
+        options: &ParseOptions,
+    ) -> Result<Number, ParseError> {
+        let digits_offset = usize::from(str.starts_with('-'));
+        if options.dialect == Dialect::Strict
+            && str[digits_offset..].starts_with('0')
+            && str[digits_offset + 1..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            return Err(leading_zero(byte_offset + digits_offset));
+        }
+        if options.lossless_numbers {
+            return Ok(Number::Raw {
+                size_in_bytes: str.len(),
+            });
+        }
+        if str.contains(['.', 'e', 'E']) {
+            return parse_float(str, byte_offset);
+        }
+        let (negative, digits) = match str.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, str),
+        };
+        let i = digits.parse::<u64>().ok();
+        if negative && i == Some(0) {
+            return parse_float(str, byte_offset);
+        }
+        make_int(i, negative, byte_offset)
+    }
+
+    /// Converts the number at `byte_offset` in `input` to [Json].
+    pub(crate) fn into_json(self, input: &str, byte_offset: usize) -> Json {
+        match self {
+            Number::Int(i) => Json::Int(i),
+            Number::NegInt(i) => Json::NegInt(i),
+            Number::Float(f) => Json::Float(f),
+            Number::Raw { size_in_bytes } => {
+                Json::Number(input[byte_offset..byte_offset + size_in_bytes].to_string())
+            }
+        }
+    }
+}
+
+impl From<Number> for ParseEventKind {
+    fn from(number: Number) -> Self {
+        match number {
+            Number::Int(i) => ParseEventKind::Int(i),
+            Number::NegInt(i) => ParseEventKind::NegInt(i),
+            Number::Float(f) => ParseEventKind::Float(f),
+            Number::Raw { size_in_bytes } => ParseEventKind::Number { size_in_bytes },
+        }
+    }
+}
+
+#[test]
+fn number_parse_test() {
+    let options = ParseOptions::default();
+    let parse = |str: &str| Number::parse(str, 0, &options);
+    assert_eq!(parse("0"), Ok(Number::Int(0)));
+    assert_eq!(parse("123"), Ok(Number::Int(123)));
+    assert_eq!(parse("-123"), Ok(Number::NegInt(-123)));
+    assert_eq!(parse("1.5"), Ok(Number::Float(1.5)));
+    assert_eq!(parse("-1e3"), Ok(Number::Float(-1000.0)));
+    assert_eq!(parse("25E-1"), Ok(Number::Float(2.5)));
+    assert!(matches!(parse("-0"), Ok(Number::Float(f)) if f.is_sign_negative()));
+    assert_eq!(parse("18446744073709551615"), Ok(Number::Int(u64::MAX)));
+    assert_eq!(parse("-9223372036854775808"), Ok(Number::NegInt(i64::MIN)));
+    assert!(parse("18446744073709551616").is_err());
+    assert!(parse("-9223372036854775809").is_err());
+    assert!(parse("1e400").is_err());
+}
@@ -0,0 +1,72 @@
+/// Options for the parsers.
+///
+/// The default options parse JSON with `//` and `/* ... */` comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// The accepted syntax. [Dialect::Json] by default.
+    pub dialect: Dialect,
+
+    /// Don't parse numbers, report the number text as it appears in the input instead.
+    ///
+    /// Event parsers generate [ParseEventKind::Number][crate::ParseEventKind::Number] and AST
+    /// parsers generate [Json::Number][crate::Json::Number] for numbers. Numbers that don't fit
+    /// into the integer and float types are only accepted in this mode.
+    pub lossless_numbers: bool,
+
+    /// Accept `// ...` comments. Enabled by default.
+    pub line_comments: bool,
+
+    /// Accept `/* ... */` comments. Enabled by default.
+    pub block_comments: bool,
+
+    /// Allow `/* ... */` comments to nest, as in `/* a /* b */ c */`. Disabled by default.
+    pub nested_block_comments: bool,
+
+    /// Accept `# ...` comments. Disabled by default.
+    pub hash_comments: bool,
+
+    /// Don't stop at errors. Errors are reported as
+    /// [ParseEventKind::Error][crate::ParseEventKind::Error] events, and parsing continues after
+    /// skipping to the next `,`, newline, or closing bracket of the current array or object. The
+    /// line after a newline starts the next value or object key. At the end of input the open
+    /// arrays and objects are closed. Disabled by default.
+    ///
+    /// Only supported by [parse_events][crate::parse_events] and
+    /// [parse_ast_recovering][crate::parse_ast_recovering]. Other parsers stop at the first error.
+    pub recover_errors: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            dialect: Dialect::Json,
+            lossless_numbers: false,
+            line_comments: true,
+            block_comments: true,
+            nested_block_comments: false,
+            hash_comments: false,
+            recover_errors: false,
+        }
+    }
+}
+
+/// The syntax accepted by the parsers.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Dialect {
+    /// JSON, with the comments enabled in [ParseOptions]. Integers with leading zeros, control
+    /// characters in strings, and form feed as whitespace are also accepted.
+    #[default]
+    Json,
+
+    /// [JSON5](https://spec.json5.org/): in addition to JSON, accepts identifier object keys,
+    /// single-quoted strings, trailing commas, hexadecimal numbers, numbers with a leading `+` or
+    /// leading or trailing decimal point, `Infinity` and `NaN`, and Unicode whitespace.
+    ///
+    /// Not supported by [parse_events_lexgen][crate::parse_events_lexgen].
+    Json5,
+
+    /// [RFC 8259](https://www.rfc-editor.org/rfc/rfc8259) JSON: rejects comments regardless of the
+    /// comment options, integers with leading zeros, unescaped control characters in strings,
+    /// and whitespace other than space, tab, line feed and carriage return.
+    Strict,
+}
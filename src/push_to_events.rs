@@ -1,72 +1,34 @@
-use crate::{EventListener, ParseError, ParseEvent, ParseEventKind};
+use crate::event_listener::EventHandler;
+use crate::{ControlFlow, FinishListener, ParseError, ParseEvent};
 
-/// An [EventListener] that collects parse events.
+/// An [EventListener][crate::EventListener] that collects parse events.
 pub struct PushToEvents {
     events: Vec<ParseEvent>,
     error: Option<ParseError>,
 }
 
-impl EventListener for PushToEvents {
-    fn handle_start_object(&mut self, byte_offset: usize) -> bool {
-        self.events
-            .push(ParseEvent::new(byte_offset, ParseEventKind::StartObject));
-        true
+impl EventHandler for PushToEvents {
+    fn on_event(&mut self, event: ParseEvent) -> ControlFlow {
+        self.events.push(event);
+        ControlFlow::Continue
     }
 
-    fn handle_end_object(&mut self, byte_offset: usize) -> bool {
-        self.events
-            .push(ParseEvent::new(byte_offset, ParseEventKind::EndObject));
-        true
-    }
-
-    fn handle_start_array(&mut self, byte_offset: usize) -> bool {
-        self.events
-            .push(ParseEvent::new(byte_offset, ParseEventKind::StartArray));
-        true
-    }
-
-    fn handle_end_array(&mut self, byte_offset: usize) -> bool {
-        self.events
-            .push(ParseEvent::new(byte_offset, ParseEventKind::EndArray));
-        true
-    }
-
-    fn handle_int(&mut self, byte_offset: usize, i: u64) -> bool {
-        self.events
-            .push(ParseEvent::new(byte_offset, ParseEventKind::Int(i)));
-        true
-    }
-
-    fn handle_str(&mut self, byte_offset: usize, size_in_bytes: usize) -> bool {
-        self.events.push(ParseEvent::new(
-            byte_offset,
-            ParseEventKind::Str { size_in_bytes },
-        ));
-        true
-    }
-
-    fn handle_bool(&mut self, byte_offset: usize, b: bool) -> bool {
-        self.events
-            .push(ParseEvent::new(byte_offset, ParseEventKind::Bool(b)));
-        true
-    }
-
-    fn handle_null(&mut self, byte_offset: usize) -> bool {
-        self.events
-            .push(ParseEvent::new(byte_offset, ParseEventKind::Null));
-        true
+    fn on_error(&mut self, error: ParseError) {
+        self.error = Some(error);
     }
+}
 
-    fn handle_comment(&mut self, byte_offset: usize, size_in_bytes: usize) -> bool {
-        self.events.push(ParseEvent::new(
-            byte_offset,
-            ParseEventKind::Comment { size_in_bytes },
-        ));
-        true
-    }
+impl FinishListener for PushToEvents {
+    type Output = Vec<ParseEvent>;
+    type Error = ParseError;
 
-    fn handle_error(&mut self, error: crate::ParseError) {
-        self.error = Some(error);
+    /// Returns the events, or the parse error. Use [PushToEvents::into_events] for the events
+    /// before the error.
+    fn finish(self) -> Result<Vec<ParseEvent>, ParseError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.events),
+        }
     }
 }
 
@@ -94,12 +56,333 @@ impl PushToEvents {
 fn event_tests() {
     for (str, events) in crate::test_common::event_tests() {
         println!("Parsing {:?}", str);
-        let mut push_to_events = PushToEvents::new();
-        crate::event_push_parser::parse(&str, &mut push_to_events);
-        let events_ = push_to_events
+        let events_ = crate::event_push_parser::parse(&str, PushToEvents::new())
+            .unwrap()
             .into_iter()
-            .map(|ev| ev.unwrap().kind)
+            .map(|ev| ev.kind)
             .collect::<Vec<_>>();
         assert_eq!(events_, events);
     }
 }
+
+#[test]
+fn number_error_tests() {
+    for (str, byte_offset) in crate::test_common::number_error_tests() {
+        println!("Parsing {:?}", str);
+        let error = crate::event_push_parser::parse(&str, PushToEvents::new()).err();
+        assert_eq!(error.unwrap().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn overflow_error_tests() {
+    for (str, byte_offset) in crate::test_common::overflow_error_tests() {
+        println!("Parsing {:?}", str);
+        let error = crate::event_push_parser::parse(&str, PushToEvents::new()).err();
+        assert_eq!(error.unwrap().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn lossless_number_tests() {
+    let options = crate::ParseOptions {
+        lossless_numbers: true,
+        ..crate::ParseOptions::default()
+    };
+    for (str, events) in crate::test_common::lossless_number_event_tests() {
+        println!("Parsing {:?}", str);
+        let events_ =
+            crate::event_push_parser::parse_with_options(&str, PushToEvents::new(), options)
+                .unwrap()
+                .into_iter()
+                .map(|ev| ev.kind)
+                .collect::<Vec<_>>();
+        assert_eq!(events_, events);
+    }
+}
+
+#[test]
+fn comment_option_tests() {
+    for (str, events) in crate::test_common::comment_option_event_tests() {
+        println!("Parsing {:?}", str);
+        let events_ = crate::event_push_parser::parse_with_options(
+            &str,
+            PushToEvents::new(),
+            crate::test_common::comment_options(),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|ev| ev.kind)
+        .collect::<Vec<_>>();
+        assert_eq!(events_, events);
+    }
+}
+
+#[test]
+fn comment_error_tests() {
+    for (str, options, byte_offset) in crate::test_common::comment_error_tests() {
+        println!("Parsing {:?}", str);
+        let error =
+            crate::event_push_parser::parse_with_options(&str, PushToEvents::new(), options).err();
+        assert_eq!(error.unwrap().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn escape_error_tests() {
+    for (str, byte_offset) in crate::test_common::escape_error_tests() {
+        println!("Parsing {:?}", str);
+        let error = crate::event_push_parser::parse(&str, PushToEvents::new()).err();
+        assert_eq!(error.unwrap().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn json5_tests() {
+    for (str, events) in crate::test_common::json5_event_tests() {
+        println!("Parsing {:?}", str);
+        let events_ = crate::event_push_parser::parse_with_options(
+            &str,
+            PushToEvents::new(),
+            crate::test_common::json5_options(),
+        )
+        .unwrap()
+        .into_iter()
+        .map(|ev| ev.kind)
+        .collect::<Vec<_>>();
+        assert_eq!(events_, events);
+    }
+}
+
+#[test]
+fn json5_only_tests() {
+    for (str, byte_offset) in crate::test_common::json5_only_tests() {
+        println!("Parsing {:?}", str);
+        let error = crate::event_push_parser::parse(&str, PushToEvents::new()).err();
+        assert_eq!(error.unwrap().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn json5_error_tests() {
+    for (str, byte_offset) in crate::test_common::json5_error_tests() {
+        println!("Parsing {:?}", str);
+        let error = crate::event_push_parser::parse_with_options(
+            &str,
+            PushToEvents::new(),
+            crate::test_common::json5_options(),
+        )
+        .err();
+        assert_eq!(error.unwrap().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn json5_byte_offsets() {
+    let events = crate::event_push_parser::parse_with_options(
+        "{ab: 'c', d: +0x1, e: NaN,}",
+        PushToEvents::new(),
+        crate::test_common::json5_options(),
+    )
+    .unwrap()
+    .into_iter()
+    .map(|ev| ev.byte_offset)
+    .collect::<Vec<_>>();
+    assert_eq!(events, vec![0, 1, 6, 10, 13, 19, 22, 26]);
+}
+
+#[test]
+fn event_parser_tests() {
+    use crate::test_common::*;
+
+    let lossless_options = crate::ParseOptions {
+        lossless_numbers: true,
+        ..crate::ParseOptions::default()
+    };
+    let mut tests: Vec<(String, crate::ParseOptions)> = vec![];
+    let default_options = crate::ParseOptions::default();
+    tests.extend(
+        event_tests()
+            .into_iter()
+            .map(|(str, _)| (str, default_options)),
+    );
+    tests.extend(
+        comment_option_event_tests()
+            .into_iter()
+            .map(|(str, _)| (str, comment_options())),
+    );
+    tests.extend(
+        lossless_number_event_tests()
+            .into_iter()
+            .map(|(str, _)| (str, lossless_options)),
+    );
+    tests.extend(
+        json5_event_tests()
+            .into_iter()
+            .map(|(str, _)| (str, json5_options())),
+    );
+
+    // The events and their spans are the same as the event parser's.
+    for (str, options) in tests {
+        println!("Parsing {:?}", str);
+        let events =
+            crate::event_push_parser::parse_with_options(&str, PushToEvents::new(), options)
+                .unwrap();
+        let events_ = crate::parse_events_with_options(&str, options)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(events, events_);
+    }
+}
+
+#[test]
+fn strict_error_tests() {
+    for (str, byte_offset, kind) in crate::test_common::strict_error_tests() {
+        println!("Parsing {:?}", str);
+        let error = crate::event_push_parser::parse_with_options(
+            &str,
+            PushToEvents::new(),
+            crate::test_common::strict_options(),
+        )
+        .err();
+        assert_eq!(error, Some(ParseError { byte_offset, kind }));
+    }
+}
+
+#[test]
+fn unexpected_token_tests() {
+    for (str, error) in crate::test_common::unexpected_token_tests() {
+        println!("Parsing {:?}", str);
+        let error_ = crate::event_push_parser::parse(&str, PushToEvents::new()).err();
+        assert_eq!(error_, Some(error));
+    }
+}
+
+#[test]
+fn control_flow_stop_tests() {
+    use crate::test_common::ControlListener;
+    use crate::ControlFlow;
+
+    for (str, options) in crate::test_common::control_flow_inputs() {
+        println!("Parsing {:?}", str);
+        let mut listener = ControlListener::new(|_, _| ControlFlow::Continue);
+        crate::event_push_parser::push_events(&str, &mut listener, options);
+        let events = listener.events;
+        assert!(listener.error.is_some());
+
+        // Stopping at each event, the error after the events is not reported.
+        for stop_index in 0..events.len() {
+            let listener = ControlListener::new(|index, _| {
+                if index == stop_index {
+                    ControlFlow::Stop
+                } else {
+                    ControlFlow::Continue
+                }
+            });
+            let events_ = crate::event_push_parser::parse_with_options(&str, listener, options);
+            assert_eq!(events_.unwrap(), events[..=stop_index]);
+        }
+    }
+}
+
+#[test]
+fn control_flow_skip_value_tests() {
+    use crate::test_common::ControlListener;
+    use crate::ControlFlow;
+
+    // Skipping the values in the top-level array or object. `SkipValue` from other callbacks
+    // is the same as `Continue`.
+    for (str, options, events) in crate::test_common::skip_value_tests() {
+        println!("Parsing {:?}", str);
+        let listener = ControlListener::new(|index, _| {
+            if index == 0 {
+                ControlFlow::Continue
+            } else {
+                ControlFlow::SkipValue
+            }
+        });
+        let events_ = crate::event_push_parser::parse_with_options(&str, listener, options);
+        assert_eq!(events_.unwrap(), events);
+    }
+
+    for (str, byte_offset) in crate::test_common::skip_value_error_tests() {
+        println!("Parsing {:?}", str);
+        let listener = ControlListener::new(|index, _| {
+            if index == 0 {
+                ControlFlow::Continue
+            } else {
+                ControlFlow::SkipValue
+            }
+        });
+        let error = crate::event_push_parser::parse_with_options(
+            &str,
+            listener,
+            crate::test_common::comment_options(),
+        )
+        .unwrap_err();
+        assert_eq!(error.byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn listener_error_test() {
+    use crate::EventListener;
+
+    #[derive(Debug, PartialEq)]
+    enum DepthError {
+        TooDeep(usize),
+        Parse(ParseError),
+    }
+
+    /// Fails on arrays nested deeper than `max_depth`.
+    struct DepthListener {
+        depth: usize,
+        max_depth: usize,
+        error: Option<DepthError>,
+    }
+
+    impl EventListener for DepthListener {
+        fn handle_start_array(&mut self, byte_offset: usize) -> ControlFlow {
+            self.depth += 1;
+            if self.depth > self.max_depth {
+                self.error = Some(DepthError::TooDeep(byte_offset));
+                return ControlFlow::Stop;
+            }
+            ControlFlow::Continue
+        }
+
+        fn handle_end_array(&mut self, _byte_offset: usize) -> ControlFlow {
+            self.depth -= 1;
+            ControlFlow::Continue
+        }
+
+        fn handle_error(&mut self, error: ParseError) {
+            self.error = Some(DepthError::Parse(error));
+        }
+    }
+
+    impl FinishListener for DepthListener {
+        type Output = ();
+        type Error = DepthError;
+
+        fn finish(self) -> Result<(), DepthError> {
+            match self.error {
+                Some(error) => Err(error),
+                None => Ok(()),
+            }
+        }
+    }
+
+    fn parse(input: &str) -> Result<(), DepthError> {
+        let listener = DepthListener {
+            depth: 0,
+            max_depth: 2,
+            error: None,
+        };
+        crate::event_push_parser::parse(input, listener)
+    }
+
+    assert_eq!(parse("[[1], []]"), Ok(()));
+    assert_eq!(parse("[[[1]], ["), Err(DepthError::TooDeep(2)));
+    assert!(matches!(parse("[[1], "), Err(DepthError::Parse(_))));
+}
@@ -0,0 +1,390 @@
+use crate::escape::unescape;
+use crate::event_listener::EventHandler;
+use crate::{
+    ChunkedParser, ControlFlow, ParseError, ParseErrorKind, ParseEvent, ParseEventKind,
+    ParseOptions,
+};
+
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io;
+use std::ops::Range;
+
+/// Size of the chunks read from the reader.
+const CHUNK_SIZE: usize = 8 * 1024;
+
+/// Parses input read from `reader` to [ParseEvent]s.
+pub fn parse_events<R: io::Read>(reader: R) -> ReaderParser<R> {
+    ReaderParser::new(reader, ParseOptions::default())
+}
+
+/// Parses input read from `reader` to [ParseEvent]s, with the given options.
+pub fn parse_events_with_options<R: io::Read>(reader: R, options: ParseOptions) -> ReaderParser<R> {
+    ReaderParser::new(reader, options)
+}
+
+/// A parser that generates [ParseEvent]s from an [io::Read], without reading the whole input to
+/// memory.
+///
+/// The input is read in chunks and parsed with a [ChunkedParser], so only the chunk, the events
+/// parsed from it, and an incomplete token at the end of the chunk are kept in memory. Byte
+/// offsets of the events are offsets in the whole input. As the input is not available to the
+/// caller, the text of the last string, number or comment event is available from
+/// [ReaderParser::str] and [ReaderParser::text].
+///
+/// Errors reading the input are reported as [ParseErrorKind::Io] errors, the [io::Error] is
+/// available from [ReaderParser::take_io_error]. [ParseOptions::recover_errors] is not supported.
+pub struct ReaderParser<R: io::Read> {
+    reader: R,
+    state: ReaderState,
+}
+
+/// Parsing state of the parsers that read the input in chunks: [ReaderParser] and
+/// [AsyncReaderParser][crate::AsyncReaderParser].
+pub(crate) struct ReaderState {
+    /// `None` after the end of input, or after an error.
+    parser: Option<ChunkedParser>,
+
+    /// Buffer for reading the next chunk.
+    pub(crate) chunk: Box<[u8]>,
+
+    /// Number of bytes read.
+    byte_offset: usize,
+
+    events: EventQueue,
+
+    /// Location of the text of the last event in `events.texts`, and whether the text is a string
+    /// with escape sequences.
+    current_text: (Range<usize>, bool),
+
+    io_error: Option<io::Error>,
+}
+
+/// An [EventHandler] that collects the events parsed from a chunk.
+struct EventQueue {
+    events: VecDeque<(Result<ParseEvent, ParseError>, Range<usize>)>,
+
+    /// Texts of the events in `events`.
+    texts: String,
+
+    /// Location of the text passed to the last [EventHandler::on_text] call in `texts`.
+    next_text: Range<usize>,
+
+    failed: bool,
+}
+
+impl<R: io::Read> ReaderParser<R> {
+    fn new(reader: R, options: ParseOptions) -> ReaderParser<R> {
+        ReaderParser {
+            reader,
+            state: ReaderState::new(options),
+        }
+    }
+
+    /// The value of the last [ParseEventKind::Str] event, decoded. The string is borrowed from
+    /// the parser when it doesn't have escape sequences.
+    pub fn str(&self) -> Cow<'_, str> {
+        self.state.str()
+    }
+
+    /// The input text of the last [ParseEventKind::Str], [ParseEventKind::Number] or
+    /// [ParseEventKind::Comment] event, as in
+    /// [EventListener::handle_text][crate::EventListener::handle_text]. Empty after other events.
+    pub fn text(&self) -> &str {
+        self.state.text()
+    }
+
+    /// The error of the last [ParseErrorKind::Io] error.
+    pub fn take_io_error(&mut self) -> Option<io::Error> {
+        self.state.take_io_error()
+    }
+}
+
+impl<R: io::Read> Iterator for ReaderParser<R> {
+    type Item = Result<ParseEvent, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.state.next_event() {
+                return Some(event);
+            }
+            if self.state.is_done() {
+                return None;
+            }
+            let result = self.reader.read(&mut self.state.chunk);
+            self.state.parse_chunk(result);
+        }
+    }
+}
+
+impl ReaderState {
+    pub(crate) fn new(options: ParseOptions) -> ReaderState {
+        ReaderState {
+            parser: Some(ChunkedParser::new(options)),
+            chunk: vec![0; CHUNK_SIZE].into_boxed_slice(),
+            byte_offset: 0,
+            events: EventQueue {
+                events: VecDeque::new(),
+                texts: String::new(),
+                next_text: 0..0,
+                failed: false,
+            },
+            current_text: (0..0, false),
+            io_error: None,
+        }
+    }
+
+    /// The next event parsed from the chunks read so far.
+    pub(crate) fn next_event(&mut self) -> Option<Result<ParseEvent, ParseError>> {
+        let (event, text) = self.events.events.pop_front()?;
+        let has_escapes = matches!(
+            event,
+            Ok(ParseEvent {
+                kind: ParseEventKind::Str {
+                    has_escapes: true,
+                    ..
+                },
+                ..
+            })
+        );
+        self.current_text = (text, has_escapes);
+        Some(event)
+    }
+
+    /// Whether the input is parsed, or parsing stopped after an error. The remaining events are
+    /// available from [ReaderState::next_event].
+    pub(crate) fn is_done(&self) -> bool {
+        self.parser.is_none()
+    }
+
+    /// Parses the chunk read to `chunk`, with the `result` of the read. Call when
+    /// [ReaderState::next_event] returns `None`.
+    pub(crate) fn parse_chunk(&mut self, result: io::Result<usize>) {
+        let parser = match self.parser.as_mut() {
+            Some(parser) => parser,
+            None => return,
+        };
+
+        // The texts of the events are no longer needed.
+        self.events.texts.clear();
+        self.current_text = (0..0, false);
+
+        match result {
+            Ok(0) => {
+                self.parser.take().unwrap().finish_events(&mut self.events);
+            }
+            Ok(size) => {
+                parser.feed(&self.chunk[..size], &mut self.events);
+                self.byte_offset += size;
+            }
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => {
+                self.events.events.push_back((
+                    Err(ParseError::new(
+                        self.byte_offset,
+                        ParseErrorKind::Io(error.kind()),
+                    )),
+                    0..0,
+                ));
+                self.io_error = Some(error);
+                self.parser = None;
+            }
+        }
+
+        if self.events.failed {
+            self.parser = None;
+        }
+    }
+
+    /// Number of bytes read.
+    #[cfg(feature = "async")]
+    pub(crate) fn byte_offset(&self) -> usize {
+        self.byte_offset
+    }
+
+    pub(crate) fn str(&self) -> Cow<'_, str> {
+        let (_, has_escapes) = self.current_text;
+        if has_escapes {
+            Cow::Owned(unescape(self.text()))
+        } else {
+            Cow::Borrowed(self.text())
+        }
+    }
+
+    pub(crate) fn text(&self) -> &str {
+        let (range, _) = &self.current_text;
+        &self.events.texts[range.clone()]
+    }
+
+    pub(crate) fn take_io_error(&mut self) -> Option<io::Error> {
+        self.io_error.take()
+    }
+}
+
+impl EventHandler for EventQueue {
+    fn on_event(&mut self, event: ParseEvent) -> ControlFlow {
+        // The text from the last `handle_text` call is the text of the event.
+        let text = match event.kind {
+            ParseEventKind::Number { .. }
+            | ParseEventKind::Str { .. }
+            | ParseEventKind::Comment { .. } => std::mem::replace(&mut self.next_text, 0..0),
+            _ => 0..0,
+        };
+        self.events.push_back((Ok(event), text));
+        ControlFlow::Continue
+    }
+
+    fn on_text(&mut self, text: &str) -> ControlFlow {
+        let start = self.texts.len();
+        self.texts.push_str(text);
+        self.next_text = start..self.texts.len();
+        ControlFlow::Continue
+    }
+
+    fn on_error(&mut self, error: ParseError) {
+        self.events.push_back((Err(error), 0..0));
+        self.failed = true;
+    }
+}
+
+/// A reader that returns one byte at a time, and then an error if `error` is set.
+#[cfg(test)]
+struct ByteReader<'a> {
+    input: &'a [u8],
+    error: Option<io::ErrorKind>,
+}
+
+#[cfg(test)]
+impl<'a> io::Read for ByteReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.input.split_first() {
+            Some((byte, rest)) => {
+                buf[0] = *byte;
+                self.input = rest;
+                Ok(1)
+            }
+            None => match self.error {
+                Some(kind) => Err(io::Error::new(kind, "test")),
+                None => Ok(0),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn collect_events<I: Iterator<Item = Result<ParseEvent, ParseError>>>(
+    iter: I,
+) -> (Vec<ParseEvent>, Option<ParseError>) {
+    let mut events = vec![];
+    for event in iter {
+        match event {
+            Ok(event) => events.push(event),
+            Err(error) => return (events, Some(error)),
+        }
+    }
+    (events, None)
+}
+
+#[test]
+fn event_parser_eq_tests() {
+    use crate::test_common::*;
+
+    let mut tests: Vec<(String, ParseOptions)> = vec![];
+    for (str, _) in event_tests() {
+        tests.push((str, ParseOptions::default()));
+    }
+    for (str, _) in unexpected_token_tests() {
+        tests.push((str, ParseOptions::default()));
+    }
+    for (str, _) in escape_error_tests() {
+        tests.push((str, ParseOptions::default()));
+    }
+    for (str, _) in json5_event_tests() {
+        tests.push((str, json5_options()));
+    }
+    for (str, _) in comment_option_event_tests() {
+        tests.push((str, comment_options()));
+    }
+
+    for (str, options) in tests {
+        println!("Parsing {:?}", str);
+        let expected = collect_events(crate::parse_events_with_options(&str, options));
+        assert_eq!(
+            collect_events(parse_events_with_options(str.as_bytes(), options)),
+            expected
+        );
+        let reader = ByteReader {
+            input: str.as_bytes(),
+            error: None,
+        };
+        assert_eq!(
+            collect_events(parse_events_with_options(reader, options)),
+            expected
+        );
+    }
+}
+
+#[test]
+fn text_test() {
+    let input = r#"{"a\n": ["é😀", 1.50], /* c */ "b": "\u0041"}"#;
+    let options = ParseOptions {
+        lossless_numbers: true,
+        ..ParseOptions::default()
+    };
+    let reader = ByteReader {
+        input: input.as_bytes(),
+        error: None,
+    };
+    let mut parser = parse_events_with_options(reader, options);
+    let mut texts = vec![];
+    while let Some(event) = parser.next() {
+        match event.unwrap().kind {
+            ParseEventKind::Str { .. } => {
+                let str = parser.str();
+                let borrowed = matches!(str, Cow::Borrowed(_));
+                texts.push((parser.text().to_string(), str.into_owned(), borrowed));
+            }
+            ParseEventKind::Number { .. } | ParseEventKind::Comment { .. } => {
+                texts.push((parser.text().to_string(), String::new(), true));
+            }
+            _ => {}
+        }
+    }
+    let expected = [
+        ("a\\n", "a\n", false),
+        ("é😀", "é😀", true),
+        ("1.50", "", true),
+        ("/* c */", "", true),
+        ("b", "b", true),
+        ("\\u0041", "A", false),
+    ];
+    assert_eq!(
+        texts,
+        expected
+            .iter()
+            .map(|(text, str, borrowed)| (text.to_string(), str.to_string(), *borrowed))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn io_error_test() {
+    let reader = ByteReader {
+        input: b"[1, 2",
+        error: Some(io::ErrorKind::ConnectionReset),
+    };
+    let mut parser = parse_events(reader);
+    let events: Vec<_> = parser.by_ref().collect();
+    assert_eq!(events.len(), 3);
+    assert_eq!(
+        events[2],
+        Err(ParseError::new(
+            5,
+            ParseErrorKind::Io(io::ErrorKind::ConnectionReset)
+        ))
+    );
+    assert_eq!(
+        parser.take_io_error().unwrap().kind(),
+        io::ErrorKind::ConnectionReset
+    );
+}
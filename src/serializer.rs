@@ -0,0 +1,290 @@
+use crate::Json;
+
+use std::fmt;
+use std::io;
+
+/// Options for serializing [Json].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SerializeOptions {
+    /// Number of spaces per indentation level for pretty printing. When `None` the output has no
+    /// whitespace. `None` by default.
+    pub indent: Option<usize>,
+
+    /// Escape non-ASCII characters in strings as `\uXXXX`, with surrogate pairs for characters
+    /// outside of the Basic Multilingual Plane. Disabled by default.
+    pub ascii_only: bool,
+
+    /// Escape `<`, `>`, `&`, U+2028 and U+2029 in strings as `\uXXXX`, so that the output can be
+    /// embedded in HTML `<script>` tags. Disabled by default.
+    pub html_safe: bool,
+}
+
+/// Writes `json` to `out`.
+///
+/// Floats are written with a fraction or exponent, so they are parsed back as floats. Infinite
+/// and NaN floats and [Json::Error] nodes, which are not valid JSON, are written as `null`.
+/// [Json::Number]s are written as they are.
+pub fn write_json<W: fmt::Write>(
+    out: &mut W,
+    json: &Json,
+    options: SerializeOptions,
+) -> fmt::Result {
+    write_value(out, json, &options, 0)
+}
+
+/// Writes `json` to `out`, as in [write_json].
+pub fn write_json_io<W: io::Write>(
+    out: &mut W,
+    json: &Json,
+    options: SerializeOptions,
+) -> io::Result<()> {
+    let mut adapter = IoAdapter { out, error: None };
+    match write_json(&mut adapter, json, options) {
+        Ok(()) => Ok(()),
+        Err(fmt::Error) => Err(adapter.error.unwrap()),
+    }
+}
+
+/// Writes the JSON text, compact by default, or with the alternate flag (`{:#}`) pretty-printed
+/// with two spaces of indentation.
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = SerializeOptions {
+            indent: if f.alternate() { Some(2) } else { None },
+            ..SerializeOptions::default()
+        };
+        write_json(f, self, options)
+    }
+}
+
+/// Writes `json` nested in `level` arrays and objects.
+pub(crate) fn write_value<W: fmt::Write>(
+    out: &mut W,
+    json: &Json,
+    options: &SerializeOptions,
+    level: usize,
+) -> fmt::Result {
+    match json {
+        Json::Int(i) => write!(out, "{}", i),
+        Json::NegInt(i) => write!(out, "{}", i),
+        Json::Float(f) => write_float(out, *f),
+        Json::Number(number) => out.write_str(number),
+        Json::String(str) => write_str(out, str, options),
+        Json::Bool(b) => write!(out, "{}", b),
+        Json::Null | Json::Error => out.write_str("null"),
+
+        Json::Array(elements) => {
+            if elements.is_empty() {
+                return out.write_str("[]");
+            }
+            out.write_char('[')?;
+            for (i, element) in elements.iter().enumerate() {
+                if i != 0 {
+                    out.write_char(',')?;
+                }
+                write_newline(out, options, level + 1)?;
+                write_value(out, element, options, level + 1)?;
+            }
+            write_newline(out, options, level)?;
+            out.write_char(']')
+        }
+
+        Json::Object(members) => {
+            if members.is_empty() {
+                return out.write_str("{}");
+            }
+            out.write_char('{')?;
+            for (i, (key, value)) in members.iter().enumerate() {
+                if i != 0 {
+                    out.write_char(',')?;
+                }
+                write_newline(out, options, level + 1)?;
+                write_str(out, key, options)?;
+                out.write_char(':')?;
+                if options.indent.is_some() {
+                    out.write_char(' ')?;
+                }
+                write_value(out, value, options, level + 1)?;
+            }
+            write_newline(out, options, level)?;
+            out.write_char('}')
+        }
+    }
+}
+
+/// In pretty mode, writes a newline and indentation for `level`.
+fn write_newline<W: fmt::Write>(
+    out: &mut W,
+    options: &SerializeOptions,
+    level: usize,
+) -> fmt::Result {
+    if let Some(indent) = options.indent {
+        out.write_char('\n')?;
+        for _ in 0..level * indent {
+            out.write_char(' ')?;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn write_float<W: fmt::Write>(out: &mut W, f: f64) -> fmt::Result {
+    if !f.is_finite() {
+        return out.write_str("null");
+    }
+    // `Debug` prints the shortest representation that parses back to the same value, with a
+    // fraction or exponent.
+    write!(out, "{:?}", f)
+}
+
+/// Writes `str` with the quotes, escaping the characters that need to be escaped in JSON strings,
+/// and the characters selected in `options`.
+pub(crate) fn write_str<W: fmt::Write>(
+    out: &mut W,
+    str: &str,
+    options: &SerializeOptions,
+) -> fmt::Result {
+    out.write_char('"')?;
+
+    // Copy the characters that don't need escaping in chunks.
+    let mut chunk_start = 0;
+    for (byte_offset, char) in str.char_indices() {
+        let escape = match char {
+            '"' => Some("\\\""),
+            '\\' => Some("\\\\"),
+            '\n' => Some("\\n"),
+            '\r' => Some("\\r"),
+            '\t' => Some("\\t"),
+            '\u{8}' => Some("\\b"),
+            '\u{c}' => Some("\\f"),
+            _ => None,
+        };
+        let escape_code_point = char < ' '
+            || (options.ascii_only && !char.is_ascii())
+            || (options.html_safe && matches!(char, '<' | '>' | '&' | '\u{2028}' | '\u{2029}'));
+        if escape.is_none() && !escape_code_point {
+            continue;
+        }
+
+        out.write_str(&str[chunk_start..byte_offset])?;
+        chunk_start = byte_offset + char.len_utf8();
+
+        match escape {
+            Some(escape) => out.write_str(escape)?,
+            None => {
+                let mut buf = [0u16; 2];
+                for unit in char.encode_utf16(&mut buf) {
+                    write!(out, "\\u{:04x}", unit)?;
+                }
+            }
+        }
+    }
+    out.write_str(&str[chunk_start..])?;
+
+    out.write_char('"')
+}
+
+/// Implements [fmt::Write] for an [io::Write], keeping the I/O error.
+pub(crate) struct IoAdapter<W: io::Write> {
+    pub(crate) out: W,
+
+    /// The error of the last failed write.
+    pub(crate) error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoAdapter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.out.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+#[test]
+fn serialize_test() {
+    let json = crate::parse_ast(r#"{"a": [1, -2, 1.5, 1e300, true, null, []], "b": {}}"#).unwrap();
+    assert_eq!(
+        json.to_string(),
+        r#"{"a":[1,-2,1.5,1e300,true,null,[]],"b":{}}"#
+    );
+    assert_eq!(
+        format!("{:#}", json),
+        r#"{
+  "a": [
+    1,
+    -2,
+    1.5,
+    1e300,
+    true,
+    null,
+    []
+  ],
+  "b": {}
+}"#
+    );
+
+    assert_eq!(Json::Float(1.0).to_string(), "1.0");
+    assert_eq!(Json::Float(-0.0).to_string(), "-0.0");
+    assert_eq!(Json::Float(f64::NAN).to_string(), "null");
+    assert_eq!(Json::Number(String::from("1.50")).to_string(), "1.50");
+}
+
+#[test]
+fn escape_test() {
+    let json = Json::String(String::from("\"\\/\n\r\t\u{8}\u{c}\u{1}<é😀>&\u{2028}"));
+    assert_eq!(
+        json.to_string(),
+        "\"\\\"\\\\/\\n\\r\\t\\b\\f\\u0001<é😀>&\u{2028}\""
+    );
+
+    let mut out = String::new();
+    let options = SerializeOptions {
+        ascii_only: true,
+        html_safe: true,
+        ..SerializeOptions::default()
+    };
+    write_json(&mut out, &json, options).unwrap();
+    assert_eq!(
+        out,
+        "\"\\\"\\\\/\\n\\r\\t\\b\\f\\u0001\\u003c\\u00e9\\ud83d\\ude00\\u003e\\u0026\\u2028\""
+    );
+}
+
+#[test]
+fn io_test() {
+    let json = Json::Array(vec![Json::Int(1), Json::String(String::from("a"))]);
+    let mut out: Vec<u8> = vec![];
+    let options = SerializeOptions {
+        indent: Some(4),
+        ..SerializeOptions::default()
+    };
+    write_json_io(&mut out, &json, options).unwrap();
+    assert_eq!(out, b"[\n    1,\n    \"a\"\n]");
+}
+
+#[test]
+fn round_trip_tests() {
+    let options = [
+        SerializeOptions::default(),
+        SerializeOptions {
+            indent: Some(2),
+            ascii_only: true,
+            html_safe: true,
+        },
+    ];
+    let inputs = crate::test_common::ast_tests()
+        .into_iter()
+        .chain(crate::test_common::json5_ast_tests());
+    for (str, ast) in inputs {
+        // Infinity and NaN are written as null.
+        if str.contains("Infinity") || str.contains("NaN") {
+            continue;
+        }
+        for options in options {
+            let mut out = String::new();
+            write_json(&mut out, &ast, options).unwrap();
+            println!("Parsing {:?}, serialized from {:?}", out, str);
+            assert_eq!(crate::parse_ast(&out).unwrap(), ast);
+        }
+    }
+}
@@ -1,19 +1,26 @@
 /// A simple AST without comments and source locations.
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 pub enum Json {
+    /// A non-negative integer.
     Int(u64),
+
+    /// A negative integer.
+    NegInt(i64),
+
+    /// A number with a fraction or exponent, or `-0`.
+    Float(f64),
+
+    /// A number as it appears in the input, with
+    /// [ParseOptions::lossless_numbers][crate::ParseOptions::lossless_numbers].
+    Number(String),
+
     String(String),
     Bool(bool),
     Array(Vec<Json>),
     Object(Vec<(String, Json)>),
     Null,
-}
 
-impl Json {
-    pub(crate) fn into_string(self) -> String {
-        match self {
-            Json::String(str) => str,
-            _ => panic!(),
-        }
-    }
+    /// An error in place of a value, with
+    /// [ParseOptions::recover_errors][crate::ParseOptions::recover_errors].
+    Error,
 }
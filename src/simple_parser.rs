@@ -1,175 +1,234 @@
-use crate::{Json, ParseError};
+use crate::comment::{is_comment_start, scan_comment};
+use crate::error::{
+    Token, COLON, COMMA_OR_END_ARRAY, COMMA_OR_END_OBJECT, END_OF_INPUT, KEY, KEY_OR_END_OBJECT,
+    VALUE, VALUE_OR_END_ARRAY,
+};
+use crate::escape::{decode_string, scan_string};
+use crate::identifier::{is_identifier_start, scan_identifier};
+use crate::number::{is_number_start, scan_number};
+use crate::whitespace::{check_whitespace, is_whitespace};
+use crate::{AstNode, Dialect, Json, ParseError, ParseErrorKind, ParseOptions, Span, SpannedJson};
 
 use std::iter::Peekable;
 use std::str::CharIndices;
 
 /// Parses input directly to [Json].
 pub fn parse(input: &str) -> Result<Json, ParseError> {
+    parse_with_options(input, ParseOptions::default())
+}
+
+/// Parses input directly to [Json], with the given options.
+pub fn parse_with_options(input: &str, options: ParseOptions) -> Result<Json, ParseError> {
+    parse_generic(input, options)
+}
+
+/// Parses input directly to [SpannedJson].
+pub fn parse_spanned(input: &str) -> Result<SpannedJson, ParseError> {
+    parse_spanned_with_options(input, ParseOptions::default())
+}
+
+/// Parses input directly to [SpannedJson], with the given options.
+pub fn parse_spanned_with_options(
+    input: &str,
+    options: ParseOptions,
+) -> Result<SpannedJson, ParseError> {
+    parse_generic(input, options)
+}
+
+fn parse_generic<N: AstNode>(input: &str, options: ParseOptions) -> Result<N, ParseError> {
     let mut iter = input.char_indices().peekable();
-    let (_, json) = parse_single(&mut iter, input)?;
-    skip_trivia(&mut iter)?;
+    let (_, json) = parse_single(&mut iter, input, &options, VALUE, None)?;
+    skip_trivia(&mut iter, input, &options)?;
     if let Some((byte_offset, _)) = iter.next() {
         // We should return the parsed object with this error, but it's OK for the purposes of this
         // post.
-        return Err(ParseError {
+        return Err(ParseError::unexpected(
+            input,
             byte_offset,
-            reason: "trailing characters after paring",
-        });
+            END_OF_INPUT,
+            None,
+        ));
     }
     Ok(json)
 }
 
-fn parse_single(
+/// Parse a value. `expected` are the tokens valid at the value's location and
+/// `container_byte_offset` is the location of the current container, for errors.
+fn parse_single<N: AstNode>(
     iter: &mut Peekable<CharIndices>,
     input: &str,
-) -> Result<(usize, Json), ParseError> {
-    skip_trivia(iter)?;
+    options: &ParseOptions,
+    expected: &'static [Token],
+    container_byte_offset: Option<usize>,
+) -> Result<(usize, N), ParseError> {
+    skip_trivia(iter, input, options)?;
 
     let (byte_offset, char) = match iter.next() {
         Some(next) => next,
         None => {
-            return Err(ParseError {
-                byte_offset: input.len(),
-                reason: "unexpected end of input",
-            })
+            return Err(ParseError::unexpected(
+                input,
+                input.len(),
+                expected,
+                container_byte_offset,
+            ))
         }
     };
 
     if char == '[' {
-        let mut array: Vec<Json> = Vec::with_capacity(10);
+        let mut array: Vec<N> = Vec::with_capacity(10);
         loop {
-            skip_trivia(iter)?;
+            skip_trivia(iter, input, options)?;
             match iter.peek().copied() {
-                Some((_, ']')) => {
+                Some((end_byte_offset, ']')) => {
                     // Consume ']'
                     iter.next();
-                    return Ok((byte_offset, Json::Array(array)));
+                    let span = Span::new(byte_offset, end_byte_offset + 1);
+                    return Ok((byte_offset, N::array(array, span)));
                 }
 
                 Some((comma_byte_offset, ',')) => {
                     if array.is_empty() {
-                        return Err(ParseError {
-                            byte_offset: comma_byte_offset,
-                            reason: "unexpected character while parsing array",
-                        });
+                        return Err(ParseError::unexpected(
+                            input,
+                            comma_byte_offset,
+                            VALUE_OR_END_ARRAY,
+                            Some(byte_offset),
+                        ));
                     }
 
                     // Consume ','
                     iter.next();
-                    array.push(parse_single(iter, input)?.1);
-                }
 
-                Some((byte_offset, _)) => {
-                    if !array.is_empty() {
-                        // Need to see a ',' before the next element.
-                        return Err(ParseError {
-                            byte_offset,
-                            reason: "unexpected character while parsing array",
-                        });
-                    }
+                    let expected = if options.dialect == Dialect::Json5 {
+                        // Trailing comma, the loop consumes the ']'.
+                        skip_trivia(iter, input, options)?;
+                        if let Some((_, ']')) = iter.peek() {
+                            continue;
+                        }
+                        VALUE_OR_END_ARRAY
+                    } else {
+                        VALUE
+                    };
+
+                    array.push(parse_single(iter, input, options, expected, Some(byte_offset))?.1);
+                }
 
-                    array.push(parse_single(iter, input)?.1);
+                Some(_) if array.is_empty() => {
+                    array.push(
+                        parse_single(iter, input, options, VALUE_OR_END_ARRAY, Some(byte_offset))?
+                            .1,
+                    );
                 }
 
-                None => {
-                    return Err(ParseError {
-                        byte_offset: input.len(),
-                        reason: "end of input while parsing array",
-                    })
+                _ => {
+                    // Need to see a ',' before the next element.
+                    let expected = if array.is_empty() {
+                        VALUE_OR_END_ARRAY
+                    } else {
+                        COMMA_OR_END_ARRAY
+                    };
+                    return Err(ParseError::unexpected(
+                        input,
+                        next_byte_offset(iter, input),
+                        expected,
+                        Some(byte_offset),
+                    ));
                 }
             }
         }
     }
 
     if char == '{' {
-        let mut object: Vec<(String, Json)> = Vec::with_capacity(10);
+        let mut object: Vec<(N::Key, N)> = Vec::with_capacity(10);
 
-        enum State {
+        enum State<K> {
             Done,
             ExpectKey,
-            ExpectColon { key: String },
-            ExpectValue { key: String },
+            ExpectColon { key: K },
+            ExpectValue { key: K },
         }
 
-        let mut state = State::Done;
+        let mut state: State<N::Key> = State::Done;
 
         loop {
-            skip_trivia(iter)?;
+            skip_trivia(iter, input, options)?;
             match std::mem::replace(&mut state, State::Done) {
                 State::Done => {
                     match iter.peek().copied() {
-                        Some((byte_offset, ',')) => {
+                        Some((comma_byte_offset, ',')) => {
                             if object.is_empty() {
-                                return Err(ParseError {
-                                    byte_offset,
-                                    reason: "unexpected comma while parsing object",
-                                });
+                                return Err(ParseError::unexpected(
+                                    input,
+                                    comma_byte_offset,
+                                    KEY_OR_END_OBJECT,
+                                    Some(byte_offset),
+                                ));
                             }
                             iter.next(); // consume ','
                             state = State::ExpectKey;
                         }
 
-                        Some((_, '}')) => {
+                        Some((end_byte_offset, '}')) => {
                             iter.next(); // consume '}'
-                            return Ok((byte_offset, Json::Object(object)));
-                        }
-
-                        Some((_, '"')) => {
-                            let key = parse_single(iter, input)?.1.into_string();
-                            state = State::ExpectColon { key };
+                            let span = Span::new(byte_offset, end_byte_offset + 1);
+                            return Ok((byte_offset, N::object(object, span)));
                         }
 
-                        Some((byte_offset, _)) => {
-                            return Err(ParseError {
-                                byte_offset,
-                                reason: "unexpected char while parsing object",
-                            })
+                        Some(_) if object.is_empty() => {
+                            state = State::ExpectKey;
                         }
 
-                        None => {
-                            return Err(ParseError {
-                                byte_offset: input.len(),
-                                reason: "unexpected end of input while parsing object",
-                            })
+                        _ => {
+                            let expected = if object.is_empty() {
+                                KEY_OR_END_OBJECT
+                            } else {
+                                COMMA_OR_END_OBJECT
+                            };
+                            return Err(ParseError::unexpected(
+                                input,
+                                next_byte_offset(iter, input),
+                                expected,
+                                Some(byte_offset),
+                            ));
                         }
                     }
                 }
 
-                State::ExpectKey => match parse_single(iter, input)? {
-                    (_, Json::String(key)) => {
-                        state = State::ExpectColon { key };
-                    }
-
-                    (byte_offset, _) => {
-                        return Err(ParseError {
-                            byte_offset,
-                            reason: "unexpected value while parsing object key",
-                        })
+                State::ExpectKey => {
+                    if let Some((_, '}')) = iter.peek() {
+                        if options.dialect == Dialect::Json5 {
+                            // Trailing comma, the `Done` state consumes the '}'.
+                            continue;
+                        }
                     }
-                },
+                    let expected = if object.is_empty() || options.dialect == Dialect::Json5 {
+                        KEY_OR_END_OBJECT
+                    } else {
+                        KEY
+                    };
+                    let key = parse_key::<N>(iter, input, options, expected, byte_offset)?;
+                    state = State::ExpectColon { key };
+                }
 
-                State::ExpectColon { key } => match iter.next() {
+                State::ExpectColon { key } => match iter.peek() {
                     Some((_, ':')) => {
+                        iter.next(); // consume ':'
                         state = State::ExpectValue { key };
                     }
 
-                    Some((byte_offset, _)) => {
-                        return Err(ParseError {
-                            byte_offset,
-                            reason: "unexpected char while parsing object",
-                        })
-                    }
-
-                    None => {
-                        return Err(ParseError {
-                            byte_offset: input.len(),
-                            reason: "unexpected end of input while parsing object",
-                        })
+                    _ => {
+                        return Err(ParseError::unexpected(
+                            input,
+                            next_byte_offset(iter, input),
+                            COLON,
+                            Some(byte_offset),
+                        ))
                     }
                 },
 
                 State::ExpectValue { key } => {
-                    let value = parse_single(iter, input)?.1;
+                    let value = parse_single(iter, input, options, VALUE, Some(byte_offset))?.1;
                     object.push((key, value));
                     state = State::Done;
                 }
@@ -182,12 +241,11 @@ fn parse_single(
             && next_char(iter) == Some('u')
             && next_char(iter) == Some('e')
         {
-            return Ok((byte_offset, Json::Bool(true)));
+            let span = Span::new(byte_offset, next_byte_offset(iter, input));
+            let value = N::scalar(Json::Bool(true), span);
+            return Ok((byte_offset, value));
         }
-        return Err(ParseError {
-            byte_offset,
-            reason: "unexpected keyword",
-        });
+        return Err(ParseError::new(byte_offset, ParseErrorKind::InvalidKeyword));
     }
 
     if char == 'f' {
@@ -196,12 +254,11 @@ fn parse_single(
             && next_char(iter) == Some('s')
             && next_char(iter) == Some('e')
         {
-            return Ok((byte_offset, Json::Bool(false)));
+            let span = Span::new(byte_offset, next_byte_offset(iter, input));
+            let value = N::scalar(Json::Bool(false), span);
+            return Ok((byte_offset, value));
         }
-        return Err(ParseError {
-            byte_offset,
-            reason: "unexpected keyword",
-        });
+        return Err(ParseError::new(byte_offset, ParseErrorKind::InvalidKeyword));
     }
 
     if char == 'n' {
@@ -209,86 +266,115 @@ fn parse_single(
             && next_char(iter) == Some('l')
             && next_char(iter) == Some('l')
         {
-            return Ok((byte_offset, Json::Null));
+            let span = Span::new(byte_offset, next_byte_offset(iter, input));
+            let value = N::scalar(Json::Null, span);
+            return Ok((byte_offset, value));
         }
-        return Err(ParseError {
-            byte_offset,
-            reason: "unexpected keyword",
-        });
+        return Err(ParseError::new(byte_offset, ParseErrorKind::InvalidKeyword));
     }
 
-    if char.is_ascii_digit() {
-        let mut i: u64 = u64::from((char as u8) - b'0');
+    if is_number_start(char, options) {
+        let (end, number) = scan_number(iter, input, byte_offset, char, options)?;
+        let value = N::scalar(
+            number.into_json(input, byte_offset),
+            Span::new(byte_offset, end),
+        );
+        return Ok((byte_offset, value));
+    }
 
-        while let Some((_, next)) = iter.peek().copied() {
-            if !next.is_ascii_digit() {
-                break;
-            }
+    if char == '"' || (char == '\'' && options.dialect == Dialect::Json5) {
+        let (end, string) = parse_string_body(iter, byte_offset, char, input, options)?;
+        let value = N::scalar(Json::String(string), Span::new(byte_offset, end));
+        return Ok((byte_offset, value));
+    }
 
-            // Consume the digit.
-            iter.next();
+    Err(ParseError::unexpected(
+        input,
+        byte_offset,
+        expected,
+        container_byte_offset,
+    ))
+}
 
-            // Ignore overflows for the purposes of this post.
-            i *= 10;
-            i += u64::from((next as u8) - b'0');
+/// Parse an object key. `expected` are the tokens valid at the key's location, for errors.
+fn parse_key<N: AstNode>(
+    iter: &mut Peekable<CharIndices>,
+    input: &str,
+    options: &ParseOptions,
+    expected: &'static [Token],
+    container_byte_offset: usize,
+) -> Result<N::Key, ParseError> {
+    let (byte_offset, char) = match iter.next() {
+        Some(next) => next,
+        None => {
+            return Err(ParseError::unexpected(
+                input,
+                input.len(),
+                expected,
+                Some(container_byte_offset),
+            ))
         }
+    };
 
-        return Ok((byte_offset, Json::Int(i)));
+    if char == '"' || (char == '\'' && options.dialect == Dialect::Json5) {
+        let (end, key) = parse_string_body(iter, byte_offset, char, input, options)?;
+        return Ok(N::key(key, Span::new(byte_offset, end)));
     }
 
-    if char == '"' {
-        for (next_byte_offset, next) in iter.by_ref() {
-            if next == '"' {
-                let string = input[byte_offset + 1..next_byte_offset].to_string();
-                return Ok((byte_offset, Json::String(string)));
-            }
-        }
-
-        return Err(ParseError {
-            byte_offset: input.len(),
-            reason: "unexpected end of input while parsing string",
-        });
+    if options.dialect == Dialect::Json5 && is_identifier_start(char) {
+        let end = scan_identifier(iter, input.len());
+        let key = input[byte_offset..end].to_string();
+        return Ok(N::key(key, Span::new(byte_offset, end)));
     }
 
-    Err(ParseError {
+    Err(ParseError::unexpected(
+        input,
         byte_offset,
-        reason: "unexpected character",
-    })
+        expected,
+        Some(container_byte_offset),
+    ))
+}
+
+/// Parse a string after the opening `quote` at `byte_offset`. Returns the byte offset after the
+/// closing quote, and the string.
+fn parse_string_body(
+    iter: &mut Peekable<CharIndices>,
+    byte_offset: usize,
+    quote: char,
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(usize, String), ParseError> {
+    let (closing_quote, has_escapes) = scan_string(iter, input.len(), quote, options)?;
+    let string = decode_string(&input[byte_offset + 1..closing_quote], has_escapes);
+    Ok((closing_quote + 1, string))
 }
 
 fn next_char(iter: &mut Peekable<CharIndices>) -> Option<char> {
     iter.next().map(|(_, char)| char)
 }
 
-pub(crate) fn skip_trivia(iter: &mut Peekable<CharIndices>) -> Result<(), ParseError> {
+/// Byte offset of the next character, or end of input.
+fn next_byte_offset(iter: &mut Peekable<CharIndices>, input: &str) -> usize {
+    iter.peek()
+        .map_or(input.len(), |(byte_offset, _)| *byte_offset)
+}
+
+fn skip_trivia(
+    iter: &mut Peekable<CharIndices>,
+    input: &str,
+    options: &ParseOptions,
+) -> Result<(), ParseError> {
     while let Some((byte_offset, char)) = iter.peek().copied() {
-        if char.is_ascii_whitespace() {
+        if is_whitespace(char) {
+            check_whitespace(byte_offset, char, options)?;
             iter.next();
             continue;
         }
 
-        if char == '/' {
+        if is_comment_start(char, options) {
             iter.next();
-            match iter.next() {
-                Some((_, '/')) => {
-                    skip_until_eol(iter);
-                    break;
-                }
-
-                Some(_) => {
-                    return Err(ParseError {
-                        byte_offset,
-                        reason: "unexpected character",
-                    })
-                }
-
-                None => {
-                    return Err(ParseError {
-                        byte_offset,
-                        reason: "unexpected end of input",
-                    })
-                }
-            }
+            scan_comment(iter, input.len(), byte_offset, char, options)?;
+            continue;
         }
 
         break;
@@ -297,18 +383,145 @@ pub(crate) fn skip_trivia(iter: &mut Peekable<CharIndices>) -> Result<(), ParseE
     Ok(())
 }
 
-fn skip_until_eol(iter: &mut Peekable<CharIndices>) {
-    for (_, char) in iter.by_ref() {
-        if char == '\n' {
-            break;
+#[test]
+fn ast_tests() {
+    for (str, ast) in crate::test_common::ast_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(parse(&str).unwrap(), ast);
+    }
+}
+
+#[test]
+fn number_error_tests() {
+    for (str, byte_offset) in crate::test_common::number_error_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(parse(&str).unwrap_err().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn overflow_error_tests() {
+    for (str, byte_offset) in crate::test_common::overflow_error_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(parse(&str).unwrap_err().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn lossless_number_tests() {
+    let options = ParseOptions {
+        lossless_numbers: true,
+        ..crate::ParseOptions::default()
+    };
+    for (str, ast) in crate::test_common::lossless_number_ast_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(parse_with_options(&str, options).unwrap(), ast);
+    }
+}
+
+#[test]
+fn comment_option_tests() {
+    for (str, _) in crate::test_common::comment_option_event_tests() {
+        println!("Parsing {:?}", str);
+        assert!(parse_with_options(&str, crate::test_common::comment_options()).is_ok());
+    }
+}
+
+#[test]
+fn comment_error_tests() {
+    for (str, options, byte_offset) in crate::test_common::comment_error_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(
+            parse_with_options(&str, options).unwrap_err().byte_offset,
+            byte_offset
+        );
+    }
+}
+
+#[test]
+fn escape_error_tests() {
+    for (str, byte_offset) in crate::test_common::escape_error_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(parse(&str).unwrap_err().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn json5_tests() {
+    for (str, ast) in crate::test_common::json5_ast_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(
+            parse_with_options(&str, crate::test_common::json5_options()).unwrap(),
+            ast
+        );
+    }
+    let nan = parse_with_options("-NaN", crate::test_common::json5_options()).unwrap();
+    assert!(matches!(nan, Json::Float(f) if f.is_nan()));
+}
+
+#[test]
+fn json5_only_tests() {
+    for (str, byte_offset) in crate::test_common::json5_only_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(parse(&str).unwrap_err().byte_offset, byte_offset);
+    }
+}
+
+#[test]
+fn json5_error_tests() {
+    for (str, byte_offset) in crate::test_common::json5_error_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(
+            parse_with_options(&str, crate::test_common::json5_options())
+                .unwrap_err()
+                .byte_offset,
+            byte_offset
+        );
+    }
+}
+
+#[test]
+fn strict_tests() {
+    for (str, ast) in crate::test_common::ast_tests() {
+        if str.contains("//") || str.contains("/*") {
+            continue;
         }
+        println!("Parsing {:?}", str);
+        assert_eq!(
+            parse_with_options(&str, crate::test_common::strict_options()).unwrap(),
+            ast
+        );
     }
 }
 
 #[test]
-fn ast_tests() {
+fn strict_error_tests() {
+    for (str, byte_offset, kind) in crate::test_common::strict_error_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(
+            parse_with_options(&str, crate::test_common::strict_options()),
+            Err(ParseError { byte_offset, kind })
+        );
+    }
+}
+
+#[test]
+fn unexpected_token_tests() {
+    for (str, error) in crate::test_common::unexpected_token_tests() {
+        println!("Parsing {:?}", str);
+        assert_eq!(parse(&str), Err(error));
+    }
+}
+
+#[test]
+fn spanned_tests() {
     for (str, ast) in crate::test_common::ast_tests() {
         println!("Parsing {:?}", str);
-        assert_eq!(parse(&str).unwrap(), ast);
+        assert_eq!(parse_spanned(&str).unwrap().into_json(), ast);
+    }
+    for (str, options, texts) in crate::test_common::spanned_tests() {
+        println!("Parsing {:?}", str);
+        let ast = parse_spanned_with_options(&str, options).unwrap();
+        assert_eq!(crate::test_common::span_texts(&str, &ast), texts);
     }
 }
@@ -0,0 +1,104 @@
+use crate::comment::{is_comment_start, scan_comment};
+use crate::error::{COMMA_OR_END_ARRAY, COMMA_OR_END_OBJECT};
+use crate::escape::scan_string;
+use crate::event_parser::Container;
+use crate::{CommentKind, Dialect, ParseError, ParseOptions};
+
+use std::iter::Peekable;
+
+/// A token scanned by [skip_token].
+pub(crate) struct Skipped {
+    /// Byte offset after the token.
+    pub(crate) end: usize,
+
+    /// The kind of the token when it's a comment.
+    pub(crate) comment: Option<CommentKind>,
+}
+
+/// Skips arrays and objects until `container_stack` has `depth` containers, without parsing the
+/// values. Returns the byte offset after the last closing bracket.
+///
+/// `iter` yields the characters of `input` after the opening brackets in `container_stack`.
+pub(crate) fn skip_containers<I: Iterator<Item = (usize, char)>>(
+    iter: &mut Peekable<I>,
+    input: &str,
+    container_stack: &mut Vec<(Container, usize)>,
+    depth: usize,
+    options: &ParseOptions,
+) -> Result<usize, ParseError> {
+    let mut end = 0;
+    while container_stack.len() > depth {
+        let next = match iter.next() {
+            Some(next) => next,
+            None => return Err(skip_error(input, input.len(), container_stack)),
+        };
+        end = skip_token(iter, input, next, container_stack, 0, options)?.end;
+    }
+    Ok(end)
+}
+
+/// Scans the token that starts with `(start, char)`, consumed from `iter`, in an array or object
+/// that is skipped. Only brackets, strings and comments are scanned: strings and comments may have
+/// brackets. Other characters are skipped one at a time.
+///
+/// Opening brackets are pushed to `container_stack` with their byte offset plus `base`, matching
+/// closing brackets are popped. Errors are at byte offsets in `input`.
+pub(crate) fn skip_token<I: Iterator<Item = (usize, char)>>(
+    iter: &mut Peekable<I>,
+    input: &str,
+    (start, char): (usize, char),
+    container_stack: &mut Vec<(Container, usize)>,
+    base: usize,
+    options: &ParseOptions,
+) -> Result<Skipped, ParseError> {
+    let mut comment = None;
+    let end = match char {
+        '[' => {
+            container_stack.push((Container::Array, base + start));
+            start + 1
+        }
+
+        '{' => {
+            container_stack.push((Container::Object, base + start));
+            start + 1
+        }
+
+        ']' | '}' => {
+            let (container, _) = *container_stack.last().unwrap();
+            if (char == ']') != (container == Container::Array) {
+                return Err(skip_error(input, start, container_stack));
+            }
+            container_stack.pop();
+            start + 1
+        }
+
+        '"' | '\'' if char == '"' || options.dialect == Dialect::Json5 => {
+            let (closing_quote, _) = scan_string(iter, input.len(), char, options)?;
+            closing_quote + 1
+        }
+
+        _ if is_comment_start(char, options) => {
+            let (size_in_bytes, kind) = scan_comment(iter, input.len(), start, char, options)?;
+            comment = Some(kind);
+            start + size_in_bytes
+        }
+
+        _ => start + char.len_utf8(),
+    };
+    Ok(Skipped { end, comment })
+}
+
+/// The error for an unexpected closing bracket or end of input at `byte_offset` in a skipped
+/// array or object.
+pub(crate) fn skip_error(
+    input: &str,
+    byte_offset: usize,
+    container_stack: &[(Container, usize)],
+) -> ParseError {
+    let (container, container_byte_offset) = *container_stack.last().unwrap();
+    let expected = match container {
+        Container::Array => COMMA_OR_END_ARRAY,
+        Container::Object => COMMA_OR_END_OBJECT,
+    };
+    ParseError::unexpected(input, byte_offset, expected, Some(container_byte_offset))
+}
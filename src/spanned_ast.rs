@@ -0,0 +1,175 @@
+use crate::Json;
+
+/// A part of the input, as byte offsets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    /// Byte offset of the first character.
+    pub start: usize,
+
+    /// Byte offset after the last character.
+    pub end: usize,
+}
+
+/// An AST with source locations of values and object keys, without comments.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedJson {
+    pub kind: SpannedJsonKind,
+
+    /// Location of the value. For strings the span includes the quotes, for arrays and objects the
+    /// brackets.
+    pub span: Span,
+}
+
+/// Details of a [SpannedJson] value.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpannedJsonKind {
+    /// A non-negative integer.
+    Int(u64),
+
+    /// A negative integer.
+    NegInt(i64),
+
+    /// A number with a fraction or exponent, or `-0`.
+    Float(f64),
+
+    /// A number as it appears in the input, with
+    /// [ParseOptions::lossless_numbers][crate::ParseOptions::lossless_numbers].
+    Number(String),
+
+    String(String),
+    Bool(bool),
+    Array(Vec<SpannedJson>),
+    Object(Vec<(SpannedKey, SpannedJson)>),
+    Null,
+
+    /// An error in place of a value, with
+    /// [ParseOptions::recover_errors][crate::ParseOptions::recover_errors]. The span is empty, at
+    /// the error location.
+    Error,
+}
+
+/// An object key in [SpannedJson].
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedKey {
+    pub key: String,
+
+    /// Location of the key, including the quotes.
+    pub span: Span,
+}
+
+/// ASTs built by the parsers: [Json] and [SpannedJson]. The trait is sealed, the nodes are only
+/// made by the parsers.
+pub trait AstNode: sealed::NodeBuilder {}
+
+impl AstNode for Json {}
+
+impl AstNode for SpannedJson {}
+
+pub(crate) mod sealed {
+    use crate::{Json, Span};
+
+    /// Makes the nodes of an [AstNode][super::AstNode]. Not nameable outside of the crate.
+    pub trait NodeBuilder: Sized {
+        /// Type of the object keys.
+        type Key;
+
+        /// Makes a node for a value other than an array or object.
+        fn scalar(value: Json, span: Span) -> Self;
+
+        fn array(elements: Vec<Self>, span: Span) -> Self;
+
+        fn object(members: Vec<(Self::Key, Self)>, span: Span) -> Self;
+
+        fn key(key: String, span: Span) -> Self::Key;
+    }
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+}
+
+impl SpannedJson {
+    /// Drops the locations.
+    pub fn into_json(self) -> Json {
+        match self.kind {
+            SpannedJsonKind::Int(i) => Json::Int(i),
+            SpannedJsonKind::NegInt(i) => Json::NegInt(i),
+            SpannedJsonKind::Float(f) => Json::Float(f),
+            SpannedJsonKind::Number(number) => Json::Number(number),
+            SpannedJsonKind::String(str) => Json::String(str),
+            SpannedJsonKind::Bool(b) => Json::Bool(b),
+            SpannedJsonKind::Array(elements) => {
+                Json::Array(elements.into_iter().map(SpannedJson::into_json).collect())
+            }
+            SpannedJsonKind::Object(members) => Json::Object(
+                members
+                    .into_iter()
+                    .map(|(key, value)| (key.key, value.into_json()))
+                    .collect(),
+            ),
+            SpannedJsonKind::Null => Json::Null,
+            SpannedJsonKind::Error => Json::Error,
+        }
+    }
+}
+
+impl sealed::NodeBuilder for Json {
+    type Key = String;
+
+    fn scalar(value: Json, _span: Span) -> Self {
+        value
+    }
+
+    fn array(elements: Vec<Self>, _span: Span) -> Self {
+        Json::Array(elements)
+    }
+
+    fn object(members: Vec<(Self::Key, Self)>, _span: Span) -> Self {
+        Json::Object(members)
+    }
+
+    fn key(key: String, _span: Span) -> Self::Key {
+        key
+    }
+}
+
+impl sealed::NodeBuilder for SpannedJson {
+    type Key = SpannedKey;
+
+    fn scalar(value: Json, span: Span) -> Self {
+        let kind = match value {
+            Json::Int(i) => SpannedJsonKind::Int(i),
+            Json::NegInt(i) => SpannedJsonKind::NegInt(i),
+            Json::Float(f) => SpannedJsonKind::Float(f),
+            Json::Number(number) => SpannedJsonKind::Number(number),
+            Json::String(str) => SpannedJsonKind::String(str),
+            Json::Bool(b) => SpannedJsonKind::Bool(b),
+            Json::Null => SpannedJsonKind::Null,
+            Json::Error => SpannedJsonKind::Error,
+            Json::Array(_) | Json::Object(_) => {
+                unreachable!("arrays and objects are made with `array` and `object`")
+            }
+        };
+        SpannedJson { kind, span }
+    }
+
+    fn array(elements: Vec<Self>, span: Span) -> Self {
+        SpannedJson {
+            kind: SpannedJsonKind::Array(elements),
+            span,
+        }
+    }
+
+    fn object(members: Vec<(Self::Key, Self)>, span: Span) -> Self {
+        SpannedJson {
+            kind: SpannedJsonKind::Object(members),
+            span,
+        }
+    }
+
+    fn key(key: String, span: Span) -> Self::Key {
+        SpannedKey { key, span }
+    }
+}
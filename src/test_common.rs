@@ -1,4 +1,11 @@
-use crate::{Json, ParseEventKind};
+use crate::error::{
+    Token, COLON, COMMA_OR_END_ARRAY, COMMA_OR_END_OBJECT, END_OF_INPUT, KEY_OR_END_OBJECT, VALUE,
+};
+use crate::event_listener::EventHandler;
+use crate::{
+    CommentKind, ControlFlow, Dialect, FinishListener, Json, ParseError, ParseErrorKind,
+    ParseEvent, ParseEventKind, ParseOptions, SpannedJson, SpannedJsonKind,
+};
 
 #[rustfmt::skip]
 pub(crate) fn ast_tests() -> Vec<(String, Json)> {
@@ -68,6 +75,44 @@ pub(crate) fn ast_tests() -> Vec<(String, Json)> {
                 (s("g"), Json::Array(vec![])),
             ])
         ),
+
+        // Block comments and comments terminated by end of input
+        (s("/* hi */ 1"), Json::Int(1)),
+        (s("1 // hi"), Json::Int(1)),
+        (s("// a\n// b\n1"), Json::Int(1)),
+        (s("[1 /* a */, /* b */ 2]"), Json::Array(vec![Json::Int(1), Json::Int(2)])),
+        (s(r#"{"a" /* b */ : /* c */ 1 /* d */}"#), Json::Object(vec![(s("a"), Json::Int(1))])),
+
+        // Numbers
+        (s("-1"), Json::NegInt(-1)),
+        (s("0"), Json::Int(0)),
+        (s("-0"), Json::Float(-0.0)),
+        (s("3.25"), Json::Float(3.25)),
+        (s("-0.5"), Json::Float(-0.5)),
+        (s("1e9"), Json::Float(1e9)),
+        (s("1E+2"), Json::Float(100.0)),
+        (s("25e-1"), Json::Float(2.5)),
+        (s("18446744073709551615"), Json::Int(u64::MAX)),
+        (s("-9223372036854775808"), Json::NegInt(i64::MIN)),
+        (
+            s(r#"[ -12, 1.5, { "a": -1e-3 } ]"#),
+            Json::Array(vec![
+                Json::NegInt(-12),
+                Json::Float(1.5),
+                Json::Object(vec![(s("a"), Json::Float(-1e-3))]),
+            ])
+        ),
+
+        // Strings with escape sequences
+        (s(r#""a\"b""#), Json::String(s("a\"b"))),
+        (s(r#""\\\/\b\f\n\r\t""#), Json::String(s("\\/\u{8}\u{c}\n\r\t"))),
+        (s(r#""\u00e9\u00E9""#), Json::String(s("\u{e9}\u{e9}"))),
+        (s(r#""\ud83d\ude00""#), Json::String(s("\u{1f600}"))),
+        (s(r#"["\"", "//"]"#), Json::Array(vec![Json::String(s("\"")), Json::String(s("//"))])),
+        (
+            s(r#"{ "a\nb": "\"x\"" }"#),
+            Json::Object(vec![(s("a\nb"), Json::String(s("\"x\"")))])
+        ),
     ]
 }
 
@@ -78,7 +123,7 @@ pub(crate) fn event_tests() -> Vec<(String, Vec<ParseEventKind>)> {
     vec![
         // Simple cases
         (s("123"), vec![Int(123)]),
-        (s(r#""hi""#), vec![Str { size_in_bytes: 2 }]),
+        (s(r#""hi""#), vec![Str { size_in_bytes: 2, has_escapes: false }]),
         (s("true"), vec![Bool(true)]),
         (s("false"), vec![Bool(false)]),
         (s("[]"), vec![StartArray, EndArray]),
@@ -87,7 +132,7 @@ pub(crate) fn event_tests() -> Vec<(String, Vec<ParseEventKind>)> {
 
         // Simple cases + whitespace
         (s(" 123 "), vec![Int(123)]),
-        (s(r#" "hi" "#), vec![Str { size_in_bytes: 2 }]),
+        (s(r#" "hi" "#), vec![Str { size_in_bytes: 2, has_escapes: false }]),
         (s(" true "), vec![Bool(true)]),
         (s(" false "), vec![Bool(false)]),
         (s(" [ ] "), vec![StartArray, EndArray]),
@@ -96,7 +141,7 @@ pub(crate) fn event_tests() -> Vec<(String, Vec<ParseEventKind>)> {
 
         // Simple cases, with comments before JSON
         (add_comment_before("123"), vec![COMMENT, Int(123)]),
-        (add_comment_before(r#""hi""#), vec![COMMENT, Str { size_in_bytes: 2 }]),
+        (add_comment_before(r#""hi""#), vec![COMMENT, Str { size_in_bytes: 2, has_escapes: false }]),
         (add_comment_before("true"), vec![COMMENT, Bool(true)]),
         (add_comment_before("false"), vec![COMMENT, Bool(false)]),
         (add_comment_before("[]"), vec![COMMENT, StartArray, EndArray]),
@@ -105,7 +150,7 @@ pub(crate) fn event_tests() -> Vec<(String, Vec<ParseEventKind>)> {
 
         // Simple cases, with comments after JSON
         (add_comment_after("123"), vec![Int(123), COMMENT]),
-        (add_comment_after(r#""hi""#), vec![Str { size_in_bytes: 2 }, COMMENT]),
+        (add_comment_after(r#""hi""#), vec![Str { size_in_bytes: 2, has_escapes: false }, COMMENT]),
         (add_comment_after("true"), vec![Bool(true), COMMENT]),
         (add_comment_after("false"), vec![Bool(false), COMMENT]),
         (add_comment_after("[]"), vec![StartArray, EndArray, COMMENT]),
@@ -124,8 +169,8 @@ pub(crate) fn event_tests() -> Vec<(String, Vec<ParseEventKind>)> {
             ]"#), vec![StartArray, Int(1), COMMENT, Int(2), EndArray]),
         (s(r#"{"a":1 // hi
             ,"b":2
-            }"#), vec![StartObject, Str { size_in_bytes: 1 }, Int(1), COMMENT,
-                       Str { size_in_bytes: 1 }, Int(2), EndObject]),
+            }"#), vec![StartObject, Str { size_in_bytes: 1, has_escapes: false }, Int(1), COMMENT,
+                       Str { size_in_bytes: 1, has_escapes: false }, Int(2), EndObject]),
 
         // Comments inside container: after a comma
         (s(r#"[1, // hi
@@ -133,41 +178,521 @@ pub(crate) fn event_tests() -> Vec<(String, Vec<ParseEventKind>)> {
             ]"#), vec![StartArray, Int(1), COMMENT, Int(2), EndArray]),
         (s(r#"{"a":1, // hi
             "b":2
-            }"#), vec![StartObject, Str { size_in_bytes: 1 }, Int(1), COMMENT,
-                       Str { size_in_bytes: 1 }, Int(2), EndObject]),
+            }"#), vec![StartObject, Str { size_in_bytes: 1, has_escapes: false }, Int(1), COMMENT,
+                       Str { size_in_bytes: 1, has_escapes: false }, Int(2), EndObject]),
 
         // Comments inside container: before a colon
         (s(r#"{"a" // hi
-            :1}"#), vec![StartObject, Str { size_in_bytes: 1 }, COMMENT, Int(1), EndObject]),
+            :1}"#), vec![StartObject, Str { size_in_bytes: 1, has_escapes: false }, COMMENT, Int(1), EndObject]),
 
         // Comments inside container: after a colon
         (s(r#"{"a": // hi
-            1}"#), vec![StartObject, Str { size_in_bytes: 1 }, COMMENT, Int(1), EndObject]),
+            1}"#), vec![StartObject, Str { size_in_bytes: 1, has_escapes: false }, COMMENT, Int(1), EndObject]),
 
         // Arrays
         (s(r#"[ 1 ]"#), vec![StartArray, Int(1), EndArray]),
         (
             s(r#"[ true, false, "hi", null, 456, {}, [] ]"#),
             vec![
-                StartArray, Bool(true), Bool(false), Str { size_in_bytes: 2 },
+                StartArray, Bool(true), Bool(false), Str { size_in_bytes: 2, has_escapes: false },
                 Null, Int(456), StartObject, EndObject, StartArray, EndArray, EndArray,
             ]
         ),
 
         // Object
-        (s(r#"{ "a" : 1 }"#), vec![StartObject, Str { size_in_bytes: 1 }, Int(1), EndObject]),
+        (s(r#"{ "a" : 1 }"#), vec![StartObject, Str { size_in_bytes: 1, has_escapes: false }, Int(1), EndObject]),
         (
             s(r#"{ "a": true, "b": false, "c": "hi", "d": null, "e": 456, "f": {}, "g": [] }"#),
             vec![
-                StartObject, Str { size_in_bytes: 1 }, Bool(true), Str { size_in_bytes: 1 }, Bool(false),
-                Str { size_in_bytes: 1 }, Str { size_in_bytes: 2 }, Str { size_in_bytes: 1 }, Null,
-                Str { size_in_bytes: 1 }, Int(456), Str { size_in_bytes: 1 }, StartObject, EndObject,
-                Str { size_in_bytes: 1 }, StartArray, EndArray, EndObject,
+                StartObject, Str { size_in_bytes: 1, has_escapes: false }, Bool(true), Str { size_in_bytes: 1, has_escapes: false }, Bool(false),
+                Str { size_in_bytes: 1, has_escapes: false }, Str { size_in_bytes: 2, has_escapes: false }, Str { size_in_bytes: 1, has_escapes: false }, Null,
+                Str { size_in_bytes: 1, has_escapes: false }, Int(456), Str { size_in_bytes: 1, has_escapes: false }, StartObject, EndObject,
+                Str { size_in_bytes: 1, has_escapes: false }, StartArray, EndArray, EndObject,
+            ]
+        ),
+
+        // Block comments and comments terminated by end of input
+        (s("/* hi */ 1"), vec![Comment { size_in_bytes: 8, kind: CommentKind::Block }, Int(1)]),
+        (s("/**/1"), vec![Comment { size_in_bytes: 4, kind: CommentKind::Block }, Int(1)]),
+        (s("/***/1"), vec![Comment { size_in_bytes: 5, kind: CommentKind::Block }, Int(1)]),
+        (s("/* a /* b */ 1"), vec![Comment { size_in_bytes: 12, kind: CommentKind::Block }, Int(1)]),
+        (s("1 // hi"), vec![Int(1), Comment { size_in_bytes: 5, kind: CommentKind::Line }]),
+        (s("// a\n// b\n1"), vec![Comment { size_in_bytes: 5, kind: CommentKind::Line },
+                                  Comment { size_in_bytes: 5, kind: CommentKind::Line }, Int(1)]),
+        (
+            s("[1 /* a */, /* b */ 2]"),
+            vec![
+                StartArray, Int(1), Comment { size_in_bytes: 7, kind: CommentKind::Block },
+                Comment { size_in_bytes: 7, kind: CommentKind::Block }, Int(2), EndArray,
+            ]
+        ),
+
+        // Numbers
+        (s("-1"), vec![NegInt(-1)]),
+        (s("-0"), vec![Float(-0.0)]),
+        (s("3.25"), vec![Float(3.25)]),
+        (s("1e9"), vec![Float(1e9)]),
+        (s("[-12,1.5E-1]"), vec![StartArray, NegInt(-12), Float(0.15), EndArray]),
+        (s(r#"{"a":-1}"#), vec![StartObject, Str { size_in_bytes: 1, has_escapes: false }, NegInt(-1), EndObject]),
+
+        // Strings with escape sequences
+        (s(r#""a\"b""#), vec![Str { size_in_bytes: 4, has_escapes: true }]),
+        (s(r#""\ud83d\ude00""#), vec![Str { size_in_bytes: 12, has_escapes: true }]),
+        (s(r#""//""#), vec![Str { size_in_bytes: 2, has_escapes: false }]),
+        (
+            s(r#"{ "a\nb": "\"" }"#),
+            vec![
+                StartObject, Str { size_in_bytes: 4, has_escapes: true },
+                Str { size_in_bytes: 2, has_escapes: true }, EndObject,
+            ]
+        ),
+    ]
+}
+
+/// Options with `#` comments and nested block comments enabled.
+pub(crate) fn comment_options() -> ParseOptions {
+    ParseOptions {
+        hash_comments: true,
+        nested_block_comments: true,
+        ..ParseOptions::default()
+    }
+}
+
+/// Inputs for parsing with [comment_options], and the expected events.
+#[rustfmt::skip]
+pub(crate) fn comment_option_event_tests() -> Vec<(String, Vec<ParseEventKind>)> {
+    use ParseEventKind::*;
+
+    vec![
+        (s("# hi\n1"), vec![Comment { size_in_bytes: 5, kind: CommentKind::Hash }, Int(1)]),
+        (s("1 # hi"), vec![Int(1), Comment { size_in_bytes: 4, kind: CommentKind::Hash }]),
+        (
+            s("[1, # a\n2]"),
+            vec![StartArray, Int(1), Comment { size_in_bytes: 4, kind: CommentKind::Hash }, Int(2), EndArray]
+        ),
+        (s("/* a /* b */ c */ 1"), vec![Comment { size_in_bytes: 17, kind: CommentKind::Block }, Int(1)]),
+        (s("/*/**/*/1"), vec![Comment { size_in_bytes: 8, kind: CommentKind::Block }, Int(1)]),
+    ]
+}
+
+/// Inputs with invalid or disabled comments, parse options, and byte offsets of the errors.
+#[rustfmt::skip]
+pub(crate) fn comment_error_tests() -> Vec<(String, ParseOptions, usize)> {
+    let default = ParseOptions::default();
+    let no_line_comments = ParseOptions { line_comments: false, ..default };
+    let no_block_comments = ParseOptions { block_comments: false, ..default };
+    vec![
+        (s("/* abc"), default, 0),
+        (s("[1, /* a ]"), default, 4),
+        (s("1 /"), default, 2),
+        (s("# a\n1"), default, 0),
+        (s("/* /* */ 1"), comment_options(), 0),
+        (s("// a\n1"), no_line_comments, 0),
+        (s("/* a */ 1"), no_block_comments, 0),
+    ]
+}
+
+/// Inputs with invalid numbers, and byte offsets of the errors.
+#[rustfmt::skip]
+pub(crate) fn number_error_tests() -> Vec<(String, usize)> {
+    vec![
+        (s("-"), 1),
+        (s("[-x]"), 2),
+        (s("1."), 2),
+        (s("1.e5"), 2),
+        (s("1e"), 2),
+        (s("[1e+]"), 4),
+        (s("-.5"), 1),
+    ]
+}
+
+/// Inputs with numbers that don't fit into the number types, and byte offsets of the errors.
+#[rustfmt::skip]
+pub(crate) fn overflow_error_tests() -> Vec<(String, usize)> {
+    vec![
+        (s("18446744073709551616"), 0),
+        (s("-9223372036854775809"), 0),
+        (s("[1, 123456789012345678901234567890]"), 4),
+        (s(r#"{"a": -99999999999999999999}"#), 6),
+        (s("1e400"), 0),
+    ]
+}
+
+/// Inputs for parsing with [ParseOptions::lossless_numbers][crate::ParseOptions], and the
+/// expected events.
+#[rustfmt::skip]
+pub(crate) fn lossless_number_event_tests() -> Vec<(String, Vec<ParseEventKind>)> {
+    use ParseEventKind::*;
+
+    vec![
+        (s("123"), vec![Number { size_in_bytes: 3 }]),
+        (s("123456789012345678901234567890"), vec![Number { size_in_bytes: 30 }]),
+        (
+            s("[-1.5e10, 0, true]"),
+            vec![StartArray, Number { size_in_bytes: 7 }, Number { size_in_bytes: 1 }, Bool(true), EndArray]
+        ),
+    ]
+}
+
+/// Inputs for parsing with [ParseOptions::lossless_numbers][crate::ParseOptions], and the
+/// expected ASTs.
+#[rustfmt::skip]
+pub(crate) fn lossless_number_ast_tests() -> Vec<(String, Json)> {
+    vec![
+        (s("123"), Json::Number(s("123"))),
+        (s("123456789012345678901234567890"), Json::Number(s("123456789012345678901234567890"))),
+        (
+            s(r#"{"id": -18446744073709551616.000, "n": [1e400]}"#),
+            Json::Object(vec![
+                (s("id"), Json::Number(s("-18446744073709551616.000"))),
+                (s("n"), Json::Array(vec![Json::Number(s("1e400"))])),
+            ])
+        ),
+    ]
+}
+
+/// Inputs with invalid escape sequences, and byte offsets of the errors.
+#[rustfmt::skip]
+pub(crate) fn escape_error_tests() -> Vec<(String, usize)> {
+    vec![
+        (s(r#""\q""#), 2),
+        (s(r#"["ab\x"]"#), 5),
+        (s(r#""\u12G4""#), 5),
+        (s(r#""\ud83d""#), 1),
+        (s(r#""\ud83dx""#), 1),
+        (s(r#""\ud83d\n""#), 1),
+        (s(r#""\ude00""#), 1),
+        (s(r#"{"\ud83d\u0041": 1}"#), 2),
+    ]
+}
+
+/// Options with the JSON5 dialect.
+pub(crate) fn json5_options() -> ParseOptions {
+    ParseOptions {
+        dialect: Dialect::Json5,
+        ..ParseOptions::default()
+    }
+}
+
+/// Inputs for parsing with [json5_options], and the expected events.
+#[rustfmt::skip]
+pub(crate) fn json5_event_tests() -> Vec<(String, Vec<ParseEventKind>)> {
+    use ParseEventKind::*;
+
+    vec![
+        (s("'a'"), vec![Str { size_in_bytes: 1, has_escapes: false }]),
+        (s(r#"'a"\'b'"#), vec![Str { size_in_bytes: 5, has_escapes: true }]),
+        (s("[1,]"), vec![StartArray, Int(1), EndArray]),
+        (s("[1, // a\n]"), vec![StartArray, Int(1), Comment { size_in_bytes: 5, kind: CommentKind::Line }, EndArray]),
+        (
+            s("{a: 1, $_b2: 'x',}"),
+            vec![
+                StartObject,
+                Str { size_in_bytes: 1, has_escapes: false },
+                Int(1),
+                Str { size_in_bytes: 4, has_escapes: false },
+                Str { size_in_bytes: 1, has_escapes: false },
+                EndObject,
+            ]
+        ),
+        (s("{'a': 1}"), vec![StartObject, Str { size_in_bytes: 1, has_escapes: false }, Int(1), EndObject]),
+        (s("0x1F"), vec![Int(31)]),
+        (s("-0XfF"), vec![NegInt(-255)]),
+        (s("+1"), vec![Int(1)]),
+        (s("[.5, 5., +.5e1]"), vec![StartArray, Float(0.5), Float(5.0), Float(5.0), EndArray]),
+        (s("[Infinity, -Infinity]"), vec![StartArray, Float(f64::INFINITY), Float(f64::NEG_INFINITY), EndArray]),
+    ]
+}
+
+/// Inputs for parsing with [json5_options], and the expected ASTs.
+#[rustfmt::skip]
+pub(crate) fn json5_ast_tests() -> Vec<(String, Json)> {
+    vec![
+        (s(r#"'a\x41"'"#), Json::String(s("aA\""))),
+        (s("[1, 2,]"), Json::Array(vec![Json::Int(1), Json::Int(2)])),
+        (
+            s("{a: 0x10, 'b': [+Infinity,], c_: -.5,}"),
+            Json::Object(vec![
+                (s("a"), Json::Int(16)),
+                (s("b"), Json::Array(vec![Json::Float(f64::INFINITY)])),
+                (s("c_"), Json::Float(-0.5)),
+            ])
+        ),
+        (
+            s("\u{FEFF}[1,\x0B2,\u{A0}3,\u{2028}4,\u{2029}5,\u{2003}6,\u{3000}7]"),
+            Json::Array((1..=7).map(Json::Int).collect()),
+        ),
+    ]
+}
+
+/// Inputs accepted in JSON5 but not in JSON, and byte offsets of the errors when parsing as JSON.
+#[rustfmt::skip]
+pub(crate) fn json5_only_tests() -> Vec<(String, usize)> {
+    vec![
+        (s("'a'"), 0),
+        (s("[1,]"), 3),
+        (s("[1, /* a */ ]"), 12),
+        (s(r#"{"a": 1,}"#), 8),
+        (s("{a: 1}"), 1),
+        (s("0x1"), 1),
+        (s("+1"), 0),
+        (s("NaN"), 0),
+        (s(r#""\x41""#), 2),
+        (s("[1,\u{2028}2]"), 3),
+    ]
+}
+
+/// Inputs that are invalid in JSON5, and byte offsets of the errors.
+#[rustfmt::skip]
+pub(crate) fn json5_error_tests() -> Vec<(String, usize)> {
+    vec![
+        (s("[,]"), 1),
+        (s("[1,,]"), 3),
+        (s("{,}"), 1),
+        (s("0x"), 2),
+        (s("Infinit"), 0),
+        (s(r#"'\x4g'"#), 4),
+        (s("{1: 2}"), 1),
+        (s("[1,\u{85}2]"), 3),
+    ]
+}
+
+/// Options with the strict dialect.
+pub(crate) fn strict_options() -> ParseOptions {
+    ParseOptions {
+        dialect: Dialect::Strict,
+        ..ParseOptions::default()
+    }
+}
+
+/// Inputs that are invalid with [strict_options], byte offsets of the errors, and the error
+/// kinds.
+#[rustfmt::skip]
+pub(crate) fn strict_error_tests() -> Vec<(String, usize, ParseErrorKind)> {
+    use ParseErrorKind::*;
+
+    vec![
+        (s("// a\n1"), 0, CommentNotAllowed),
+        (s("[1 /* a */]"), 3, CommentNotAllowed),
+        (s("0123"), 0, LeadingZero),
+        (s("[1, -00.5]"), 5, LeadingZero),
+        (s("\"a\tb\""), 2, ControlCharacterInString),
+        (s("{\"a\nb\": 1}"), 3, ControlCharacterInString),
+        (s("\x0C1"), 0, InvalidWhitespace),
+        (s("[1,\u{a0}2]"), 3, InvalidWhitespace),
+    ]
+}
+
+/// Inputs with unexpected tokens, and the errors.
+#[rustfmt::skip]
+pub(crate) fn unexpected_token_tests() -> Vec<(String, ParseError)> {
+    let error = |byte_offset, found, expected, container_byte_offset| ParseError {
+        byte_offset,
+        kind: ParseErrorKind::UnexpectedToken { found, expected, container_byte_offset },
+    };
+    vec![
+        (s(""), error(0, Token::EndOfInput, VALUE, None)),
+        (s("[1 2]"), error(3, Token::Number, COMMA_OR_END_ARRAY, Some(0))),
+        (s("[[]"), error(3, Token::EndOfInput, COMMA_OR_END_ARRAY, Some(0))),
+        (s("{"), error(1, Token::EndOfInput, KEY_OR_END_OBJECT, Some(0))),
+        (s("{1: 2}"), error(1, Token::Number, KEY_OR_END_OBJECT, Some(0))),
+        (s(r#"{"a" 1}"#), error(5, Token::Number, COLON, Some(0))),
+        (s(r#"{"a": 1 "b": 2}"#), error(8, Token::String, COMMA_OR_END_OBJECT, Some(0))),
+        (s(r#"{"a": [1, {"b": }]}"#), error(16, Token::EndObject, VALUE, Some(10))),
+        (s("[1] x"), error(4, Token::Char('x'), END_OF_INPUT, None)),
+        (s("]"), error(0, Token::EndArray, VALUE, None)),
+    ]
+}
+
+/// Inputs for parsing with [ParseOptions::recover_errors], the expected ASTs, and byte offsets of
+/// the errors.
+#[rustfmt::skip]
+pub(crate) fn recovery_tests() -> Vec<(String, Json, Vec<usize>)> {
+    use Json::*;
+
+    vec![
+        (s(""), Error, vec![0]),
+        (s("[1 2]"), Array(vec![Int(1), Error]), vec![3]),
+        (s("[1,,2]"), Array(vec![Int(1), Error, Int(2)]), vec![3]),
+        (s("[1, }, 2]"), Array(vec![Int(1), Error, Int(2)]), vec![4]),
+        (s("[1, 2"), Array(vec![Int(1), Int(2), Error]), vec![5]),
+        (s("[\n  1 2\n  3\n]"), Array(vec![Int(1), Error, Int(3)]), vec![6]),
+        (
+            s("{\n  \"a\": 1 2\n  \"b\": 3\n}"),
+            Object(vec![(s("a"), Int(1)), (s("b"), Int(3))]),
+            vec![11]
+        ),
+        (s("[1] x"), Array(vec![Int(1)]), vec![4]),
+        (
+            s(r#"{"a": tru, "b": 2}"#),
+            Object(vec![(s("a"), Error), (s("b"), Int(2))]),
+            vec![6]
+        ),
+        (
+            s(r#"{"a" 1, "b": 2}"#),
+            Object(vec![(s("a"), Error), (s("b"), Int(2))]),
+            vec![5]
+        ),
+        (s(r#"{1: 2, "b": 3}"#), Object(vec![(s("b"), Int(3))]), vec![1]),
+        (s(r#"{"a": 1"#), Object(vec![(s("a"), Int(1))]), vec![7]),
+        (
+            s(r#"{"a": [1, {"b": }]"#),
+            Object(vec![(s("a"), Array(vec![Int(1), Object(vec![(s("b"), Error)])]))]),
+            vec![16, 18]
+        ),
+        (s(r#"["a\q", 1]"#), Array(vec![Error, Int(1)]), vec![4]),
+    ]
+}
+
+/// Inputs, parse options, and the expected input slices of the values and object keys in the
+/// [SpannedJson], in pre-order. See [span_texts].
+#[rustfmt::skip]
+pub(crate) fn spanned_tests() -> Vec<(String, ParseOptions, Vec<&'static str>)> {
+    let default = ParseOptions::default();
+    vec![
+        (s(" 123 "), default, vec!["123"]),
+        (s(r#""a\"b""#), default, vec![r#""a\"b""#]),
+        (s("[false, {}, [], -0]"), default, vec!["[false, {}, [], -0]", "false", "{}", "[]", "-0"]),
+        (
+            s(r#"{"a": [1, -2.5e3, true], "b\n": null, "c": "x"}"#),
+            default,
+            vec![
+                r#"{"a": [1, -2.5e3, true], "b\n": null, "c": "x"}"#,
+                r#""a""#, "[1, -2.5e3, true]", "1", "-2.5e3", "true",
+                r#""b\n""#, "null",
+                r#""c""#, r#""x""#,
+            ]
+        ),
+        (s("[1 /* a */, // b\n 2]"), default, vec!["[1 /* a */, // b\n 2]", "1", "2"]),
+        (
+            s("{a: 'b', c: 0x1F, d: [Infinity,],}"),
+            json5_options(),
+            vec![
+                "{a: 'b', c: 0x1F, d: [Infinity,],}",
+                "a", "'b'", "c", "0x1F", "d", "[Infinity,]", "Infinity",
             ]
         ),
     ]
 }
 
+/// Input slices of the values and object keys in `json`, in pre-order.
+pub(crate) fn span_texts<'a>(input: &'a str, json: &SpannedJson) -> Vec<&'a str> {
+    let mut texts = vec![&input[json.span.start..json.span.end]];
+    match &json.kind {
+        SpannedJsonKind::Array(elements) => {
+            for element in elements {
+                texts.extend(span_texts(input, element));
+            }
+        }
+        SpannedJsonKind::Object(members) => {
+            for (key, value) in members {
+                texts.push(&input[key.span.start..key.span.end]);
+                texts.extend(span_texts(input, value));
+            }
+        }
+        _ => {}
+    }
+    texts
+}
+
+/// An [EventHandler] that collects the events and the error, and returns `control(index, kind)`
+/// from the callbacks, with the index of the event.
+pub(crate) struct ControlListener<F: FnMut(usize, &ParseEventKind) -> ControlFlow> {
+    pub(crate) control: F,
+    pub(crate) events: Vec<ParseEventKind>,
+    pub(crate) error: Option<ParseError>,
+}
+
+impl<F: FnMut(usize, &ParseEventKind) -> ControlFlow> ControlListener<F> {
+    pub(crate) fn new(control: F) -> ControlListener<F> {
+        ControlListener {
+            control,
+            events: vec![],
+            error: None,
+        }
+    }
+}
+
+impl<F: FnMut(usize, &ParseEventKind) -> ControlFlow> EventHandler for ControlListener<F> {
+    fn on_event(&mut self, event: ParseEvent) -> ControlFlow {
+        let control = (self.control)(self.events.len(), &event.kind);
+        self.events.push(event.kind);
+        control
+    }
+
+    fn on_error(&mut self, error: ParseError) {
+        self.error = Some(error);
+    }
+}
+
+impl<F: FnMut(usize, &ParseEventKind) -> ControlFlow> FinishListener for ControlListener<F> {
+    type Output = Vec<ParseEventKind>;
+    type Error = ParseError;
+
+    fn finish(self) -> Result<Vec<ParseEventKind>, ParseError> {
+        match self.error {
+            Some(error) => Err(error),
+            None => Ok(self.events),
+        }
+    }
+}
+
+/// Inputs for testing [ControlFlow]s, with the options to parse them. Each has a callback for
+/// each kind of event, and ends with an error.
+pub(crate) fn control_flow_inputs() -> Vec<(String, ParseOptions)> {
+    let lossless_options = ParseOptions {
+        lossless_numbers: true,
+        ..ParseOptions::default()
+    };
+    vec![
+        (
+            s("/* a */ {\"a\": [1, -2, 3.5, \"s\", true, null, {}], // b\n\"c\": false} x"),
+            comment_options(),
+        ),
+        (s("{a: [1.5, 'x'], 'b': []}]"), json5_options()),
+        (s("[1.5, 2] 3"), lossless_options),
+    ]
+}
+
+/// Inputs for testing [ControlFlow::SkipValue], with the events when skipping every array and
+/// object except the top-level one.
+#[rustfmt::skip]
+pub(crate) fn skip_value_tests() -> Vec<(String, ParseOptions, Vec<ParseEventKind>)> {
+    use ParseEventKind::*;
+    vec![
+        (
+            s(r#"[1, {"a": [2, "]"], "b": {}}, [3, /* ] */ [4]], true]"#),
+            comment_options(),
+            vec![StartArray, Int(1), StartObject, StartArray, Bool(true), EndArray],
+        ),
+        (
+            s("{'a': {b: '}', \"c\\\"\": [], }, 'd': [null,], }"),
+            json5_options(),
+            vec![
+                StartObject,
+                Str { size_in_bytes: 1, has_escapes: false },
+                StartObject,
+                Str { size_in_bytes: 1, has_escapes: false },
+                StartArray,
+                EndObject,
+            ],
+        ),
+    ]
+}
+
+/// Inputs with errors in values skipped with [ControlFlow::SkipValue], with the error byte
+/// offsets.
+pub(crate) fn skip_value_error_tests() -> Vec<(String, usize)> {
+    vec![
+        (s("[[1, 2}]"), 6),
+        (s("[{\"a\": \"]}"), 10),
+        (s("[[1"), 3),
+        (s("[[\"\\x\"]]"), 4),
+        (s("[[1 /* ]]"), 4),
+        (s("[[1]] 2"), 6),
+    ]
+}
+
 fn add_comment_before(input: &str) -> String {
     format!("// hi\n{}", input)
 }
@@ -176,7 +701,10 @@ fn add_comment_after(input: &str) -> String {
     format!("{}\n// hi\n", input)
 }
 
-const COMMENT: ParseEventKind = ParseEventKind::Comment { size_in_bytes: 6 };
+const COMMENT: ParseEventKind = ParseEventKind::Comment {
+    size_in_bytes: 6,
+    kind: CommentKind::Line,
+};
 
 fn s(s: &str) -> String {
     s.to_string()
@@ -1,133 +1,65 @@
-use crate::{parse_events, EventListener, ParseError, ParseEvent, ParseEventKind};
+use crate::event_listener::EventHandler;
+use crate::{
+    ControlFlow, Extractor, ExtractorListener, FieldType, FinishListener, Json, ParseError,
+    ParseEvent,
+};
+
+use std::sync::OnceLock;
 
 /// Parse the "timestamp" field at the top-level map of the JSON.
 pub fn parse_timestamp(log_line: &str) -> Result<Option<u64>, ParseError> {
-    let mut container_depth: u32 = 0;
-    let mut expect_timestamp = false;
-
-    for event in parse_events(log_line) {
-        let ParseEvent { kind, byte_offset } = match event {
-            Ok(event) => event,
-            Err(err) => return Err(err),
-        };
-
-        let expect_timestamp_ = expect_timestamp;
-        expect_timestamp = false;
-
-        match kind {
-            ParseEventKind::StartObject => {
-                container_depth += 1;
-            }
-
-            ParseEventKind::EndObject => {
-                container_depth -= 1;
-            }
-
-            ParseEventKind::StartArray => {
-                if container_depth == 0 {
-                    // Array at the top level, the line does not contain the field.
-                    return Ok(None);
-                }
-                container_depth += 1;
-            }
-
-            ParseEventKind::EndArray => {
-                container_depth -= 1;
-            }
-
-            ParseEventKind::Str { size_in_bytes } => {
-                if container_depth != 1 {
-                    continue;
-                }
-                let str = &log_line[byte_offset..byte_offset + size_in_bytes];
-                expect_timestamp = str == "timestamp";
-            }
+    timestamp_extractor().extract(log_line).map(timestamp)
+}
 
-            ParseEventKind::Int(i) => {
-                if expect_timestamp_ {
-                    return Ok(Some(i));
-                }
-            }
+/// An [Extractor] for the "timestamp" field.
+fn timestamp_extractor() -> &'static Extractor {
+    static EXTRACTOR: OnceLock<Extractor> = OnceLock::new();
+    EXTRACTOR.get_or_init(|| {
+        let mut extractor = Extractor::new();
+        extractor.add_field("/timestamp", FieldType::Int).unwrap();
+        extractor
+    })
+}
 
-            ParseEventKind::Bool(_) | ParseEventKind::Null | ParseEventKind::Comment { .. } => {}
-        }
+/// The timestamp in the values extracted by [timestamp_extractor].
+fn timestamp(values: Vec<Option<Json>>) -> Option<u64> {
+    match values[0] {
+        Some(Json::Int(i)) => Some(i),
+        _ => None,
     }
-
-    Ok(None)
 }
 
-/// A timestamp parser similar to [parse_timestamp], but implements [EventListener].
+/// A timestamp parser similar to [parse_timestamp], but implements
+/// [EventListener][crate::EventListener].
 pub struct TimestampParserListener<'a> {
-    container_depth: u32,
-
-    /// Whether the next `Int` event is the timestamp. Set after seeing a `Str("timestamp")` at
-    /// container depth 1.
-    expect_timestamp: bool,
-
-    input: &'a str,
-
-    /// The parsed value.
-    timestamp_value: Option<u64>,
-
-    error: Option<ParseError>,
+    listener: ExtractorListener<'a>,
 }
 
 impl<'a> TimestampParserListener<'a> {
     pub fn new(input: &'a str) -> Self {
         Self {
-            container_depth: 0,
-            expect_timestamp: false,
-            input,
-            timestamp_value: None,
-            error: None,
+            listener: timestamp_extractor().listener(input),
         }
     }
 }
 
-impl<'a> EventListener for TimestampParserListener<'a> {
-    fn handle_start_object(&mut self, _byte_offset: usize) -> bool {
-        self.container_depth += 1;
-        true
-    }
-
-    fn handle_end_object(&mut self, _byte_offset: usize) -> bool {
-        self.container_depth -= 1;
-        true
-    }
-
-    fn handle_start_array(&mut self, _byte_offset: usize) -> bool {
-        if self.container_depth == 0 {
-            // Array at the top level, the line does not contain the field.
-            return false;
-        }
-        self.container_depth += 1;
-        true
+impl<'a> EventHandler for TimestampParserListener<'a> {
+    fn on_event(&mut self, event: ParseEvent) -> ControlFlow {
+        self.listener.on_event(event)
     }
 
-    fn handle_end_array(&mut self, _byte_offset: usize) -> bool {
-        self.container_depth -= 1;
-        true
-    }
-
-    fn handle_str(&mut self, byte_offset: usize, size_in_bytes: usize) -> bool {
-        if self.container_depth == 1 {
-            let str = &self.input[byte_offset..byte_offset + size_in_bytes];
-            self.expect_timestamp = str == "timestamp";
-        }
-        true
+    fn on_error(&mut self, error: ParseError) {
+        self.listener.on_error(error)
     }
+}
 
-    fn handle_int(&mut self, _byte_offset: usize, i: u64) -> bool {
-        if self.expect_timestamp {
-            self.timestamp_value = Some(i);
-            false
-        } else {
-            true
-        }
-    }
+impl<'a> FinishListener for TimestampParserListener<'a> {
+    type Output = Option<u64>;
+    type Error = ParseError;
 
-    fn handle_error(&mut self, error: ParseError) {
-        self.error = Some(error);
+    /// Returns the timestamp as in [parse_timestamp].
+    fn finish(self) -> Result<Option<u64>, ParseError> {
+        self.listener.finish().map(timestamp)
     }
 }
 
@@ -142,15 +74,15 @@ fn parse_timestamp_test() {
         parse_timestamp(r#"{"x":["timestamp",999],"timestamp":123}"#),
         Ok(Some(123))
     );
+    assert_eq!(parse_timestamp(r#"{"time\u0073tamp":123}"#), Ok(Some(123)));
 }
 
 #[test]
 fn parse_timestamp_listener_test() {
     fn parse(input: &str) -> u64 {
-        let mut listener = TimestampParserListener::new(input);
-        crate::parse_events_push(input, &mut listener);
-        assert_eq!(listener.error, None);
-        listener.timestamp_value.unwrap()
+        crate::parse_events_push(input, TimestampParserListener::new(input))
+            .unwrap()
+            .unwrap()
     }
 
     assert_eq!(parse(r#"{"timestamp":123}"#), 123);
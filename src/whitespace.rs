@@ -0,0 +1,43 @@
+use crate::{Dialect, ParseError, ParseErrorKind, ParseOptions};
+
+/// Whether `char` is a whitespace character in any dialect: a character accepted by
+/// [char::is_whitespace], or the byte order mark U+FEFF.
+pub(crate) fn is_whitespace(char: char) -> bool {
+    char.is_whitespace() || char == '\u{FEFF}'
+}
+
+/// Checks the whitespace character `char` at `byte_offset`. `char` should be a character accepted
+/// by [is_whitespace].
+///
+/// Space, tab, line feed and carriage return are accepted in all dialects. Form feed is accepted
+/// except in [Dialect::Strict]. [Dialect::Json5] also accepts vertical tab, the byte order mark,
+/// the line and paragraph separators, and the Unicode space separators (category Zs). Other
+/// whitespace characters are errors.
+pub(crate) fn check_whitespace(
+    byte_offset: usize,
+    char: char,
+    options: &ParseOptions,
+) -> Result<(), ParseError> {
+    match char {
+        ' ' | '\t' | '\n' | '\r' => Ok(()),
+        '\x0C' if options.dialect != Dialect::Strict => Ok(()),
+        '\x0B'
+        | '\u{FEFF}'
+        | '\u{2028}'
+        | '\u{2029}'
+        | '\u{A0}'
+        | '\u{1680}'
+        | '\u{2000}'..='\u{200A}'
+        | '\u{202F}'
+        | '\u{205F}'
+        | '\u{3000}'
+            if options.dialect == Dialect::Json5 =>
+        {
+            Ok(())
+        }
+        _ => Err(ParseError::new(
+            byte_offset,
+            ParseErrorKind::InvalidWhitespace,
+        )),
+    }
+}